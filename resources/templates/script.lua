@@ -0,0 +1,19 @@
+-- Moves the entity to the right at `speed` units per second.
+--
+-- Each callback is optional and gets the entity's handle. Parameters are declared with their
+-- defaults in the `parameters` table, which holds the entity's values whenever a callback is called.
+
+parameters = {
+    speed = 0.0,
+}
+
+function on_start(id)
+end
+
+function on_update(id, dt)
+    local x = alpha.get_position_x() + parameters.speed * dt
+    alpha.set_position(x, alpha.get_position_y())
+end
+
+function on_destroy(id)
+end
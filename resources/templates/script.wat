@@ -0,0 +1,22 @@
+;; Moves the entity to the right at `speed` units per second.
+;;
+;; Each callback is optional and gets the entity's handle. Parameters are declared below, one per
+;; line with a name, a type and a default value, and read back by the order they're declared in.
+(module
+  (import "alpha" "get_position_x" (func $get_position_x (result f32)))
+  (import "alpha" "get_position_y" (func $get_position_y (result f32)))
+  (import "alpha" "set_position" (func $set_position (param f32 f32)))
+  (import "alpha" "get_parameter_float" (func $get_parameter_float (param i32) (result f32)))
+
+  (@custom "alpha_parameters" "speed float 0\n")
+
+  (func (export "on_start") (param $id i64))
+
+  (func (export "on_update") (param $id i64) (param $dt f32)
+    (call $set_position
+      (f32.add
+        (call $get_position_x)
+        (f32.mul (call $get_parameter_float (i32.const 0)) (local.get $dt)))
+      (call $get_position_y)))
+
+  (func (export "on_destroy") (param $id i64)))
@@ -0,0 +1,135 @@
+//! Per-frame heap allocation tracking, enabled with the `alloc-tracker` feature.
+//!
+//! Allocations are attributed to whichever [`Subsystem`] scope is active on the allocating thread.
+//! Without the feature the scopes still compile but nothing is counted.
+
+#[cfg(feature = "alloc-tracker")]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "alloc-tracker")]
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    Engine,
+    Game,
+    Editor,
+    Renderer,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 4] = [
+        Subsystem::Engine,
+        Subsystem::Game,
+        Subsystem::Editor,
+        Subsystem::Renderer,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subsystem::Engine => "Engine",
+            Subsystem::Game => "Game",
+            Subsystem::Editor => "Editor",
+            Subsystem::Renderer => "Renderer",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Allocations {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+static COUNTS: [AtomicUsize; 4] = counters();
+static BYTES: [AtomicUsize; 4] = counters();
+static LAST_FRAME_COUNTS: [AtomicUsize; 4] = counters();
+static LAST_FRAME_BYTES: [AtomicUsize; 4] = counters();
+
+const fn counters() -> [AtomicUsize; 4] {
+    [
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+    ]
+}
+
+thread_local! {
+    static CURRENT: Cell<usize> = Cell::new(Subsystem::Engine as usize);
+}
+
+/// Attributes allocations on this thread to `subsystem` until the returned guard is dropped.
+pub fn scope(subsystem: Subsystem) -> Scope {
+    let previous = CURRENT.with(|current| current.replace(subsystem as usize));
+    Scope { previous }
+}
+
+pub struct Scope {
+    previous: usize,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.previous));
+    }
+}
+
+/// Publishes the allocations counted since the previous call and starts counting a new frame.
+pub fn end_frame() {
+    for subsystem in Subsystem::ALL {
+        let i = subsystem as usize;
+        LAST_FRAME_COUNTS[i].store(COUNTS[i].swap(0, Ordering::Relaxed), Ordering::Relaxed);
+        LAST_FRAME_BYTES[i].store(BYTES[i].swap(0, Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
+
+pub fn last_frame(subsystem: Subsystem) -> Allocations {
+    let i = subsystem as usize;
+    Allocations {
+        count: LAST_FRAME_COUNTS[i].load(Ordering::Relaxed),
+        bytes: LAST_FRAME_BYTES[i].load(Ordering::Relaxed),
+    }
+}
+
+pub fn enabled() -> bool {
+    cfg!(feature = "alloc-tracker")
+}
+
+#[cfg(feature = "alloc-tracker")]
+fn record(size: usize) {
+    // The thread local may already have been destroyed while a thread is shutting down.
+    let i = CURRENT
+        .try_with(|current| current.get())
+        .unwrap_or(Subsystem::Engine as usize);
+    COUNTS[i].fetch_add(1, Ordering::Relaxed);
+    BYTES[i].fetch_add(size, Ordering::Relaxed);
+}
+
+#[cfg(feature = "alloc-tracker")]
+struct TrackingAllocator;
+
+#[cfg(feature = "alloc-tracker")]
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
@@ -0,0 +1,534 @@
+use crate::components::{Shape, Sprite, Transform};
+use glam::{Vec2, Vec4};
+use hecs::{Entity, World};
+
+/// Animates an entity's properties along keyframe tracks, each of which plays independently.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animation {
+    pub tracks: Vec<Track>,
+    /// A preset to play, which is turned into tracks from the entity's current values the next
+    /// time animations are updated.
+    pub preset: Option<AnimationPreset>,
+}
+
+/// The keyframes for one property, in time order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub property: AnimatedProperty,
+    pub keyframes: Vec<Keyframe>,
+    pub looping: bool,
+    /// How far the track has played, in seconds.
+    pub elapsed: f32,
+}
+
+impl Track {
+    pub fn new(property: AnimatedProperty) -> Self {
+        Self {
+            property,
+            keyframes: Vec::new(),
+            looping: false,
+            elapsed: 0.0,
+        }
+    }
+
+    /// The time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// The value at `time` seconds into the track, or `None` if it has no keyframes.
+    pub fn sample(&self, time: f32) -> Option<Vec4> {
+        let first = self.keyframes.first()?;
+        let time = match self.duration() {
+            duration if self.looping && duration > 0.0 => time.rem_euclid(duration),
+            _ => time,
+        };
+
+        let next = match self.keyframes.iter().position(|k| k.time >= time) {
+            Some(0) => return Some(first.value),
+            Some(next) => next,
+            None => return self.keyframes.last().map(|keyframe| keyframe.value),
+        };
+        let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let t = (time - from.time) / (to.time - from.time);
+
+        Some(from.value.lerp(to.value, to.easing.apply(t)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    /// When the keyframe is reached, in seconds from the start of the track.
+    pub time: f32,
+    /// The property's value, using as many components as the property has.
+    pub value: Vec4,
+    /// How the value eases into this keyframe from the one before.
+    pub easing: Easing,
+}
+
+/// The properties which can be animated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimatedProperty {
+    Position,
+    /// In degrees.
+    Rotation,
+    Scale,
+    /// The colour of the entity's [`Shape`] and the tint of its [`Sprite`].
+    Color,
+}
+
+impl AnimatedProperty {
+    pub const ALL: [AnimatedProperty; 4] = [
+        AnimatedProperty::Position,
+        AnimatedProperty::Rotation,
+        AnimatedProperty::Scale,
+        AnimatedProperty::Color,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AnimatedProperty::Position => "position",
+            AnimatedProperty::Rotation => "rotation",
+            AnimatedProperty::Scale => "scale",
+            AnimatedProperty::Color => "color",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|property| property.name() == name)
+    }
+
+    /// The entity's current value for the property, if it has the component it belongs to.
+    pub fn get(self, world: &World, entity: Entity) -> Option<Vec4> {
+        match self {
+            AnimatedProperty::Position => {
+                let position = world.get::<Transform>(entity).ok()?.position;
+                Some(position.extend(0.0).extend(0.0))
+            }
+            AnimatedProperty::Rotation => {
+                let rotation = world.get::<Transform>(entity).ok()?.rotation;
+                Some(Vec4::new(rotation, 0.0, 0.0, 0.0))
+            }
+            AnimatedProperty::Scale => {
+                let scale = world.get::<Transform>(entity).ok()?.scale;
+                Some(scale.extend(0.0).extend(0.0))
+            }
+            AnimatedProperty::Color => match world.get::<Shape>(entity) {
+                Ok(shape) => Some(shape.color),
+                Err(_) => world.get::<Sprite>(entity).ok().map(|sprite| sprite.tint),
+            },
+        }
+    }
+
+    pub fn set(self, world: &World, entity: Entity, value: Vec4) {
+        match self {
+            AnimatedProperty::Position => {
+                if let Ok(mut transform) = world.get_mut::<Transform>(entity) {
+                    transform.position = Vec2::new(value.x, value.y);
+                }
+            }
+            AnimatedProperty::Rotation => {
+                if let Ok(mut transform) = world.get_mut::<Transform>(entity) {
+                    transform.rotation = value.x;
+                }
+            }
+            AnimatedProperty::Scale => {
+                if let Ok(mut transform) = world.get_mut::<Transform>(entity) {
+                    transform.scale = Vec2::new(value.x, value.y);
+                }
+            }
+            AnimatedProperty::Color => {
+                if let Ok(mut shape) = world.get_mut::<Shape>(entity) {
+                    shape.color = value;
+                }
+                if let Ok(mut sprite) = world.get_mut::<Sprite>(entity) {
+                    sprite.tint = value;
+                }
+            }
+        }
+    }
+}
+
+/// How a value moves between keyframes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Holds the previous value, then jumps to the next.
+    Step,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 5] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::Step,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease_in",
+            Easing::EaseOut => "ease_out",
+            Easing::EaseInOut => "ease_in_out",
+            Easing::Step => "step",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|easing| easing.name() == name)
+    }
+
+    /// Maps how far between two keyframes the time is, from 0 to 1, to how far the value is.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Step if t < 1.0 => 0.0,
+            Easing::Step => 1.0,
+        }
+    }
+}
+
+/// Built-in animations for common effects, which are played as tracks from the entity's current
+/// values, so they work wherever the entity is and whatever its size and colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationPreset {
+    /// Grows a little and shrinks back.
+    Pulse,
+    /// Jitters from side to side and settles back where it was.
+    Shake,
+    /// Fades in from fully transparent.
+    FadeIn,
+    /// Grows from nothing, overshooting a little before settling.
+    Pop,
+}
+
+impl AnimationPreset {
+    pub const ALL: [AnimationPreset; 4] = [
+        AnimationPreset::Pulse,
+        AnimationPreset::Shake,
+        AnimationPreset::FadeIn,
+        AnimationPreset::Pop,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AnimationPreset::Pulse => "pulse",
+            AnimationPreset::Shake => "shake",
+            AnimationPreset::FadeIn => "fade_in",
+            AnimationPreset::Pop => "pop",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    /// The property the preset animates.
+    pub fn property(self) -> AnimatedProperty {
+        match self {
+            AnimationPreset::Pulse | AnimationPreset::Pop => AnimatedProperty::Scale,
+            AnimationPreset::Shake => AnimatedProperty::Position,
+            AnimationPreset::FadeIn => AnimatedProperty::Color,
+        }
+    }
+
+    /// The preset's track, starting and ending at `base`, the property's value.
+    pub fn track(self, base: Vec4) -> Track {
+        let key = |time, value, easing| Keyframe {
+            time,
+            value,
+            easing,
+        };
+        let scaled = |factor: f32| Vec4::new(base.x * factor, base.y * factor, base.z, base.w);
+        let offset = |x: f32| base + Vec4::new(x, 0.0, 0.0, 0.0);
+
+        let mut track = Track::new(self.property());
+        track.keyframes = match self {
+            AnimationPreset::Pulse => vec![
+                key(0.0, base, Easing::Linear),
+                key(0.15, scaled(1.15), Easing::EaseOut),
+                key(0.3, base, Easing::EaseIn),
+            ],
+            AnimationPreset::Shake => vec![
+                key(0.0, base, Easing::Linear),
+                key(0.05, offset(-6.0), Easing::EaseOut),
+                key(0.1, offset(6.0), Easing::EaseInOut),
+                key(0.15, offset(-4.0), Easing::EaseInOut),
+                key(0.2, offset(4.0), Easing::EaseInOut),
+                key(0.25, offset(-2.0), Easing::EaseInOut),
+                key(0.3, base, Easing::EaseIn),
+            ],
+            AnimationPreset::FadeIn => vec![
+                key(0.0, Vec4::new(base.x, base.y, base.z, 0.0), Easing::Linear),
+                key(0.5, base, Easing::EaseOut),
+            ],
+            AnimationPreset::Pop => vec![
+                key(0.0, scaled(0.0), Easing::Linear),
+                key(0.15, scaled(1.2), Easing::EaseOut),
+                key(0.25, base, Easing::EaseInOut),
+            ],
+        };
+        track
+    }
+}
+
+/// Plays the preset on the entity from its current values, replacing any track already animating
+/// the same property.
+///
+/// Scripts can do the same by calling `play_preset`.
+pub fn play_preset(world: &mut World, entity: Entity, preset: AnimationPreset) {
+    let property = preset.property();
+    // Starting a preset again part way through, e.g. pulsing on every hit, should start from where
+    // the one playing will settle rather than wherever it has got to.
+    let playing = world.get::<Animation>(entity).ok().and_then(|animation| {
+        let track = animation.tracks.iter().find(|t| t.property == property)?;
+        let playing = track.looping || track.elapsed <= track.duration();
+        track.keyframes.last().filter(|_| playing).map(|k| k.value)
+    });
+    let base = match playing.or_else(|| property.get(world, entity)) {
+        Some(base) => base,
+        None => return,
+    };
+
+    add_track(world, entity, preset.track(base));
+}
+
+/// Animates the entity's property from its current value to `to` over `duration` seconds,
+/// replacing any track already animating that property.
+///
+/// Scripts can do the same by calling `tween`.
+pub fn tween(
+    world: &mut World,
+    entity: Entity,
+    property: AnimatedProperty,
+    to: Vec4,
+    duration: f32,
+    easing: Easing,
+) {
+    let from = match property.get(world, entity) {
+        Some(from) => from,
+        None => return,
+    };
+
+    let mut track = Track::new(property);
+    track.keyframes = vec![
+        Keyframe {
+            time: 0.0,
+            value: from,
+            easing: Easing::Linear,
+        },
+        Keyframe {
+            time: duration.max(0.0),
+            value: to,
+            easing,
+        },
+    ];
+
+    add_track(world, entity, track);
+}
+
+/// Adds the track to the entity's animation, replacing any track for the same property.
+fn add_track(world: &mut World, entity: Entity, track: Track) {
+    match world.get_mut::<Animation>(entity) {
+        Ok(mut animation) => {
+            animation.tracks.retain(|t| t.property != track.property);
+            animation.tracks.push(track);
+        }
+        Err(_) => {
+            let animation = Animation {
+                tracks: vec![track],
+                preset: None,
+            };
+            world.insert_one(entity, animation).ok();
+        }
+    }
+}
+
+/// Flips through the frames of an atlas on the entity's [`Sprite`], which is split into a grid of
+/// equally sized frames numbered left to right, top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimatedSprite {
+    pub columns: u32,
+    pub rows: u32,
+    pub clips: Vec<SpriteClip>,
+    /// The index of the clip being shown.
+    pub clip: usize,
+    pub playing: bool,
+    /// How far the clip has played, in seconds.
+    pub elapsed: f32,
+    /// Plays the clip while the game isn't running, so that it can be previewed in the editor.
+    pub preview: bool,
+}
+
+/// A run of consecutive frames in the atlas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteClip {
+    /// A single word, so that it can be written to scene files.
+    pub name: String,
+    pub first_frame: u32,
+    pub frame_count: u32,
+    pub frames_per_second: f32,
+    pub looping: bool,
+}
+
+impl Default for AnimatedSprite {
+    fn default() -> Self {
+        Self {
+            columns: 1,
+            rows: 1,
+            clips: Vec::new(),
+            clip: 0,
+            playing: true,
+            elapsed: 0.0,
+            preview: false,
+        }
+    }
+}
+
+impl Default for SpriteClip {
+    fn default() -> Self {
+        Self {
+            name: "idle".to_string(),
+            first_frame: 0,
+            frame_count: 1,
+            frames_per_second: 10.0,
+            looping: true,
+        }
+    }
+}
+
+impl AnimatedSprite {
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Stops on the current frame.
+    pub fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    /// Switches to the clip with the given name and plays it from the start, returning `false` if
+    /// there isn't one. Does nothing if it's already the current clip.
+    pub fn set_clip(&mut self, name: &str) -> bool {
+        match self.clips.iter().position(|clip| clip.name == name) {
+            Some(clip) => {
+                self.set_clip_index(clip);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn set_clip_index(&mut self, clip: usize) {
+        if clip != self.clip && clip < self.clips.len() {
+            self.clip = clip;
+            self.elapsed = 0.0;
+            self.playing = true;
+        }
+    }
+
+    pub fn current_clip(&self) -> Option<&SpriteClip> {
+        self.clips.get(self.clip)
+    }
+
+    /// The frame of the atlas being shown, or `None` if there's no current clip.
+    pub fn frame(&self) -> Option<u32> {
+        let clip = self.current_clip()?;
+        let count = clip.frame_count.max(1);
+        let played = (self.elapsed * clip.frames_per_second).max(0.0) as u32;
+        let offset = if clip.looping {
+            played % count
+        } else {
+            played.min(count - 1)
+        };
+        Some(clip.first_frame + offset)
+    }
+
+    /// The corners of a frame, in texture coordinates.
+    pub fn frame_uvs(&self, frame: u32) -> (Vec2, Vec2) {
+        let grid = Vec2::new(self.columns.max(1) as f32, self.rows.max(1) as f32);
+        let cell = Vec2::new(
+            (frame % self.columns.max(1)) as f32,
+            (frame / self.columns.max(1)) as f32,
+        );
+        (cell / grid, (cell + Vec2::ONE) / grid)
+    }
+
+    /// Whether a clip which doesn't loop has shown its last frame for its full duration.
+    fn finished(&self) -> bool {
+        match self.current_clip() {
+            Some(clip) if !clip.looping && clip.frames_per_second > 0.0 => {
+                self.elapsed * clip.frames_per_second >= clip.frame_count as f32
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Plays each entity's animation tracks on by `dt` seconds, after starting any presets they've been
+/// given. Tracks which have finished leave the property alone, so that it can be changed by other
+/// systems.
+pub(crate) fn system_animation(world: &mut World, dt: f32) {
+    let presets: Vec<(Entity, AnimationPreset)> = world
+        .query_mut::<&mut Animation>()
+        .into_iter()
+        .filter_map(|(entity, animation)| Some((entity, animation.preset.take()?)))
+        .collect();
+    for (entity, preset) in presets {
+        play_preset(world, entity, preset);
+    }
+
+    let mut values = Vec::new();
+    for (entity, animation) in world.query_mut::<&mut Animation>() {
+        for track in &mut animation.tracks {
+            let finished = !track.looping && track.elapsed > track.duration();
+            track.elapsed += dt;
+            if finished {
+                continue;
+            }
+
+            if let Some(value) = track.sample(track.elapsed) {
+                values.push((entity, track.property, value));
+            }
+        }
+    }
+
+    for (entity, property, value) in values {
+        property.set(world, entity, value);
+    }
+}
+
+/// Advances each playing [`AnimatedSprite`] by `dt` seconds and shows its current frame. While the
+/// game is paused, only sprites being previewed are advanced.
+pub(crate) fn system_sprite_animation(world: &mut World, dt: f32, paused: bool) {
+    for (_, (animated, sprite)) in world.query_mut::<(&mut AnimatedSprite, &mut Sprite)>() {
+        let advancing = if paused {
+            animated.preview
+        } else {
+            animated.playing
+        };
+        if advancing {
+            animated.elapsed += dt;
+            if animated.finished() && !paused {
+                animated.playing = false;
+            }
+        }
+
+        if let Some(frame) = animated.frame() {
+            let (uv_min, uv_max) = animated.frame_uvs(frame);
+            sprite.uv_min = uv_min;
+            sprite.uv_max = uv_max;
+        }
+    }
+}
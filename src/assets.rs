@@ -0,0 +1,529 @@
+use crate::audio::AudioSource;
+use crate::components::{Script, Sprite};
+use crate::error::Error;
+use crate::files;
+use crate::sub_scene::SubScene;
+use crate::tilemap::Tilemap;
+use hecs::World;
+use image::RgbaImage;
+use log::{info, warn};
+#[cfg(not(target_arch = "wasm32"))]
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+/// Something which can be loaded from a file by [`Assets`].
+pub trait Asset: Sized + Send + Sync + 'static {
+    /// Reads the asset. Called off the main thread.
+    fn load(path: &Path) -> Result<Self, Error>;
+}
+
+/// A decoded texture, ready to be uploaded to the GPU.
+pub struct Image {
+    pub image: RgbaImage,
+}
+
+impl Asset for Image {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let bytes = files::read(path).map_err(|e| Error::Asset(path.to_path_buf(), e))?;
+        let image = image::load_from_memory(&bytes)
+            .map_err(|e| Error::Texture(path.to_path_buf(), e))?
+            .to_rgba8();
+        Ok(Self { image })
+    }
+}
+
+/// The text of a scene file.
+pub struct SceneFile {
+    pub source: String,
+}
+
+impl Asset for SceneFile {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let source =
+            files::read_to_string(path).map_err(|e| Error::Asset(path.to_path_buf(), e))?;
+        Ok(Self { source })
+    }
+}
+
+/// The contents of a script, either WebAssembly or Lua.
+pub struct ScriptFile {
+    pub bytes: Vec<u8>,
+}
+
+impl Asset for ScriptFile {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let bytes = files::read(path).map_err(|e| Error::Asset(path.to_path_buf(), e))?;
+        Ok(Self { bytes })
+    }
+}
+
+/// The still encoded contents of a sound file, which are decoded as the sound is played.
+pub struct AudioFile {
+    pub bytes: Arc<[u8]>,
+}
+
+impl Asset for AudioFile {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let bytes = files::read(path).map_err(|e| Error::Asset(path.to_path_buf(), e))?;
+        Ok(Self {
+            bytes: bytes.into(),
+        })
+    }
+}
+
+/// Refers to an asset by its path. Handles to the same path are equal, and can be used as the
+/// path itself.
+///
+/// Once a handle has been given to [`Assets`], it counts towards the asset's references, and the
+/// asset is unloaded once there are none left.
+pub struct Handle<T> {
+    path: Arc<PathBuf>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Arc::new(path.into()),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Handle<T> {
+    fn default() -> Self {
+        Self::new(PathBuf::new())
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.path).finish()
+    }
+}
+
+impl<T> Deref for Handle<T> {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<T> From<PathBuf> for Handle<T> {
+    fn from(path: PathBuf) -> Self {
+        Self::new(path)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadState {
+    /// [`Assets`] hasn't been given a handle to the asset.
+    NotLoaded,
+    Loading,
+    Loaded,
+    /// The asset couldn't be read, for the given reason. It's tried again when it's reloaded.
+    Failed(String),
+}
+
+struct Entry<T> {
+    /// The handle shared by everything referring to the asset, which is counted to know when it's
+    /// no longer used.
+    handle: Arc<PathBuf>,
+    state: LoadState,
+    asset: Option<Arc<T>>,
+}
+
+type ReloadHook<T> = Box<dyn FnMut(&Path, &T)>;
+
+struct Storage<T> {
+    entries: HashMap<PathBuf, Entry<T>>,
+    hooks: Vec<ReloadHook<T>>,
+    /// Assets which have been loaded or reloaded since they were last taken.
+    changed: Vec<PathBuf>,
+}
+
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            hooks: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+/// Sent back from the loading threads, to store what they loaded.
+type Completion = Box<dyn FnOnce(&mut Assets) + Send>;
+
+/// Reloads the assets of one kind which were read from a changed file.
+type Reloader = fn(&mut Assets, &Path);
+
+/// Loads textures, scenes, scripts and sounds on background threads, keeping each one loaded for
+/// as long as there are [`Handle`]s to it.
+pub struct Assets {
+    storages: HashMap<TypeId, Box<dyn Any>>,
+    reloaders: HashMap<TypeId, Reloader>,
+    sender: Sender<Completion>,
+    receiver: Receiver<Completion>,
+    /// How many loads haven't finished yet.
+    loading: usize,
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            storages: HashMap::new(),
+            reloaders: HashMap::new(),
+            sender,
+            receiver,
+            loading: 0,
+        }
+    }
+}
+
+impl Assets {
+    /// Returns a handle to the asset at `path`, starting to load it if it isn't already.
+    pub fn load<T: Asset, P: Into<PathBuf>>(&mut self, path: P) -> Handle<T> {
+        let mut handle = Handle::new(path);
+        self.track(&mut handle);
+        handle
+    }
+
+    /// Makes `handle` count towards its asset's references, starting to load the asset if it
+    /// isn't already, e.g. for handles read from a scene file.
+    pub fn track<T: Asset>(&mut self, handle: &mut Handle<T>) {
+        if handle.path.as_os_str().is_empty() {
+            return;
+        }
+
+        let path = handle.path.as_ref().clone();
+        let entry = self
+            .storage_mut::<T>()
+            .entries
+            .entry(path.clone())
+            .or_insert_with(|| Entry {
+                handle: handle.path.clone(),
+                state: LoadState::NotLoaded,
+                asset: None,
+            });
+        if !Arc::ptr_eq(&entry.handle, &handle.path) {
+            handle.path = entry.handle.clone();
+        }
+        if entry.state == LoadState::NotLoaded {
+            self.start_loading::<T>(path);
+        }
+    }
+
+    /// Reads the asset at `path` again if it's loaded, e.g. because its file has changed.
+    pub fn reload<T: Asset>(&mut self, path: &Path) {
+        if self
+            .storage::<T>()
+            .map_or(false, |s| s.entries.contains_key(path))
+        {
+            self.start_loading::<T>(path.to_path_buf());
+        }
+    }
+
+    /// Reads every loaded asset which came from the file at `path` again, whatever its kind.
+    pub fn reload_changed(&mut self, path: &Path) {
+        let reloaders: Vec<Reloader> = self.reloaders.values().copied().collect();
+        for reload in reloaders {
+            reload(self, path);
+        }
+    }
+
+    fn reload_file<T: Asset>(&mut self, path: &Path) {
+        let changed: Vec<PathBuf> = match self.storage::<T>() {
+            Some(storage) => storage
+                .entries
+                .keys()
+                .filter(|p| same_file(p, path))
+                .cloned()
+                .collect(),
+            None => return,
+        };
+        for path in changed {
+            self.start_loading::<T>(path);
+        }
+    }
+
+    /// Calls `hook` whenever an asset of this kind is reloaded.
+    pub fn on_reload<T: Asset>(&mut self, hook: impl FnMut(&Path, &T) + 'static) {
+        self.storage_mut::<T>().hooks.push(Box::new(hook));
+    }
+
+    pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<Arc<T>> {
+        self.entry(handle)?.asset.clone()
+    }
+
+    pub fn state<T: Asset>(&self, handle: &Handle<T>) -> LoadState {
+        self.entry(handle)
+            .map_or(LoadState::NotLoaded, |entry| entry.state.clone())
+    }
+
+    /// The number of handles to the asset, not counting any which haven't been given to
+    /// [`Assets`].
+    pub fn references<T: Asset>(&self, handle: &Handle<T>) -> usize {
+        self.entry(handle)
+            .map_or(0, |entry| Arc::strong_count(&entry.handle) - 1)
+    }
+
+    /// Whether any assets are still loading, e.g. to show a loading screen.
+    pub fn is_loading(&self) -> bool {
+        self.loading > 0
+    }
+
+    /// The assets of this kind which are loaded, with the paths they were loaded from.
+    pub fn loaded<T: Asset>(&self) -> impl Iterator<Item = (&Path, &Arc<T>)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.entries.iter())
+            .filter_map(|(path, entry)| Some((path.as_path(), entry.asset.as_ref()?)))
+    }
+
+    /// Takes the assets of this kind which have been loaded or reloaded since this was last
+    /// called.
+    pub fn take_changed<T: Asset>(&mut self) -> Vec<(PathBuf, Arc<T>)> {
+        let storage = self.storage_mut::<T>();
+        let changed = std::mem::take(&mut storage.changed);
+        changed
+            .into_iter()
+            .filter_map(|path| {
+                let asset = storage.entries.get(&path)?.asset.clone()?;
+                Some((path, asset))
+            })
+            .collect()
+    }
+
+    /// Stores the assets which have finished loading since the last update.
+    pub(crate) fn update(&mut self) {
+        while let Ok(completion) = self.receiver.try_recv() {
+            self.loading -= 1;
+            completion(self);
+        }
+    }
+
+    /// Unloads the assets of this kind which no longer have any handles to them.
+    pub fn unload_unused<T: Asset>(&mut self) {
+        if let Some(storage) = self.storage_mut_if_exists::<T>() {
+            storage
+                .entries
+                .retain(|_, entry| Arc::strong_count(&entry.handle) > 1);
+        }
+    }
+
+    fn start_loading<T: Asset>(&mut self, path: PathBuf) {
+        if let Some(entry) = self.storage_mut::<T>().entries.get_mut(&path) {
+            if entry.state != LoadState::Loaded {
+                entry.state = LoadState::Loading;
+            }
+        }
+
+        self.loading += 1;
+        let sender = self.sender.clone();
+        let load = move || {
+            // Errors aren't all `Send`, so only their messages are sent back.
+            let loaded = T::load(&path).map_err(|e| e.to_string());
+            let completion: Completion = Box::new(move |assets| assets.finish(path, loaded));
+            sender.send(completion).ok();
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        thread::spawn(load);
+        // The browser has no threads to load on, so it waits for the asset instead.
+        #[cfg(target_arch = "wasm32")]
+        load();
+    }
+
+    fn finish<T: Asset>(&mut self, path: PathBuf, loaded: Result<T, String>) {
+        let storage = self.storage_mut::<T>();
+        let entry = match storage.entries.get_mut(&path) {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        match loaded {
+            Ok(asset) => {
+                let reloaded = entry.asset.is_some();
+                let asset = Arc::new(asset);
+                entry.state = LoadState::Loaded;
+                entry.asset = Some(asset.clone());
+                storage.changed.push(path.clone());
+                if reloaded {
+                    for hook in &mut storage.hooks {
+                        hook(&path, &asset);
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("{}", e);
+                entry.state = LoadState::Failed(e);
+            }
+        }
+    }
+
+    fn entry<T: Asset>(&self, handle: &Handle<T>) -> Option<&Entry<T>> {
+        self.storage::<T>()?.entries.get(handle.path())
+    }
+
+    fn storage<T: Asset>(&self) -> Option<&Storage<T>> {
+        self.storages.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    fn storage_mut_if_exists<T: Asset>(&mut self) -> Option<&mut Storage<T>> {
+        self.storages.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    fn storage_mut<T: Asset>(&mut self) -> &mut Storage<T> {
+        self.reloaders
+            .entry(TypeId::of::<T>())
+            .or_insert(Assets::reload_file::<T>);
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>::default()))
+            .downcast_mut()
+            .expect("assets are stored by their type")
+    }
+}
+
+/// Watches a directory of assets for files which are written to, so they can be reloaded.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct AssetWatcher {
+    // Events stop once the watcher is dropped.
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AssetWatcher {
+    /// Starts watching `directory` and everything in it, or returns `None` if it can't be watched,
+    /// in which case assets just aren't reloaded.
+    pub fn new(directory: &Path) -> Option<Self> {
+        let (sender, events) = mpsc::channel();
+        let watcher =
+            notify::watcher(sender, Duration::from_millis(200)).and_then(|mut watcher| {
+                watcher.watch(directory, RecursiveMode::Recursive)?;
+                Ok(watcher)
+            });
+
+        match watcher {
+            Ok(watcher) => {
+                info!("watching {} for changed assets", directory.display());
+                Some(Self {
+                    _watcher: watcher,
+                    events,
+                })
+            }
+            Err(e) => {
+                warn!(
+                    "unable to watch {} for changed assets: {}",
+                    directory.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// The files which have changed since this was last called.
+    pub fn changed(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        while let Ok(event) = self.events.try_recv() {
+            let path = match event {
+                DebouncedEvent::Create(path) | DebouncedEvent::Write(path) => path,
+                // Editors often save by writing a temporary file and renaming it over the original.
+                DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+            if !changed.contains(&path) {
+                changed.push(path);
+            }
+        }
+        changed
+    }
+}
+
+/// The browser has no directories to watch, so assets are never reloaded there.
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct AssetWatcher;
+
+#[cfg(target_arch = "wasm32")]
+impl AssetWatcher {
+    pub fn new(_directory: &Path) -> Option<Self> {
+        None
+    }
+
+    pub fn changed(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Whether two paths are the same file, e.g. a relative path read from a scene and the absolute
+/// path reported by the watcher.
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Gives the handles in the world's components to `assets`, so that what they refer to is loaded
+/// and kept loaded while they're in use.
+pub(crate) fn system_assets(world: &mut World, assets: &mut Assets) {
+    for (_, sprite) in world.query_mut::<&mut Sprite>() {
+        assets.track(&mut sprite.texture);
+    }
+    for (_, tilemap) in world.query_mut::<&mut Tilemap>() {
+        assets.track(&mut tilemap.tileset);
+    }
+    for (_, script) in world.query_mut::<&mut Script>() {
+        assets.track(&mut script.filepath);
+    }
+    for (_, sub_scene) in world.query_mut::<&mut SubScene>() {
+        assets.track(&mut sub_scene.scene);
+    }
+    for (_, source) in world.query_mut::<&mut AudioSource>() {
+        assets.track(&mut source.sound);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::assets::{same_file, Assets, AudioFile, Handle};
+use crate::collision;
+use crate::components::GlobalTransform;
+use crate::error::Error;
+use crate::files;
+use glam::{Vec2, Vec3};
+use hecs::{Entity, World};
+use log::{info, warn};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Initialises audio output, unless it's disabled, in which case sounds can still be registered
+/// but won't play.
+pub fn init(enabled: bool) -> Audio {
+    let output = if enabled { open_output() } else { None };
+
+    if enabled {
+        info!("audio initialised");
+    } else {
+        info!("audio disabled");
+    }
+
+    Audio {
+        enabled,
+        output,
+        variations: Vec::new(),
+        sources: HashMap::new(),
+        rng: rand::thread_rng(),
+    }
+}
+
+fn open_output() -> Option<(OutputStream, OutputStreamHandle)> {
+    match OutputStream::try_default() {
+        Ok(output) => Some(output),
+        Err(e) => {
+            warn!(
+                "no audio output device available, sounds will not play: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// The slowest a sound is played, since rodio can't play one at no speed at all.
+const MIN_PITCH: f32 = 0.01;
+
+/// The low-pass cutoff of a source nothing blocks, in Hz, which is about as high as people hear.
+const OPEN_CUTOFF: u32 = 20_000;
+
+/// How often a playing source picks up a change to its cutoff.
+const FILTER_PERIOD: Duration = Duration::from_millis(20);
+
+/// Identifies a sound registered with [`Audio`]. Cheap to copy, so it can be handed to scripts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SoundHandle(usize);
+
+impl SoundHandle {
+    /// The number scripts give `play_sound` to play this sound.
+    pub fn index(self) -> u32 {
+        self.0 as u32
+    }
+
+    pub fn from_index(index: u32) -> Self {
+        Self(index as usize)
+    }
+}
+
+pub struct Audio {
+    enabled: bool,
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    variations: Vec<SoundVariation>,
+    /// The [`AudioSource`] each entity is playing.
+    sources: HashMap<Entity, PlayingSource>,
+    rng: ThreadRng,
+}
+
+impl Audio {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Opens or closes the audio output. Registered sounds are kept either way.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled == self.enabled {
+            return;
+        }
+
+        self.enabled = enabled;
+        self.output = if enabled { open_output() } else { None };
+        self.sources.clear();
+    }
+
+    pub fn add_variation(&mut self, variation: SoundVariation) -> SoundHandle {
+        self.variations.push(variation);
+        SoundHandle(self.variations.len() - 1)
+    }
+
+    pub fn variation_mut(&mut self, handle: SoundHandle) -> Option<&mut SoundVariation> {
+        self.variations.get_mut(handle.0)
+    }
+
+    /// Reads the clips which came from the file at `path` again, so the next time they're played
+    /// they sound as the file does now.
+    pub fn reload(&mut self, path: &Path) {
+        for variation in &mut self.variations {
+            for (clip, clip_path) in variation.clips.iter_mut().zip(&variation.paths) {
+                let clip_path = match clip_path {
+                    Some(clip_path) if same_file(clip_path, path) => clip_path,
+                    _ => continue,
+                };
+
+                match files::read(clip_path) {
+                    Ok(bytes) => {
+                        info!("reloaded {}", clip_path.display());
+                        *clip = bytes.into();
+                    }
+                    Err(e) => warn!("unable to reload {}: {}", clip_path.display(), e),
+                }
+            }
+        }
+    }
+
+    pub fn play(&mut self, handle: SoundHandle) -> Result<(), Error> {
+        let (_, stream_handle) = match &self.output {
+            Some(output) => output,
+            None => return Ok(()),
+        };
+
+        let variation = match self.variations.get_mut(handle.0) {
+            Some(variation) => variation,
+            None => {
+                warn!("attempted to play unknown sound {:?}", handle);
+                return Ok(());
+            }
+        };
+
+        if let Some(clip) = variation.next_clip(&mut self.rng) {
+            let pitch = sample(&mut self.rng, &variation.pitch, MIN_PITCH);
+            let volume = sample(&mut self.rng, &variation.volume, 0.0);
+
+            let source = Decoder::new(Cursor::new(clip))?
+                .speed(pitch)
+                .amplify(volume)
+                .convert_samples();
+            stream_handle.play_raw(source)?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts and stops playing the world's [`AudioSource`]s as they're added and removed, and
+    /// muffles those with [`Occlusion`] while a collider lies between them and the `listener`.
+    /// Sources are paused while the game is.
+    pub(crate) fn update_sources(
+        &mut self,
+        world: &World,
+        assets: &Assets,
+        listener: Vec2,
+        paused: bool,
+    ) {
+        let stream_handle = match &self.output {
+            Some((_, stream_handle)) => stream_handle,
+            None => {
+                self.sources.clear();
+                return;
+            }
+        };
+
+        let mut query = world.query::<(&GlobalTransform, &AudioSource)>();
+        let mut present = HashSet::new();
+        for (entity, (transform, source)) in query.iter() {
+            present.insert(entity);
+
+            let started = self
+                .sources
+                .get(&entity)
+                .map_or(false, |playing| playing.sound == source.sound.path());
+            if !started {
+                let file = match assets.get(&source.sound) {
+                    Some(file) => file,
+                    None => {
+                        self.sources.remove(&entity);
+                        continue;
+                    }
+                };
+                let sink = match play_looped(stream_handle, &file.bytes) {
+                    Ok(sink) => Some(sink),
+                    Err(e) => {
+                        warn!("unable to play {}: {}", source.sound.path().display(), e);
+                        None
+                    }
+                };
+                let playing = PlayingSource {
+                    sound: source.sound.path().to_path_buf(),
+                    sink,
+                };
+                self.sources.insert(entity, playing);
+            }
+            let (sink, cutoff) = match &self.sources[&entity].sink {
+                Some(sink) => sink,
+                None => continue,
+            };
+
+            let position = transform
+                .matrix
+                .transform_point3(Vec3::new(0.5, 0.5, 0.0))
+                .truncate();
+            let occlusion = source
+                .occlusion
+                .filter(|_| collision::is_blocked(world, position, listener, entity));
+            let (volume, frequency) = match occlusion {
+                Some(occlusion) => (source.volume * occlusion.volume, occlusion.cutoff),
+                None => (source.volume, OPEN_CUTOFF),
+            };
+            sink.set_volume(volume.max(0.0));
+            cutoff.store(frequency.max(1), Ordering::Relaxed);
+            if paused {
+                sink.pause();
+            } else {
+                sink.play();
+            }
+        }
+
+        self.sources.retain(|entity, _| present.contains(entity));
+    }
+}
+
+/// Plays a sound on a loop from the entity's position for as long as the entity has it. The sound
+/// is heard from the middle of the camera's view.
+#[derive(Clone, Debug)]
+pub struct AudioSource {
+    pub sound: Handle<AudioFile>,
+    pub volume: f32,
+    /// How the sound is muffled while a [`Collider`](crate::Collider) is between it and the
+    /// listener, if it is at all.
+    pub occlusion: Option<Occlusion>,
+}
+
+impl Default for AudioSource {
+    fn default() -> Self {
+        Self {
+            sound: Handle::default(),
+            volume: 1.0,
+            occlusion: None,
+        }
+    }
+}
+
+/// How an [`AudioSource`] sounds while it's blocked from the listener.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Occlusion {
+    /// The fraction of its volume the source keeps.
+    pub volume: f32,
+    /// The frequency, in Hz, above which the source is filtered out.
+    pub cutoff: u32,
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Self {
+            volume: 0.5,
+            cutoff: 800,
+        }
+    }
+}
+
+/// An [`AudioSource`] as it's being played.
+struct PlayingSource {
+    sound: PathBuf,
+    /// The sink playing the sound and the cutoff of its low-pass filter, unless it couldn't be
+    /// played, in which case it isn't tried again until the sound changes.
+    sink: Option<(Sink, Arc<AtomicU32>)>,
+}
+
+/// Plays the sound over and over through a low-pass filter whose cutoff can be changed as it plays.
+fn play_looped(
+    stream_handle: &OutputStreamHandle,
+    bytes: &Arc<[u8]>,
+) -> Result<(Sink, Arc<AtomicU32>), Error> {
+    let sink = Sink::try_new(stream_handle)?;
+    let cutoff = Arc::new(AtomicU32::new(OPEN_CUTOFF));
+
+    let target = cutoff.clone();
+    let mut current = OPEN_CUTOFF;
+    let source = Decoder::new(Cursor::new(bytes.clone()))?
+        .buffered()
+        .repeat_infinite()
+        .convert_samples::<f32>()
+        .low_pass(OPEN_CUTOFF)
+        .periodic_access(FILTER_PERIOD, move |filter| {
+            let frequency = target.load(Ordering::Relaxed);
+            if frequency != current {
+                filter.to_low_pass(frequency);
+                current = frequency;
+            }
+        });
+    sink.append(source);
+
+    Ok((sink, cutoff))
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Selection {
+    RoundRobin,
+    Random,
+}
+
+/// A group of interchangeable clips, e.g. footsteps, played with a little random variation each
+/// time so that repeated sounds don't become monotonous.
+pub struct SoundVariation {
+    clips: Vec<Arc<[u8]>>,
+    /// The file each clip was read from, if it was, so it can be read again when it changes.
+    paths: Vec<Option<PathBuf>>,
+    pub selection: Selection,
+    pub pitch: RangeInclusive<f32>,
+    pub volume: RangeInclusive<f32>,
+    next: usize,
+}
+
+impl SoundVariation {
+    pub fn new(clips: Vec<Vec<u8>>) -> Self {
+        Self {
+            paths: vec![None; clips.len()],
+            clips: clips.into_iter().map(Arc::from).collect(),
+            selection: Selection::RoundRobin,
+            pitch: 1.0..=1.0,
+            volume: 1.0..=1.0,
+            next: 0,
+        }
+    }
+
+    pub fn from_files<P: AsRef<Path>>(filenames: &[P]) -> Result<Self, Error> {
+        let mut clips = Vec::with_capacity(filenames.len());
+        for filename in filenames {
+            let path = filename.as_ref();
+            let clip = files::read(path).map_err(|e| Error::AudioClip(path.to_path_buf(), e))?;
+            clips.push(clip);
+        }
+
+        let mut variation = Self::new(clips);
+        variation.paths = filenames
+            .iter()
+            .map(|filename| Some(filename.as_ref().to_path_buf()))
+            .collect();
+        Ok(variation)
+    }
+
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Ranges given backwards are turned around, and pitches clamped to be above 0.
+    pub fn with_pitch(mut self, pitch: RangeInclusive<f32>) -> Self {
+        self.pitch = valid_range(&pitch, MIN_PITCH);
+        self
+    }
+
+    /// Ranges given backwards are turned around, and volumes clamped to be 0 or more.
+    pub fn with_volume(mut self, volume: RangeInclusive<f32>) -> Self {
+        self.volume = valid_range(&volume, 0.0);
+        self
+    }
+
+    fn next_clip(&mut self, rng: &mut ThreadRng) -> Option<Arc<[u8]>> {
+        if self.clips.is_empty() {
+            return None;
+        }
+
+        let index = match self.selection {
+            Selection::RoundRobin => self.next % self.clips.len(),
+            Selection::Random if self.clips.len() > 1 => {
+                // Avoid playing the same clip twice in a row, which is exactly what we're trying to hide.
+                let offset = rng.gen_range(1..self.clips.len());
+                (self.next + offset) % self.clips.len()
+            }
+            Selection::Random => 0,
+        };
+        self.next = match self.selection {
+            Selection::RoundRobin => index + 1,
+            Selection::Random => index,
+        };
+
+        Some(self.clips[index].clone())
+    }
+}
+
+/// Puts the ends of `range` the right way round and clamps them to `min` or more, or 1 if they
+/// aren't numbers, so it can be sampled without panicking.
+fn valid_range(range: &RangeInclusive<f32>, min: f32) -> RangeInclusive<f32> {
+    let valid = |value: f32| {
+        if value.is_finite() {
+            value.max(min)
+        } else {
+            1.0
+        }
+    };
+    let (start, end) = (valid(*range.start()), valid(*range.end()));
+    start.min(end)..=start.max(end)
+}
+
+/// Picks a value from `range`, which may have been set directly rather than by the builders, so is
+/// made valid first.
+fn sample(rng: &mut ThreadRng, range: &RangeInclusive<f32>, min: f32) -> f32 {
+    rng.gen_range(valid_range(range, min))
+}
@@ -1,8 +1,13 @@
-use alpha::{Editor, Engine};
-
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> anyhow::Result<()> {
+    use alpha::{Editor, Engine};
+
     let mut engine = Engine::<Editor>::init()?;
     engine.run()?;
 
     Ok(())
 }
+
+/// The editor needs a desktop, so there's nothing to run in the browser.
+#[cfg(target_arch = "wasm32")]
+fn main() {}
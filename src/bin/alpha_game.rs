@@ -1,8 +1,27 @@
-use alpha::{Engine, Game};
+use alpha::Game;
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> anyhow::Result<()> {
+    use alpha::Engine;
+    use std::env;
+
+    // A built game keeps its scene and assets next to the runtime, so it can be started from
+    // anywhere.
+    let exe = env::current_exe()?;
+    if let Some(directory) = exe.parent() {
+        if directory.join("alpha_game.ini").is_file() {
+            env::set_current_dir(directory)?;
+        }
+    }
+
     let mut engine = Engine::<Game>::init()?;
     engine.run()?;
 
     Ok(())
 }
+
+/// Run by `wasm-bindgen` once the page has loaded the module.
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    alpha::web::start::<Game>();
+}
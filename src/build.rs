@@ -0,0 +1,284 @@
+use crate::assets::same_file;
+use crate::components::{Script, Sprite};
+use crate::error::Error;
+use crate::files;
+use crate::project::{BuildSettings, ProjectSettings};
+use crate::registry::ComponentRegistry;
+use crate::scene::{self, SceneDescription};
+use crate::sub_scene::SubScene;
+use crate::tilemap::Tilemap;
+use hecs::World;
+use log::info;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The scene file the `alpha_game` runtime loads on start.
+const GAME_SCENE: &str = "alpha_game.ini";
+
+/// Where textures, scripts and sub-scenes are copied to, inside the output directory.
+const ASSETS: &str = "assets";
+
+/// The kinds of file which are left out of a build when nothing packed refers to them.
+const STRIPPED_EXTENSIONS: [&str; 3] = ["alpha", "wasm", "lua"];
+
+/// What went into a build.
+#[derive(Debug)]
+pub struct BuildReport {
+    pub directory: PathBuf,
+    pub runtime: PathBuf,
+    /// Each asset the scenes refer to, or the project keeps, and where it was copied to, relative
+    /// to `directory`.
+    pub assets: Vec<(PathBuf, PathBuf)>,
+    /// The project's scenes and scripts which were left out, as nothing packed refers to them.
+    pub excluded: Vec<PathBuf>,
+}
+
+/// Builds a standalone copy of the game into `directory`, replacing anything already there.
+///
+/// Only what's reachable from the startup `scene` is packed: the textures, scripts and sub-scenes
+/// it refers to, and in turn those the sub-scenes refer to, are copied into an `assets` directory
+/// and the scenes rewritten to refer to the copies. The startup scene is saved as
+/// `alpha_game.ini`. Files on the project's keep-list, e.g. scenes the game loads by name, are
+/// packed at the same paths they have in the project, whether anything refers to them or not. The
+/// project's other scenes and scripts are left out and listed in the report.
+///
+/// The project settings and the `alpha_game` runtime, which is expected next to the running
+/// executable, are copied alongside, with a manifest of the game's files for browsers to fetch.
+pub fn build(
+    scene: &SceneDescription,
+    scene_path: &Path,
+    registry: &ComponentRegistry,
+    settings: &BuildSettings,
+    directory: &Path,
+) -> Result<BuildReport, Error> {
+    let runtime_name = format!("alpha_game{}", env::consts::EXE_SUFFIX);
+    let runtime = env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(&runtime_name)))
+        .filter(|runtime| runtime.is_file())
+        .ok_or_else(|| Error::BuildRuntime(PathBuf::from(&runtime_name)))?;
+
+    if directory.exists() {
+        fs::remove_dir_all(directory).map_err(|e| Error::Build(directory.to_path_buf(), e))?;
+    }
+    fs::create_dir_all(directory.join(ASSETS))
+        .map_err(|e| Error::Build(directory.to_path_buf(), e))?;
+
+    let mut assets = Assets::default();
+    let scene_file = directory.join(GAME_SCENE);
+    let source = assets.pack_scene(scene, registry, directory)?;
+    fs::write(&scene_file, source).map_err(|e| Error::Build(scene_file, e))?;
+
+    for kept in &settings.keep {
+        let destination = directory.join(kept);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(|e| Error::Build(parent.to_path_buf(), e))?;
+        }
+        if kept.extension().map_or(false, |ext| ext == "alpha") {
+            let source = assets.pack_scene(&read_scene(kept)?, registry, directory)?;
+            fs::write(&destination, source).map_err(|e| Error::Build(destination, e))?;
+        } else {
+            copy(kept, &destination)?;
+        }
+        assets.kept.push(kept.clone());
+    }
+
+    let mut manifest = vec![PathBuf::from(GAME_SCENE)];
+    let settings_file = Path::new(ProjectSettings::FILENAME);
+    if settings_file.is_file() {
+        copy(settings_file, &directory.join(ProjectSettings::FILENAME))?;
+        manifest.push(settings_file.to_path_buf());
+    }
+    manifest.extend(assets.copied.values().cloned());
+    manifest.extend(assets.kept.iter().cloned());
+    write_manifest(&manifest, directory)?;
+
+    copy(&runtime, &directory.join(&runtime_name))?;
+
+    let mut packed: Vec<&Path> = vec![scene_path];
+    packed.extend(assets.copied.keys().map(PathBuf::as_path));
+    packed.extend(assets.kept.iter().map(PathBuf::as_path));
+    let excluded: Vec<PathBuf> = project_files(Path::new("."), directory)
+        .map_err(|e| Error::Build(PathBuf::from("."), e))?
+        .into_iter()
+        .filter(|file| !packed.iter().any(|packed| same_file(packed, file)))
+        .collect();
+    for file in &excluded {
+        info!("left {} out of the build", file.display());
+    }
+
+    info!("built game into {}", directory.display());
+
+    let mut copied: Vec<(PathBuf, PathBuf)> = assets.copied.into_iter().collect();
+    copied.extend(assets.kept.into_iter().map(|kept| (kept.clone(), kept)));
+
+    Ok(BuildReport {
+        directory: directory.to_path_buf(),
+        runtime,
+        assets: copied,
+        excluded,
+    })
+}
+
+/// The assets copied so far, so each is only copied once however many entities use it.
+#[derive(Default)]
+struct Assets {
+    copied: HashMap<PathBuf, PathBuf>,
+    /// The files packed because they're on the keep-list.
+    kept: Vec<PathBuf>,
+}
+
+impl Assets {
+    /// Copies everything `scene` refers to into the build, returning the scene rewritten to refer
+    /// to the copies.
+    fn pack_scene(
+        &mut self,
+        scene: &SceneDescription,
+        registry: &ComponentRegistry,
+        directory: &Path,
+    ) -> Result<String, Error> {
+        let mut world = World::new();
+        scene.spawn(&mut world, registry);
+
+        for (_, sprite) in world.query_mut::<&mut Sprite>() {
+            if !sprite.texture.as_os_str().is_empty() {
+                sprite.texture = self.copy(&sprite.texture, directory)?.into();
+            }
+        }
+        for (_, tilemap) in world.query_mut::<&mut Tilemap>() {
+            if !tilemap.tileset.as_os_str().is_empty() {
+                tilemap.tileset = self.copy(&tilemap.tileset, directory)?.into();
+            }
+        }
+        for (_, script) in world.query_mut::<&mut Script>() {
+            if !script.filepath.as_os_str().is_empty() {
+                script.filepath = self.copy(&script.filepath, directory)?.into();
+            }
+        }
+        for (_, sub_scene) in world.query_mut::<&mut SubScene>() {
+            if !sub_scene.scene.as_os_str().is_empty() {
+                sub_scene.scene = self
+                    .copy_scene(&sub_scene.scene, registry, directory)?
+                    .into();
+            }
+        }
+
+        Ok(scene::serialise(&world, registry))
+    }
+
+    /// Packs the scene at `source` and everything it refers to, returning its new path relative
+    /// to `directory`.
+    fn copy_scene(
+        &mut self,
+        source: &Path,
+        registry: &ComponentRegistry,
+        directory: &Path,
+    ) -> Result<PathBuf, Error> {
+        if let Some(copied) = self.copied.get(source) {
+            return Ok(copied.clone());
+        }
+
+        // Recorded before the scene is packed, so scenes which refer back to it don't recurse.
+        let destination = self.destination(source);
+        self.copied
+            .insert(source.to_path_buf(), destination.clone());
+
+        let source = self.pack_scene(&read_scene(source)?, registry, directory)?;
+        let path = directory.join(&destination);
+        fs::write(&path, source).map_err(|e| Error::Build(path, e))?;
+
+        Ok(destination)
+    }
+
+    /// Copies the asset into the build, returning its new path relative to `directory`.
+    fn copy(&mut self, source: &Path, directory: &Path) -> Result<PathBuf, Error> {
+        if let Some(copied) = self.copied.get(source) {
+            return Ok(copied.clone());
+        }
+
+        let destination = self.destination(source);
+        copy(source, &directory.join(&destination))?;
+        self.copied
+            .insert(source.to_path_buf(), destination.clone());
+
+        Ok(destination)
+    }
+
+    /// Where to copy `source` to, relative to the output directory. Assets are flattened into one
+    /// directory, so files from different directories with the same name are told apart by a
+    /// number.
+    fn destination(&self, source: &Path) -> PathBuf {
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut destination = Path::new(ASSETS).join(&name);
+        let mut n = 1;
+        while self.copied.values().any(|copied| *copied == destination) {
+            destination = Path::new(ASSETS).join(format!("{}_{}", n, name));
+            n += 1;
+        }
+
+        destination
+    }
+}
+
+/// Lists the files in the build for the browser to fetch, with `/` between directories whatever
+/// the platform, as they're requested as URLs.
+fn write_manifest(files: &[PathBuf], directory: &Path) -> Result<(), Error> {
+    let mut manifest = String::new();
+    for file in files {
+        let components: Vec<_> = file
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        manifest.push_str(&components.join("/"));
+        manifest.push('\n');
+    }
+
+    let path = directory.join(files::MANIFEST);
+    fs::write(&path, manifest).map_err(|e| Error::Build(path, e))
+}
+
+fn read_scene(path: &Path) -> Result<SceneDescription, Error> {
+    let source = fs::read_to_string(path).map_err(|e| Error::Build(path.to_path_buf(), e))?;
+    SceneDescription::parse(&source)
+}
+
+/// Lists the scenes and scripts under `directory`, leaving out hidden directories, Cargo's
+/// `target` directories and the build's own `output` directory.
+fn project_files(directory: &Path, output: &Path) -> io::Result<Vec<PathBuf>> {
+    fn visit(directory: &Path, output: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" && !same_file(&path, output) {
+                    visit(&path, output, files)?;
+                }
+            } else if path
+                .extension()
+                .map_or(false, |ext| STRIPPED_EXTENSIONS.iter().any(|e| ext == *e))
+            {
+                files.push(path.strip_prefix(".").unwrap_or(&path).to_path_buf());
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    visit(directory, output, &mut files)?;
+    files.sort();
+
+    Ok(files)
+}
+
+fn copy(source: &Path, destination: &Path) -> Result<(), Error> {
+    fs::copy(source, destination)
+        .map(|_| ())
+        .map_err(|e| Error::Build(source.to_path_buf(), e))
+}
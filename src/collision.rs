@@ -0,0 +1,194 @@
+use crate::components::{Collider, ColliderShape, CollisionLayers, GlobalTransform};
+use crate::events::{CollisionEnded, CollisionStarted, Events};
+use glam::{Vec2, Vec3};
+use hecs::{Entity, World};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Finds which entities with a [`Collider`] overlap, publishing a [`CollisionStarted`] event when
+/// two start to overlap and a [`CollisionEnded`] event when they stop.
+///
+/// Colliders are fitted to the entities' [`GlobalTransform`]s, so they rotate and scale with them
+/// and their parents. Entities only collide if their [`CollisionLayers`] interact.
+#[derive(Default)]
+pub(crate) struct Collisions {
+    /// The pairs overlapping as of the last update, with the lower entity first.
+    touching: HashSet<(Entity, Entity)>,
+}
+
+impl Collisions {
+    /// Forgets which entities are overlapping, without publishing any events.
+    pub fn clear(&mut self) {
+        self.touching.clear();
+    }
+
+    pub fn update(&mut self, world: &World, events: &mut Events) {
+        let mut query = world.query::<(&GlobalTransform, &Collider, Option<&CollisionLayers>)>();
+        let colliders: Vec<_> = query
+            .iter()
+            .map(|(entity, (transform, collider, layers))| {
+                let bounds = Bounds::new(transform, collider.shape);
+                (entity, bounds, layers.copied().unwrap_or_default())
+            })
+            .collect();
+
+        let mut touching = HashSet::new();
+        for (i, (a, a_bounds, a_layers)) in colliders.iter().enumerate() {
+            for (b, b_bounds, b_layers) in &colliders[i + 1..] {
+                if a_layers.interacts_with(b_layers) && a_bounds.overlaps(b_bounds) {
+                    touching.insert(pair(*a, *b));
+                }
+            }
+        }
+
+        for &(a, b) in touching.difference(&self.touching) {
+            events.publish(CollisionStarted { a, b });
+        }
+        for &(a, b) in self.touching.difference(&touching) {
+            events.publish(CollisionEnded { a, b });
+        }
+        self.touching = touching;
+    }
+}
+
+/// Whether the straight line from `from` to `to` passes through any entity's [`Collider`] besides
+/// `ignore`'s, e.g. a wall between a sound and whoever's listening.
+pub(crate) fn is_blocked(world: &World, from: Vec2, to: Vec2, ignore: Entity) -> bool {
+    if from == to {
+        return false;
+    }
+
+    // A line is tested as a polygon with no width, whose only axis is the line's normal.
+    let line = Bounds::Polygon([from, to, to, from]);
+    world
+        .query::<(&GlobalTransform, &Collider)>()
+        .iter()
+        .filter(|(entity, _)| *entity != ignore)
+        .any(|(_, (transform, collider))| Bounds::new(transform, collider.shape).overlaps(&line))
+}
+
+fn pair(a: Entity, b: Entity) -> (Entity, Entity) {
+    if a.to_bits() < b.to_bits() {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// A collider's shape in world space.
+enum Bounds {
+    /// The corners of the entity, in order around it.
+    Polygon([Vec2; 4]),
+    Circle {
+        centre: Vec2,
+        radius: f32,
+    },
+}
+
+impl Bounds {
+    fn new(transform: &GlobalTransform, shape: ColliderShape) -> Self {
+        let point = |x, y| {
+            transform
+                .matrix
+                .transform_point3(Vec3::new(x, y, 0.0))
+                .truncate()
+        };
+        let corners = [
+            point(0.0, 0.0),
+            point(1.0, 0.0),
+            point(1.0, 1.0),
+            point(0.0, 1.0),
+        ];
+
+        match shape {
+            ColliderShape::Box => Bounds::Polygon(corners),
+            ColliderShape::Circle => Bounds::Circle {
+                centre: point(0.5, 0.5),
+                radius: corners[0]
+                    .distance(corners[1])
+                    .min(corners[0].distance(corners[3]))
+                    / 2.0,
+            },
+        }
+    }
+
+    /// Uses the separating axis theorem: two convex shapes don't overlap if there's an axis on
+    /// which their projections don't overlap.
+    fn overlaps(&self, other: &Bounds) -> bool {
+        match (self, other) {
+            (
+                Bounds::Circle { centre, radius },
+                Bounds::Circle {
+                    centre: other_centre,
+                    radius: other_radius,
+                },
+            ) => centre.distance_squared(*other_centre) < (radius + other_radius).powi(2),
+            (Bounds::Polygon(corners), Bounds::Polygon(other_corners)) => normals(corners)
+                .chain(normals(other_corners))
+                .filter(|axis| *axis != Vec2::ZERO)
+                .all(|axis| self.project(axis).overlaps(other.project(axis))),
+            (Bounds::Polygon(corners), Bounds::Circle { centre, .. })
+            | (Bounds::Circle { centre, .. }, Bounds::Polygon(corners)) => {
+                // Besides the polygon's edges, the circle can only be separated from it along the
+                // axis to the polygon's nearest corner.
+                let nearest = corners
+                    .iter()
+                    .min_by(|a, b| {
+                        let (a, b) = (a.distance_squared(*centre), b.distance_squared(*centre));
+                        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+                    })
+                    .map(|corner| (*centre - *corner).normalize_or_zero())
+                    .into_iter();
+                normals(corners)
+                    .chain(nearest)
+                    .filter(|axis| *axis != Vec2::ZERO)
+                    .all(|axis| self.project(axis).overlaps(other.project(axis)))
+            }
+        }
+    }
+
+    fn project(&self, axis: Vec2) -> Projection {
+        match self {
+            Bounds::Polygon(corners) => corners.iter().fold(
+                Projection {
+                    min: f32::INFINITY,
+                    max: f32::NEG_INFINITY,
+                },
+                |projection, corner| {
+                    let distance = corner.dot(axis);
+                    Projection {
+                        min: projection.min.min(distance),
+                        max: projection.max.max(distance),
+                    }
+                },
+            ),
+            Bounds::Circle { centre, radius } => {
+                let distance = centre.dot(axis);
+                Projection {
+                    min: distance - radius,
+                    max: distance + radius,
+                }
+            }
+        }
+    }
+}
+
+/// The unit normals of a polygon's edges.
+fn normals(corners: &[Vec2; 4]) -> impl Iterator<Item = Vec2> + '_ {
+    (0..corners.len()).map(|i| {
+        let edge = corners[(i + 1) % corners.len()] - corners[i];
+        edge.perp().normalize_or_zero()
+    })
+}
+
+/// Where a shape lies along an axis.
+struct Projection {
+    min: f32,
+    max: f32,
+}
+
+impl Projection {
+    fn overlaps(&self, other: Projection) -> bool {
+        self.min < other.max && other.min < self.max
+    }
+}
@@ -1,29 +1,338 @@
-use glam::{Mat4, Vec2, Vec3, Vec4};
+use crate::assets::{Handle, Image, ScriptFile};
+use glam::{const_vec2, Mat4, Vec2, Vec3, Vec4};
+use hecs::Entity;
+use std::path::PathBuf;
 
-#[derive(Debug)]
+/// A globally unique identifier which, unlike a hecs `Entity`, is never recycled and survives
+/// saving and reloading a scene.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Guid(pub u64);
+
+impl Guid {
+    pub fn generate() -> Self {
+        Self(rand::random())
+    }
+}
+
+/// Rate of change of an entity's [`Velocity`], in units per second squared.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Acceleration {
+    pub linear: Vec2,
+    pub angular: f32,
+}
+
+/// Bitmasks of the layers an entity is on and the layers it collides with. Cameras also use the
+/// layers an entity is on to decide whether to draw it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub layers: u32,
+    pub mask: u32,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self {
+            layers: 1,
+            mask: u32::MAX,
+        }
+    }
+}
+
+impl CollisionLayers {
+    /// Both entities have to be on a layer the other collides with.
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.layers & other.mask != 0 && other.layers & self.mask != 0
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Children(pub Vec<Entity>);
+
+/// The shape an entity collides with, sized to fit its [`Transform`]. Overlapping colliders are
+/// reported with [`CollisionStarted`](crate::CollisionStarted) and
+/// [`CollisionEnded`](crate::CollisionEnded) events.
+///
+/// With the `physics` feature, colliders also push each other apart. An entity with a collider
+/// but no `RigidBody` doesn't move. The friction and restitution only apply to physics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    pub friction: f32,
+    /// How bouncy the collider is, from 0 for not at all to 1 for keeping all of its speed.
+    pub restitution: f32,
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Self {
+            shape: ColliderShape::Box,
+            friction: 0.5,
+            restitution: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColliderShape {
+    Box,
+    /// The largest circle which fits the entity's size.
+    Circle,
+}
+
+impl ColliderShape {
+    pub const ALL: [ColliderShape; 2] = [ColliderShape::Box, ColliderShape::Circle];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColliderShape::Box => "box",
+            ColliderShape::Circle => "circle",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|shape| shape.name() == name)
+    }
+}
+
+/// The world space transformation of an entity, i.e. its [`Transform`] combined with those of all
+/// of its ancestors. Kept up to date by the transform propagation system.
+#[derive(Clone, Copy, Debug)]
+pub struct GlobalTransform {
+    pub matrix: Mat4,
+    pub inverse: Mat4,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+#[derive(Clone, Debug)]
 pub struct Shape {
     pub color: Vec4,
 }
 
-#[derive(Debug)]
+impl Default for Shape {
+    fn default() -> Self {
+        Self { color: Vec4::ONE }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    pub texture: Handle<Image>,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub tint: Vec4,
+}
+
+impl Sprite {
+    pub fn new<P: Into<PathBuf>>(texture: P) -> Self {
+        Self {
+            texture: Handle::new(texture),
+            uv_min: Vec2::ZERO,
+            uv_max: Vec2::ONE,
+            flip_x: false,
+            flip_y: false,
+            tint: Vec4::ONE,
+        }
+    }
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self::new(PathBuf::new())
+    }
+}
+
+/// Runs the WebAssembly module at `filepath` alongside the entity's other systems.
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+    pub filepath: Handle<ScriptFile>,
+    /// The values of the parameters the script declares, in the order it declares them.
+    pub parameters: Vec<(String, ScriptParameter)>,
+}
+
+/// A value a script lets designers tune from the editor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptParameter {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    Color(Vec4),
+}
+
+impl ScriptParameter {
+    /// Parses a parameter from its type name, e.g. `float`, and its value, e.g. `1.5`. Colors
+    /// have four values separated by spaces.
+    pub fn parse(kind: &str, value: &str) -> Option<Self> {
+        let value = value.trim();
+        match kind {
+            "float" => value.parse().ok().map(Self::Float),
+            "int" => value.parse().ok().map(Self::Int),
+            "bool" => value.parse().ok().map(Self::Bool),
+            "color" => {
+                let rgba: Vec<f32> = value
+                    .split_whitespace()
+                    .map(|v| v.parse().ok())
+                    .collect::<Option<_>>()?;
+                match rgba.as_slice() {
+                    [r, g, b, a] => Some(Self::Color(Vec4::new(*r, *g, *b, *a))),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Float(_) => "float",
+            Self::Int(_) => "int",
+            Self::Bool(_) => "bool",
+            Self::Color(_) => "color",
+        }
+    }
+
+    pub fn value(&self) -> String {
+        match self {
+            Self::Float(value) => value.to_string(),
+            Self::Int(value) => value.to_string(),
+            Self::Bool(value) => value.to_string(),
+            Self::Color(c) => format!("{} {} {} {}", c.x, c.y, c.z, c.w),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Tag(pub String);
 
-#[derive(Debug)]
+impl Default for Tag {
+    fn default() -> Self {
+        Self(String::from("Entity"))
+    }
+}
+
+/// Moves an entity with the physics simulation, which takes over its [`Transform`]. Bodies are
+/// simulated in world space, so they should be on entities without a [`Parent`].
+#[cfg(feature = "physics")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RigidBody {
+    pub kind: BodyKind,
+    /// How strongly gravity pulls on the body, where 0 isn't at all.
+    pub gravity_scale: f32,
+}
+
+#[cfg(feature = "physics")]
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            kind: BodyKind::Dynamic,
+            gravity_scale: 1.0,
+        }
+    }
+}
+
+#[cfg(feature = "physics")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyKind {
+    /// Moved by gravity and collisions.
+    Dynamic,
+    /// Never moves.
+    Fixed,
+    /// Follows its [`Transform`], pushing dynamic bodies out of the way.
+    Kinematic,
+}
+
+#[cfg(feature = "physics")]
+impl BodyKind {
+    pub const ALL: [BodyKind; 3] = [BodyKind::Dynamic, BodyKind::Fixed, BodyKind::Kinematic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BodyKind::Dynamic => "dynamic",
+            BodyKind::Fixed => "fixed",
+            BodyKind::Kinematic => "kinematic",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// Entities without this component are visible. Hiding an entity also hides its children.
+#[derive(Clone, Copy, Debug)]
+pub struct Visible(pub bool);
+
+impl Default for Visible {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Rate of change of an entity's [`Transform`], in units (or degrees) per second.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Velocity {
+    pub linear: Vec2,
+    pub angular: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Transform {
     pub position: Vec2,
     pub size: Vec2,
     pub rotation: f32,
+    pub scale: Vec2,
+    /// The point, as a fraction of `size`, which is placed at `position` and which the entity
+    /// rotates and scales around. `(0, 0)` is the bottom left corner and `(0.5, 0.5)` the centre.
+    pub origin: Vec2,
 }
 
+impl Transform {
+    pub const ORIGIN_BOTTOM_LEFT: Vec2 = Vec2::ZERO;
+    pub const ORIGIN_CENTRE: Vec2 = const_vec2!([0.5, 0.5]);
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            size: Vec2::new(100.0, 100.0),
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            origin: Self::ORIGIN_BOTTOM_LEFT,
+        }
+    }
+}
+
+/// Maps the unit square onto the entity in its parent's space, with the transform's origin placed
+/// at its position.
 pub fn compute_transformation_matrix(t: &Transform) -> Mat4 {
-    let mut transform = Mat4::from_translation(Vec3::new(t.position.x, t.position.y, 0.0));
-    transform *= Mat4::from_rotation_z(-t.rotation.to_radians());
+    let mut transform = compute_space_matrix(t);
     transform *= Mat4::from_scale(Vec3::new(t.size.x, t.size.y, 0.0));
+    transform *= Mat4::from_translation(Vec3::new(-t.origin.x, -t.origin.y, 0.0));
     transform
 }
 
 pub fn compute_inverse_transformation_matrix(t: &Transform) -> Mat4 {
-    let mut transform = Mat4::from_scale(Vec3::new(1.0 / t.size.x, 1.0 / t.size.y, 0.0));
+    let mut transform = Mat4::from_translation(Vec3::new(t.origin.x, t.origin.y, 0.0));
+    transform *= Mat4::from_scale(Vec3::new(1.0 / t.size.x, 1.0 / t.size.y, 0.0));
+    transform *= compute_inverse_space_matrix(t);
+    transform
+}
+
+/// The coordinate space that children of an entity with this transform are positioned in. Unlike
+/// [`compute_transformation_matrix`], this doesn't include the size of the entity itself.
+pub fn compute_space_matrix(t: &Transform) -> Mat4 {
+    let mut transform = Mat4::from_translation(Vec3::new(t.position.x, t.position.y, 0.0));
+    transform *= Mat4::from_rotation_z(-t.rotation.to_radians());
+    transform *= Mat4::from_scale(Vec3::new(t.scale.x, t.scale.y, 0.0));
+    transform
+}
+
+pub fn compute_inverse_space_matrix(t: &Transform) -> Mat4 {
+    let mut transform = Mat4::from_scale(Vec3::new(1.0 / t.scale.x, 1.0 / t.scale.y, 0.0));
     transform *= Mat4::from_rotation_z(t.rotation.to_radians());
     transform *= Mat4::from_translation(Vec3::new(-t.position.x, -t.position.y, 0.0));
     transform
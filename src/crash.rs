@@ -0,0 +1,94 @@
+use crate::logging;
+use crate::project::ProjectSettings;
+use backtrace::Backtrace;
+use log::error;
+use std::panic::{self, PanicInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, process};
+
+/// Whether there's a window to tell the user about a crash in front of, rather than only a
+/// terminal, which may not be visible at all for a shipped game.
+static WINDOWED: AtomicBool = AtomicBool::new(false);
+
+/// Replaces the default panic handler with one which writes a crash report, next to the log files
+/// if there are any, and tells the user the game has crashed before exiting.
+pub(crate) fn install(settings: &ProjectSettings) {
+    let name = settings.name.clone();
+    let directory = settings
+        .logging
+        .directory
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        let report = report(&name, info);
+        error!("{}", report);
+        logging::flush();
+
+        let path = write(&directory, &report);
+        if WINDOWED.load(Ordering::Relaxed) {
+            let saved = match &path {
+                Some(path) => format!("A crash report has been saved to {}.", path.display()),
+                None => String::from("The crash report couldn't be saved."),
+            };
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title(&format!("{} has crashed", name))
+                .set_description(&format!("{}\n\n{}", message(info), saved))
+                .set_buttons(rfd::MessageButtons::Ok)
+                .show();
+        }
+
+        process::exit(101);
+    }));
+}
+
+/// Shows crashes in a dialog from now on, once the window has been created.
+pub(crate) fn set_windowed(windowed: bool) {
+    WINDOWED.store(windowed, Ordering::Relaxed);
+}
+
+fn message(info: &PanicInfo) -> String {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown error");
+
+    match info.location() {
+        Some(location) => format!("{} at {}", message, location),
+        None => message.to_string(),
+    }
+}
+
+fn report(name: &str, info: &PanicInfo) -> String {
+    format!(
+        "{} {} crashed: {}\n\n{:?}",
+        name,
+        env!("CARGO_PKG_VERSION"),
+        message(info),
+        Backtrace::new()
+    )
+}
+
+/// Writes the report to `crash-<seconds since 1970>.txt`, returning where it was written.
+fn write(directory: &Path, report: &str) -> Option<PathBuf> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = directory.join(format!("crash-{}.txt", seconds));
+
+    match fs::create_dir_all(directory).and_then(|_| fs::write(&path, report)) {
+        Ok(_) => Some(path),
+        Err(e) => {
+            error!("unable to write crash report {}: {}", path.display(), e);
+            None
+        }
+    }
+}
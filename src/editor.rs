@@ -1,30 +1,201 @@
-use crate::engine::{Application, CreateApplication};
+use crate::alloc_tracker::{self, Subsystem};
+use crate::build::BuildReport;
+use crate::components::{Guid, Sprite};
+use crate::engine::{Application, CreateApplication, FrameContext, UpdateContext};
 use crate::error::Error;
 use crate::game::Game;
-use crate::renderer::Renderer;
+use crate::migration::MigrationReport;
+use crate::platform::WindowGeometry;
+use crate::query;
+use crate::renderer::camera::Camera;
+use crate::renderer::{RenderStats, Renderer};
+use crate::scene::{self, SceneDescription};
+use crate::tilemap::Tilemap;
+use crate::time::Time;
+use autosave::Autosave;
+use camera_bounds::CameraBounds;
+use find_replace::FindReplace;
 use glam::Vec2;
+use grid::Grid;
 use hecs::Entity;
-use log::info;
+use instant::Instant;
+use layout::Layout;
+use log::{error, info};
+use preferences::Preferences;
+use profiler::Profiler;
+use recent::RecentScenes;
+use selection::SelectionHighlight;
+use std::path::PathBuf;
+use templates::EntityTemplate;
+use tile_painter::TilePainter;
+use undo::UndoStack;
+use variants::{PrefabVariants, VariantDialog};
 use wgpu::TextureViewDescriptor;
-use winit::event::{Event, WindowEvent};
+use winit::dpi::PhysicalPosition;
+use winit::event::{ElementState, Event, KeyboardInput, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::window::Window;
 use winit_input_helper::WinitInputHelper;
 
+mod autosave;
+mod camera_bounds;
+mod find_replace;
+mod grid;
 mod gui;
+mod layout;
+mod preferences;
+mod profiler;
+mod recent;
+mod selection;
+mod templates;
+mod thumbnail;
+mod tile_painter;
+mod undo;
+mod variants;
 
 pub trait Pause {
     fn pause(&mut self, paused: bool);
 }
 
+/// Whether the game is being played in the editor's scene view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PlayMode {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+impl Default for PlayMode {
+    fn default() -> Self {
+        Self::Stopped
+    }
+}
+
+/// An entity being moved by dragging it around the scene view.
+pub(crate) struct Translation {
+    pub entity: Entity,
+    pub last_mouse_pos: Vec2,
+    /// Where the entity would be if it wasn't snapped to the grid.
+    pub position: Vec2,
+    /// The entity's transform before it was moved, for the undo stack.
+    pub before: Option<String>,
+}
+
+/// Entities copied in the editor, ready to be pasted.
+#[derive(Clone)]
+pub(crate) struct Clipboard {
+    /// The copied entity, whose descendants were copied along with it.
+    pub guid: Guid,
+    pub scene: SceneDescription,
+}
+
+impl Clipboard {
+    /// Entities go on the system clipboard as a scene, so they can be pasted into another editor,
+    /// or into a scene file.
+    pub fn to_text(&self) -> String {
+        self.scene.serialise()
+    }
+
+    /// Reads entities copied by [`Clipboard::to_text`], or `None` if the text isn't a scene.
+    pub fn from_text(text: &str) -> Option<Self> {
+        // Scenes always start with their version, unlike whatever else might be on the clipboard.
+        if !text.trim_start().starts_with("Version:") {
+            return None;
+        }
+
+        let scene = SceneDescription::parse(text).ok()?;
+        let guid = scene.root()?;
+
+        Some(Self { guid, scene })
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct EditorState {
     pub editor_title: String,
+    /// The editor's title with the scene being edited, and whether it has unsaved changes.
+    pub window_title: String,
     pub changed_since_last_save: bool,
+    pub scene_path: PathBuf,
+    pub open_requested: bool,
     pub save_requested: bool,
+    pub save_as_requested: bool,
     pub build_requested: bool,
+    pub build_open: bool,
+    pub build_result: Option<Result<BuildReport, Error>>,
+    pub play_mode: PlayMode,
+    /// The world as it was when play started, to be restored when it stops.
+    pub play_snapshot: Option<SceneDescription>,
+    pub play_requested: bool,
+    pub pause_requested: bool,
+    pub stop_requested: bool,
+    pub open_recent: Option<PathBuf>,
+    /// A scene opened from the menu, or dropped onto the window, while there were unsaved changes,
+    /// waiting for the user to decide what to do with them.
+    pub pending_open: Option<PathBuf>,
+    /// A scene to open once the one being edited has been saved.
+    pub open_after_save: Option<PathBuf>,
+    pub thumbnail_requested: bool,
+    pub delete_requested: bool,
+    /// The window was asked to close while there were unsaved changes.
+    pub close_requested: bool,
+    pub exit_requested: bool,
+    pub duplicate_requested: bool,
+    pub clipboard: Option<Clipboard>,
+    pub copy_requested: bool,
+    pub paste_requested: bool,
+    /// Where pasted entities go, or `None` to put them where they were copied from.
+    pub paste_position: Option<Vec2>,
+    /// Where in the world the scene view's context menu was opened.
+    pub context_menu_pos: Vec2,
+    pub spawn_requested: Option<(EntityTemplate, Vec2)>,
+    pub variants: PrefabVariants,
+    pub variant_dialog: VariantDialog,
+    /// The index of the prefab variant to spawn, and where.
+    pub variant_spawn_requested: Option<(usize, Vec2)>,
+    /// Textures dropped onto the scene view, and where in the world they were dropped.
+    pub dropped_textures: Vec<(PathBuf, Vec2)>,
+    pub undo: UndoStack,
+    pub undo_requested: bool,
+    pub redo_requested: bool,
+    pub autosave: Autosave,
+    pub find_replace: FindReplace,
+    pub project_settings_open: bool,
+    pub preferences: Preferences,
+    pub preferences_open: bool,
+    pub migrate_project_open: bool,
+    pub migration_reports: Vec<MigrationReport>,
+    pub recent: RecentScenes,
+    pub recent_thumbnails: Vec<Option<(egui::TextureId, u32, u32)>>,
     pub window_resized: bool,
+    /// The window has been moved or resized since its geometry was last noted in the layout.
+    pub window_moved: bool,
     pub active_entity: Option<Entity>,
+    pub dragged_entity: Option<Entity>,
+    pub translation: Option<Translation>,
+    pub tile_painter: TilePainter,
+    pub grid: Grid,
+    pub selection: SelectionHighlight,
+    pub camera_bounds: CameraBounds,
+    pub layout: Layout,
+    pub frame_requested: bool,
+    pub hierarchy: Vec<(Entity, usize)>,
+    pub hierarchy_search: String,
+    pub hierarchy_refreshed_at: Option<Instant>,
+    /// The names of the components the inspected entity had when they were last looked up.
+    pub inspected_components: Vec<&'static str>,
+    /// The entity inspected, and when the hierarchy had been refreshed, as its components were
+    /// looked up.
+    pub inspected_at: Option<(Entity, Instant)>,
+    pub render_stats: RenderStats,
+    pub profiler: Profiler,
+    pub sprite_preview: Option<(egui::TextureId, u32, u32)>,
+    pub tileset_preview: Option<(egui::TextureId, u32, u32)>,
+    pub game_scene_size: (u32, u32),
+    /// Where the game view is in the window, in pixels, as its top left corner and its size.
+    pub game_view: Option<(Vec2, Vec2)>,
+    /// Whether the mouse is over the game view, rather than the editor's UI.
+    pub game_view_hovered: bool,
     pub mouse_window_pos: Vec2,
     pub mouse_viewport_pos: Vec2,
     pub mouse_world_pos: Vec2,
@@ -32,13 +203,16 @@ pub(crate) struct EditorState {
 
 pub struct Editor {
     game: Option<Game>,
-    frames: usize,
 
     state: EditorState,
 
     egui_ctx: egui::Context,
     egui_platform: egui_winit::State,
     game_scene_texture: wgpu::Texture,
+    /// The input the game sees, which is only what's meant for it while it's playing.
+    game_input: WinitInputHelper,
+    /// The GUI is animating or has just changed, so needs drawing again.
+    needs_repaint: bool,
 }
 
 impl CreateApplication for Editor {
@@ -53,37 +227,42 @@ impl CreateApplication for Editor {
 
         let egui_ctx = egui::Context::default();
 
-        let game = Game::create(window, event_loop, renderer)?;
+        let mut game = Game::create(window, event_loop, renderer)?;
+        game.editor_camera = Some(Camera::new(renderer.width, renderer.height));
 
         let mut state = EditorState::default();
         state.editor_title = String::from("Alpha Editor");
+        state.scene_path = PathBuf::from("alpha_game.alpha");
         state.window_resized = true;
+        state.recent = RecentScenes::load();
+        state.variants = PrefabVariants::load();
+        state.layout = Layout::load();
+        if let Some(geometry) = state.layout.window {
+            geometry.restore(window);
+        }
+
+        state.preferences = Preferences::load();
+        if let Ok(project) = std::env::current_dir() {
+            game.watch_assets(&project);
+            state.preferences.add_recent_project(project);
+            if let Err(e) = state.preferences.save() {
+                error!("unable to save editor preferences: {}", e);
+            }
+        }
+        state.preferences.clone().apply(&mut state, &egui_ctx);
 
         // TODO: Recreate this texture whenever we resize the editor/scene view window.
-        let game_scene_texture_desc = wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: 1280,
-                height: 720,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Bgra8UnormSrgb,
-            usage: wgpu::TextureUsages::COPY_SRC
-                | wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::TEXTURE_BINDING,
-            label: None,
-        };
-        let game_scene_texture = renderer.device.create_texture(&game_scene_texture_desc);
+        let game_scene_texture = game_scene_texture(&renderer.device, 1280, 720);
+        state.game_scene_size = (1280, 720);
 
         let editor = Editor {
             game: Some(game),
-            frames: 0,
             state,
             egui_platform: egui_winit_state,
             egui_ctx,
             game_scene_texture,
+            game_input: WinitInputHelper::new(),
+            needs_repaint: true,
         };
 
         Ok(editor)
@@ -93,8 +272,32 @@ impl CreateApplication for Editor {
 impl Application for Editor {
     fn on_start(&mut self, _config_filename: Option<&str>) {
         if let Some(game) = &mut self.game {
-            game.on_start(Some("alpha_game.alpha"));
+            game.on_start(self.state.scene_path.to_str());
             game.pause(true);
+
+            if let Some(backup) = autosave::newer_backup(&self.state.scene_path) {
+                let recover = rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Warning)
+                    .set_title("Recover autosave?")
+                    .set_description(&format!(
+                        "{} is newer than the last save of {}. Do you want to recover it?",
+                        backup.display(),
+                        self.state.scene_path.display()
+                    ))
+                    .set_buttons(rfd::MessageButtons::YesNo)
+                    .show();
+
+                if recover {
+                    match game
+                        .scenes
+                        .load(&backup)
+                        .and_then(|_| game.load_scene(&backup))
+                    {
+                        Ok(_) => self.state.changed_since_last_save = true,
+                        Err(e) => error!("{}", e),
+                    }
+                }
+            }
         }
     }
 
@@ -104,51 +307,142 @@ impl Application for Editor {
             self.egui_platform.on_event(&self.egui_ctx, event);
         }
 
+        // The game view is drawn in pixels, so it's resized when the scale factor changes too.
         if let Event::WindowEvent {
-            event: WindowEvent::Resized(_size),
+            event: WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. },
             ..
         } = event
         {
             self.state.window_resized = true;
         }
+
+        if let Event::WindowEvent {
+            event: WindowEvent::Resized(_) | WindowEvent::Moved(_),
+            ..
+        } = event
+        {
+            self.state.window_moved = true;
+        }
+
+        let keyboard_taken = self.egui_ctx.wants_keyboard_input();
+        forward_game_input(&mut self.game_input, event, &self.state, keyboard_taken);
     }
 
-    fn on_update(
-        &mut self,
-        window: &Window,
-        renderer: &mut Renderer,
-        input: &WinitInputHelper,
-    ) -> Result<(), Error> {
+    fn on_fixed_update(&mut self, time: &Time) {
         let game = self.game.as_mut().unwrap();
+        game.pause(self.state.play_mode != PlayMode::Playing);
+        game.on_fixed_update(time);
+    }
 
-        let play_game = match self.frames {
-            1 => {
-                info!("Simulate start playing game in the editor");
-                true
-            }
-            2 => true,
-            3 => {
-                info!("Simulate stop playing game in the editor");
-                false
-            }
-            _ => false,
+    fn on_update(&mut self, ctx: &UpdateContext) -> Result<(), Error> {
+        let _scope = alloc_tracker::scope(Subsystem::Editor);
+
+        let game = self.game.as_mut().unwrap();
+
+        update_play_mode(game, &mut self.state);
+
+        if self.state.autosave.due() {
+            autosave(game, &mut self.state);
+        }
+
+        game.pause(self.state.play_mode != PlayMode::Playing);
+        let game_ctx = UpdateContext {
+            input: &self.game_input,
+            time: ctx.time,
         };
+        if let Err(e) = game.on_update(&game_ctx) {
+            // A game which fails shouldn't take the editor down with it, so just stop playing.
+            error!("{}", e);
+            self.state.stop_requested = true;
+        }
+
+        Ok(())
+    }
+
+    fn on_render(&mut self, ctx: &mut FrameContext) -> Result<(), Error> {
+        let _scope = alloc_tracker::scope(Subsystem::Editor);
+
+        if ctx.renderer.recovered {
+            ctx.renderer.recovered = false;
+            // The game view and egui's textures went with the old device.
+            let (width, height) = self.state.game_scene_size;
+            self.game_scene_texture = game_scene_texture(&ctx.renderer.device, width, height);
+            self.egui_ctx = egui::Context::default();
+            self.state
+                .preferences
+                .clone()
+                .apply(&mut self.state, &self.egui_ctx);
+            self.needs_repaint = true;
+        }
+
+        let game = self.game.as_mut().unwrap();
 
         let game_scene_texture_view = self.game_scene_texture.create_view(&Default::default());
-        renderer.render_to_texture(Some(game_scene_texture_view));
+        ctx.renderer
+            .render_to_texture(Some(game_scene_texture_view));
 
-        game.pause(!play_game);
-        game.on_update(window, renderer, input)
-            .expect("Handle error - game crash should not crash editor"); // TODO
+        if let Err(e) = game.on_render(ctx) {
+            error!("{}", e);
+            self.state.stop_requested = true;
+        }
+
+        let (window, input, renderer) = (ctx.window, ctx.input, &mut *ctx.renderer);
+        if self.state.window_moved {
+            self.state.window_moved = false;
+            self.state.layout.window = WindowGeometry::of(window, self.state.layout.window);
+        }
+        if self.state.play_mode == PlayMode::Playing {
+            self.state
+                .profiler
+                .record(game.time.unscaled_delta(), &game.timings);
+        } else {
+            self.state.profiler.pause();
+        }
         renderer.render_to_texture(None);
+        // The device was lost while the game was drawn, so what the editor made with it is made
+        // again next frame.
+        if renderer.recovered {
+            return Ok(());
+        }
+
+        if self.state.thumbnail_requested {
+            self.state.thumbnail_requested = false;
+            save_thumbnail(renderer, &self.game_scene_texture, &self.state);
+        }
 
         let tv = self
             .game_scene_texture
             .create_view(&TextureViewDescriptor::default());
         let game_scene_texture_id = renderer.egui_texture_from_wgpu_texture(&tv);
 
+        self.state.render_stats = renderer.stats;
+        self.state.sprite_preview = self
+            .state
+            .active_entity
+            .and_then(|entity| game.world.get::<Sprite>(entity).ok())
+            .map(|sprite| sprite.texture.clone())
+            .and_then(|texture| renderer.egui_texture(&texture));
+        self.state.tileset_preview = self
+            .state
+            .active_entity
+            .and_then(|entity| game.world.get::<Tilemap>(entity).ok())
+            .map(|tilemap| tilemap.tileset.clone())
+            .and_then(|tileset| renderer.egui_texture(&tileset));
+        self.state.recent_thumbnails = self
+            .state
+            .recent
+            .paths
+            .iter()
+            .map(|path| {
+                let path = thumbnail::path(path);
+                renderer.load_texture(&path);
+                renderer.egui_texture(&path)
+            })
+            .collect();
+
+        let scale_factor = renderer.scale_factor();
         self.egui_platform
-            .set_pixels_per_point(window.scale_factor() as f32);
+            .set_pixels_per_point(scale_factor.0 as f32);
         let egui_output = gui::update(
             &self.egui_ctx,
             &mut self.egui_platform,
@@ -156,14 +450,24 @@ impl Application for Editor {
             game,
             &mut self.state,
             window,
+            scale_factor,
             input,
             &mut self.game_scene_texture,
             &renderer.device,
         );
 
-        let render_ctx = renderer.prepare();
-        renderer.begin_egui(&render_ctx, &self.egui_ctx, &egui_output);
-        renderer.finalise(render_ctx);
+        self.needs_repaint = egui_output.needs_repaint;
+
+        if let Some(render_ctx) = renderer.prepare()? {
+            // The game is drawn into its own texture, so there's nothing under the editor's UI.
+            renderer.begin_egui(
+                &render_ctx,
+                &self.egui_ctx,
+                &egui_output,
+                Some(wgpu::Color::BLACK),
+            );
+            renderer.finalise(render_ctx);
+        }
 
         self.egui_platform.handle_platform_output(
             window,
@@ -171,16 +475,223 @@ impl Application for Editor {
             egui_output.platform_output,
         );
 
-        self.frames += 1;
-
         Ok(())
     }
 
+    fn frame_rate_limit(&self) -> Option<u32> {
+        self.game.as_ref().and_then(|game| game.frame_rate_limit())
+    }
+
+    fn window_title(&self) -> Option<&str> {
+        Some(&self.state.window_title)
+    }
+
+    fn time_scale(&self) -> f32 {
+        self.game.as_ref().map_or(1.0, |game| game.time_scale())
+    }
+
+    fn pause_when_minimized(&self) -> bool {
+        self.game
+            .as_ref()
+            .map_or(false, |game| game.pause_when_minimized())
+    }
+
+    /// While the game isn't playing, the editor only needs to draw when the GUI changes.
+    fn is_idle(&self) -> bool {
+        self.state.play_mode != PlayMode::Playing && !self.needs_repaint
+    }
+
+    fn on_close_requested(&mut self) -> bool {
+        if self.state.changed_since_last_save {
+            self.state.close_requested = true;
+            return false;
+        }
+
+        true
+    }
+
+    fn exit_requested(&self) -> bool {
+        self.state.exit_requested
+    }
+
     fn on_stop(&mut self) {
+        if let Err(e) = self.state.layout.save() {
+            error!("unable to save editor layout: {}", e);
+        }
+
         if let Some(game) = &mut self.game {
+            // Keep unsaved changes in a backup, which is offered for recovery on the next start.
+            if self.state.changed_since_last_save {
+                autosave(game, &mut self.state);
+            }
             game.on_stop();
         }
 
         info!("EDITOR on_stop");
     }
 }
+
+fn update_play_mode(game: &mut Game, state: &mut EditorState) {
+    // A game quitting shouldn't close the editor too, so stop playing instead.
+    if game.exit_requested() {
+        state.stop_requested = true;
+    }
+
+    if state.play_requested {
+        state.play_requested = false;
+
+        // Playing can change the scene, so keep a copy of it beforehand.
+        if state.play_mode == PlayMode::Stopped {
+            autosave(game, state);
+            state.play_snapshot =
+                Some(SceneDescription::capture_world(&game.world, &game.registry));
+        }
+        state.play_mode = PlayMode::Playing;
+        info!("playing game in the editor");
+    }
+
+    if state.pause_requested {
+        state.pause_requested = false;
+
+        if state.play_mode == PlayMode::Playing {
+            state.play_mode = PlayMode::Paused;
+        }
+    }
+
+    if state.stop_requested {
+        state.stop_requested = false;
+
+        if state.play_mode != PlayMode::Stopped {
+            game.stop();
+            if let Some(snapshot) = state.play_snapshot.take() {
+                restore(game, state, &snapshot);
+            }
+            state.play_mode = PlayMode::Stopped;
+            info!("stopped playing game in the editor");
+        }
+    }
+}
+
+/// Puts the world back the way it was before playing, keeping the same entity selected.
+fn restore(game: &mut Game, state: &mut EditorState, snapshot: &SceneDescription) {
+    let selected = state
+        .active_entity
+        .and_then(|entity| game.world.get::<Guid>(entity).ok().map(|guid| *guid));
+
+    game.world.clear();
+    snapshot.spawn(&mut game.world, &game.registry);
+
+    state.active_entity = selected.and_then(|selected| query::find_by_guid(&game.world, selected));
+    state.hierarchy_refreshed_at = None;
+}
+
+/// The texture the game is drawn into, to be shown in the editor's game view.
+pub(crate) fn game_scene_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Bgra8UnormSrgb,
+        usage: wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING,
+        label: None,
+    })
+}
+
+fn autosave(game: &Game, state: &mut EditorState) {
+    let contents = scene::serialise(&game.world, &game.registry);
+    match state.autosave.save(&state.scene_path, &contents) {
+        Ok(_) => info!("autosaved {}", state.scene_path.display()),
+        Err(e) => error!("unable to autosave {}: {}", state.scene_path.display(), e),
+    }
+}
+
+fn save_thumbnail(renderer: &mut Renderer, texture: &wgpu::Texture, state: &EditorState) {
+    let (width, height) = state.game_scene_size;
+    let pixels = match renderer.read_texture_rgba(texture, width, height) {
+        Some(pixels) => pixels,
+        None => return,
+    };
+
+    match thumbnail::save(&state.scene_path, pixels, width, height) {
+        Ok(_) => renderer.forget_texture(&thumbnail::path(&state.scene_path)),
+        Err(e) => error!(
+            "unable to save thumbnail for {}: {}",
+            state.scene_path.display(),
+            e
+        ),
+    }
+}
+
+/// Passes the game the input meant for it while it's playing: keys while the editor's UI isn't
+/// using the keyboard, and the mouse while it's over the game view, in the view's own pixels as if
+/// it were the window. Keys and buttons are always let go of, so none are left held down.
+fn forward_game_input(
+    input: &mut WinitInputHelper,
+    event: &Event<()>,
+    state: &EditorState,
+    keyboard_taken: bool,
+) {
+    let (window_id, window_event) = match event {
+        Event::WindowEvent { window_id, event } => (window_id, event),
+        // Frames are marked out by the events which aren't for the window.
+        _ => {
+            input.update(event);
+            return;
+        }
+    };
+
+    let playing = state.play_mode == PlayMode::Playing;
+    let hovered = playing && state.game_view_hovered;
+    let typing = playing && !keyboard_taken;
+    match window_event {
+        WindowEvent::CursorMoved {
+            device_id,
+            position,
+            ..
+        } if hovered => {
+            let min = state.game_view.map_or(Vec2::ZERO, |(min, _)| min);
+            let position = Vec2::new(position.x as f32, position.y as f32) - min;
+            #[allow(deprecated)]
+            input.update(&Event::<()>::WindowEvent {
+                window_id: *window_id,
+                event: WindowEvent::CursorMoved {
+                    device_id: *device_id,
+                    position: PhysicalPosition::new(position.x as f64, position.y as f64),
+                    modifiers: Default::default(),
+                },
+            });
+        }
+        WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state: ElementState::Released,
+                    ..
+                },
+            ..
+        }
+        | WindowEvent::MouseInput {
+            state: ElementState::Released,
+            ..
+        } => {
+            input.update(event);
+        }
+        WindowEvent::KeyboardInput { .. }
+        | WindowEvent::ReceivedCharacter(_)
+        | WindowEvent::ModifiersChanged(_)
+            if typing =>
+        {
+            input.update(event);
+        }
+        WindowEvent::MouseInput { .. } | WindowEvent::MouseWheel { .. } if hovered => {
+            input.update(event);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::Error;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Periodically writes the scene to a rotating set of backups next to the scene file:
+/// `scene.alpha.bak` is the newest, followed by `scene.alpha.bak.1`, `scene.alpha.bak.2`, etc.
+pub(crate) struct Autosave {
+    pub enabled: bool,
+    pub interval: Duration,
+    pub backups: usize,
+    last_saved: Instant,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::from_secs(5 * 60),
+            backups: 3,
+            last_saved: Instant::now(),
+        }
+    }
+}
+
+impl Autosave {
+    pub fn due(&self) -> bool {
+        self.enabled && self.last_saved.elapsed() >= self.interval
+    }
+
+    pub fn save(&mut self, scene_path: &Path, contents: &str) -> Result<(), Error> {
+        self.last_saved = Instant::now();
+
+        for i in (1..self.backups).rev() {
+            let from = backup_path(scene_path, i - 1);
+            if from.exists() {
+                let to = backup_path(scene_path, i);
+                fs::rename(from, &to).map_err(|e| Error::Io(to, e))?;
+            }
+        }
+
+        let path = backup_path(scene_path, 0);
+        fs::write(&path, contents).map_err(|e| Error::Io(path, e))
+    }
+}
+
+/// Returns the newest backup if it was written after the scene file was last saved.
+pub(crate) fn newer_backup(scene_path: &Path) -> Option<PathBuf> {
+    let backup = backup_path(scene_path, 0);
+    let backup_modified = fs::metadata(&backup).and_then(|m| m.modified()).ok()?;
+
+    match fs::metadata(scene_path).and_then(|m| m.modified()) {
+        Ok(scene_modified) if scene_modified >= backup_modified => None,
+        _ => Some(backup),
+    }
+}
+
+fn backup_path(scene_path: &Path, index: usize) -> PathBuf {
+    let mut filename = OsString::from(scene_path.as_os_str());
+    filename.push(".bak");
+    if index > 0 {
+        filename.push(format!(".{}", index));
+    }
+
+    PathBuf::from(filename)
+}
@@ -0,0 +1,56 @@
+use crate::editor::grid;
+use crate::renderer::camera::Camera;
+use egui::{Align2, Color32, FontId, Painter, Rect, Shape, Stroke};
+use glam::Vec2;
+
+const COLOR: Color32 = Color32::from_rgb(120, 200, 255);
+const DASH_LENGTH: f32 = 8.0;
+const GAP_LENGTH: f32 = 4.0;
+
+/// Shows the part of the world the game's camera sees, so it's clear what the player will see
+/// while the scene view is panned and zoomed elsewhere.
+pub(crate) struct CameraBounds {
+    pub visible: bool,
+}
+
+impl Default for CameraBounds {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+impl CameraBounds {
+    /// Outlines what `game_camera` sees, as shown in `rect` by `view_camera`.
+    pub fn draw(&self, painter: &Painter, rect: Rect, view_camera: &Camera, game_camera: &Camera) {
+        if !self.visible {
+            return;
+        }
+
+        let to_screen = grid::world_to_screen(view_camera, rect);
+        let min = game_camera.position;
+        let max = min + game_camera.view_size();
+        let corners = [
+            min,
+            Vec2::new(max.x, min.y),
+            max,
+            Vec2::new(min.x, max.y),
+            min,
+        ]
+        .map(to_screen);
+
+        let stroke = Stroke::new(1.0, COLOR);
+        painter.extend(Shape::dashed_line(
+            &corners,
+            stroke,
+            DASH_LENGTH,
+            GAP_LENGTH,
+        ));
+        painter.text(
+            corners[3] + egui::vec2(4.0, 2.0),
+            Align2::LEFT_TOP,
+            "Game Camera",
+            FontId::proportional(12.0),
+            COLOR,
+        );
+    }
+}
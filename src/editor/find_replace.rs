@@ -0,0 +1,94 @@
+use crate::error::Error;
+use crate::scene::{self, SceneDescription, SceneMatch};
+use log::error;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Searches every scene file under a directory for component values, e.g. a tag or a texture
+/// path, and replaces them in bulk.
+#[derive(Default)]
+pub(crate) struct FindReplace {
+    pub open: bool,
+    pub pattern: String,
+    pub replacement: String,
+    /// Only search components with this name, or all components if `None`.
+    pub component: Option<String>,
+    pub results: Vec<FindResult>,
+}
+
+pub(crate) struct FindResult {
+    pub path: PathBuf,
+    pub found: SceneMatch,
+    pub selected: bool,
+}
+
+impl FindReplace {
+    pub fn find(&mut self, directory: &Path) {
+        self.results.clear();
+
+        let paths = match scene::scene_files(directory) {
+            Ok(paths) => paths,
+            Err(e) => {
+                error!("unable to search {}: {}", directory.display(), e);
+                return;
+            }
+        };
+
+        for path in paths {
+            let source = match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(e) => {
+                    error!("unable to read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            let scene = match SceneDescription::parse(&source) {
+                Ok(scene) => scene,
+                Err(e) => {
+                    error!("unable to parse {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            for found in scene.find(self.component.as_deref(), &self.pattern) {
+                self.results.push(FindResult {
+                    path: path.clone(),
+                    found,
+                    selected: true,
+                });
+            }
+        }
+    }
+
+    /// Applies the replacement to the selected results, returning the scene files which changed.
+    pub fn replace_selected(&mut self) -> Vec<PathBuf> {
+        let mut by_path: BTreeMap<&Path, Vec<&SceneMatch>> = BTreeMap::new();
+        for result in self.results.iter().filter(|r| r.selected) {
+            by_path.entry(&result.path).or_default().push(&result.found);
+        }
+
+        let mut changed = Vec::new();
+        for (path, matches) in by_path {
+            let result = fs::read_to_string(path)
+                .map_err(|e| Error::SceneLoad(path.to_path_buf(), e))
+                .and_then(|source| SceneDescription::parse(&source))
+                .and_then(|mut scene| {
+                    for found in matches {
+                        scene.replace(found, &self.pattern, &self.replacement);
+                    }
+                    fs::write(path, scene.serialise())
+                        .map_err(|e| Error::SceneSave(path.to_path_buf(), e))
+                });
+
+            match result {
+                Ok(_) => changed.push(path.to_path_buf()),
+                Err(e) => error!("unable to replace in {}: {}", path.display(), e),
+            }
+        }
+
+        self.results.clear();
+
+        changed
+    }
+}
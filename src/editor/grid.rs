@@ -0,0 +1,119 @@
+use crate::components::Transform;
+use crate::renderer::camera::Camera;
+use egui::{Color32, Painter, Pos2, Rect, Stroke};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+
+/// Grids any finer than this on screen are too dense to be useful, so aren't drawn.
+const MIN_LINE_SPACING: f32 = 4.0;
+
+/// A grid drawn over the scene view, which positions, sizes and rotations can be snapped to.
+pub(crate) struct Grid {
+    pub visible: bool,
+    pub snap: bool,
+    /// The distance between grid lines, which positions snap to.
+    pub spacing: f32,
+    pub size_increment: f32,
+    /// In degrees.
+    pub rotation_increment: f32,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            snap: false,
+            spacing: 50.0,
+            size_increment: 10.0,
+            rotation_increment: 15.0,
+        }
+    }
+}
+
+impl Grid {
+    pub fn snap_position(&self, position: Vec2) -> Vec2 {
+        if self.snap {
+            snap(position, self.spacing)
+        } else {
+            position
+        }
+    }
+
+    /// Snaps the transform's position, size and rotation to the grid's increments.
+    pub fn snap_transform(&self, transform: &mut Transform) {
+        if !self.snap {
+            return;
+        }
+
+        transform.position = self.snap_position(transform.position);
+        transform.size = snap(transform.size, self.size_increment);
+        if self.rotation_increment > 0.0 {
+            let increment = self.rotation_increment;
+            transform.rotation = (transform.rotation / increment).round() * increment;
+        }
+    }
+
+    /// Draws the grid lines over the part of the world the camera can see in `rect`.
+    pub fn draw(&self, painter: &Painter, rect: Rect, camera: &Camera) {
+        if !self.visible || self.spacing <= 0.0 {
+            return;
+        }
+
+        let ndc_to_world = (camera.get_projection() * camera.get_view()).inverse();
+        let to_screen = world_to_screen(camera, rect);
+
+        let min = (ndc_to_world * Vec4::new(-1.0, -1.0, 0.0, 1.0)).xy();
+        let max = (ndc_to_world * Vec4::new(1.0, 1.0, 0.0, 1.0)).xy();
+        let (min, max) = (min.min(max), min.max(max));
+        if to_screen(Vec2::splat(self.spacing)).x - to_screen(Vec2::ZERO).x < MIN_LINE_SPACING {
+            return;
+        }
+
+        let stroke = Stroke::new(1.0, Color32::from_white_alpha(40));
+        let axis_stroke = Stroke::new(1.0, Color32::from_white_alpha(100));
+
+        let first = (min / self.spacing).floor();
+        let last = (max / self.spacing).ceil();
+        for i in first.x as i32..=last.x as i32 {
+            let x = i as f32 * self.spacing;
+            let stroke = if i == 0 { axis_stroke } else { stroke };
+            painter.line_segment(
+                [
+                    to_screen(Vec2::new(x, min.y)),
+                    to_screen(Vec2::new(x, max.y)),
+                ],
+                stroke,
+            );
+        }
+        for i in first.y as i32..=last.y as i32 {
+            let y = i as f32 * self.spacing;
+            let stroke = if i == 0 { axis_stroke } else { stroke };
+            painter.line_segment(
+                [
+                    to_screen(Vec2::new(min.x, y)),
+                    to_screen(Vec2::new(max.x, y)),
+                ],
+                stroke,
+            );
+        }
+    }
+}
+
+/// Maps points in the world to where they're shown in `rect`, which shows what the camera sees.
+pub(crate) fn world_to_screen(camera: &Camera, rect: Rect) -> impl Fn(Vec2) -> Pos2 {
+    let world_to_ndc = camera.get_projection() * camera.get_view();
+    move |world: Vec2| {
+        let ndc = (world_to_ndc * Vec4::from((world, 0.0, 1.0))).xy();
+        Pos2::new(
+            rect.min.x + (ndc.x + 1.0) * 0.5 * rect.width(),
+            rect.min.y + (1.0 - ndc.y) * 0.5 * rect.height(),
+        )
+    }
+}
+
+fn snap(value: Vec2, increment: f32) -> Vec2 {
+    if increment > 0.0 {
+        (value / increment).round() * increment
+    } else {
+        value
+    }
+}
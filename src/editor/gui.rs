@@ -1,14 +1,38 @@
-use crate::components::{compute_inverse_transformation_matrix, Shape, Tag, Transform};
-use crate::editor::EditorState;
+use crate::alloc_tracker::{self, Subsystem};
+use crate::build;
+use crate::components::{
+    compute_transformation_matrix, GlobalTransform, Guid, Script, Sprite, Tag, Transform, Visible,
+};
+use crate::editor::layout::{self, Dock, Layout};
+use crate::editor::preferences::Theme;
+use crate::editor::templates::EntityTemplate;
+use crate::editor::variants::PrefabVariant;
+use crate::editor::{self, Clipboard, EditorState, PlayMode, Translation};
 use crate::engine::Application;
 use crate::game::Game;
-use egui::{FullOutput, Image, PointerButton, Pos2, Sense, Slider, TextureId, Ui, Widget};
-use glam::{Vec2, Vec4, Vec4Swizzles};
-use hecs::Entity;
+use crate::hierarchy;
+use crate::logging;
+use crate::migration::{self, Migrations};
+use crate::platform::{ScaleFactor, WindowMode};
+use crate::project::{self, ProjectSettings};
+use crate::query;
+use crate::registry::Reflect;
+use crate::renderer::camera::Camera;
+use crate::save::SaveData;
+use crate::scene::{self, SceneDescription};
+use crate::tilemap::Tilemap;
+use egui::{
+    Button, FullOutput, Image, PointerButton, Pos2, Response, Sense, TextureId, Ui, Widget,
+};
+use glam::{Mat4, Vec2, Vec4, Vec4Swizzles};
+use hecs::{Entity, World};
+use instant::Instant;
+use log::error;
+use std::time::Duration;
 use std::{fs, path};
 use wgpu::{Device, Texture};
 use winit::dpi::PhysicalSize;
-use winit::event::{Event, WindowEvent};
+use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use winit::window::Window;
 use winit_input_helper::WinitInputHelper;
 
@@ -20,6 +44,7 @@ pub(crate) fn update(
     game: &mut Game,
     state: &mut EditorState,
     window: &Window,
+    scale_factor: ScaleFactor,
     input: &WinitInputHelper,
     game_scene_texture: &mut Texture,
     device: &Device,
@@ -29,121 +54,522 @@ pub(crate) fn update(
 
     egui::TopBottomPanel::top("Menu Bar").show(&egui_ctx, |ui| {
         egui::menu::bar(ui, |ui| {
-            let save = ui.button("💾 Save").clicked();
-            if save {
-                state.save_requested = true;
-            }
+            ui.menu_button("File", |ui| {
+                if ui.button("📂 Open...").clicked() {
+                    state.open_requested = true;
+                    ui.close_menu();
+                }
+
+                ui.menu_button("Open Recent", |ui| {
+                    if state.recent.paths.is_empty() {
+                        ui.label("No recent scenes");
+                    }
+
+                    for (i, path) in state.recent.paths.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if let Some(Some((texture_id, width, height))) =
+                                state.recent_thumbnails.get(i)
+                            {
+                                let size = egui::Vec2::new(*width as f32, *height as f32);
+                                let size = size * (RECENT_THUMBNAIL_HEIGHT / size.y);
+                                ui.image(*texture_id, size);
+                            }
+
+                            if ui.button(path.display().to_string()).clicked() {
+                                state.open_recent = Some(path.clone());
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                });
+
+                if ui.button("💾 Save").clicked() {
+                    state.save_requested = true;
+                    ui.close_menu();
+                }
+
+                if ui.button("💾 Save As...").clicked() {
+                    state.save_as_requested = true;
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                ui.checkbox(&mut state.autosave.enabled, "Autosave");
+                let mut minutes = state.autosave.interval.as_secs() / 60;
+                let interval = egui::DragValue::new(&mut minutes)
+                    .clamp_range(1..=60)
+                    .suffix(" min");
+                if ui.add_enabled(state.autosave.enabled, interval).changed() {
+                    state.autosave.interval = Duration::from_secs(minutes * 60);
+                }
+            });
+
+            ui.menu_button("View", |ui| {
+                let frame =
+                    ui.add_enabled(state.active_entity.is_some(), Button::new("Frame Selected"));
+                if frame.on_hover_text("F").clicked() {
+                    state.frame_requested = true;
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                ui.menu_button("Layout", |ui| {
+                    egui::Grid::new("Layout Grid")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            let panels = [
+                                ("Scene Hierarchy", &mut state.layout.hierarchy.dock),
+                                ("Properties", &mut state.layout.properties.dock),
+                            ];
+                            for (name, dock) in panels {
+                                ui.label(name);
+                                egui::ComboBox::from_id_source(name)
+                                    .selected_text(dock.name())
+                                    .show_ui(ui, |ui| {
+                                        for option in Dock::ALL {
+                                            ui.selectable_value(dock, option, option.name());
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                        });
+
+                    if ui.button("Reset Layout").clicked() {
+                        state.layout = Layout::default();
+                        ui.close_menu();
+                    }
+                });
+
+                ui.separator();
+
+                ui.checkbox(&mut state.camera_bounds.visible, "Show Game Camera");
+                ui.checkbox(&mut state.selection.visible, "Highlight Selection");
+                ui.add_enabled(
+                    state.selection.visible,
+                    egui::Checkbox::new(&mut state.selection.fill, "Fill Selection"),
+                );
+
+                ui.separator();
+
+                ui.checkbox(&mut state.grid.visible, "Show Grid");
+                ui.checkbox(&mut state.grid.snap, "Snap to Grid");
+
+                egui::Grid::new("Grid Settings")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Grid spacing");
+                        let spacing =
+                            egui::DragValue::new(&mut state.grid.spacing).clamp_range(1.0..=1000.0);
+                        ui.add(spacing);
+                        ui.end_row();
+
+                        ui.label("Size increment");
+                        let size = egui::DragValue::new(&mut state.grid.size_increment)
+                            .clamp_range(0.0..=1000.0);
+                        ui.add(size);
+                        ui.end_row();
+
+                        ui.label("Rotation increment");
+                        let rotation = egui::DragValue::new(&mut state.grid.rotation_increment)
+                            .clamp_range(0.0..=180.0)
+                            .suffix("°");
+                        ui.add(rotation);
+                        ui.end_row();
+                    });
+            });
+
+            ui.menu_button("Edit", |ui| {
+                let undo = ui.add_enabled(state.undo.can_undo(), Button::new("↶ Undo"));
+                if undo.on_hover_text("Ctrl+Z").clicked() {
+                    state.undo_requested = true;
+                    ui.close_menu();
+                }
+
+                let redo = ui.add_enabled(state.undo.can_redo(), Button::new("↷ Redo"));
+                if redo.on_hover_text("Ctrl+Shift+Z").clicked() {
+                    state.redo_requested = true;
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                let copy = ui.add_enabled(state.active_entity.is_some(), Button::new("📋 Copy"));
+                if copy.on_hover_text("Ctrl+C").clicked() {
+                    state.copy_requested = true;
+                    ui.close_menu();
+                }
+
+                let paste = ui.add_enabled(state.clipboard.is_some(), Button::new("📋 Paste"));
+                if paste.on_hover_text("Ctrl+V").clicked() {
+                    state.paste_requested = true;
+                    ui.close_menu();
+                }
+
+                let duplicate =
+                    ui.add_enabled(state.active_entity.is_some(), Button::new("⧉ Duplicate"));
+                if duplicate.on_hover_text("Ctrl+D").clicked() {
+                    state.duplicate_requested = true;
+                    ui.close_menu();
+                }
+
+                let variant = ui.add_enabled(
+                    state.active_entity.is_some(),
+                    Button::new("⎘ Create Prefab Variant..."),
+                );
+                if variant.clicked() {
+                    state.variant_dialog.open = true;
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                if ui.button("🔍 Find and Replace...").clicked() {
+                    state.find_replace.open = true;
+                    ui.close_menu();
+                }
+
+                if ui.button("⬆ Migrate Project...").clicked() {
+                    state.migrate_project_open = true;
+                    ui.close_menu();
+                }
+
+                if ui.button("⚙ Project Settings...").clicked() {
+                    state.project_settings_open = true;
+                    ui.close_menu();
+                }
+
+                if ui.button("⚙ Preferences...").clicked() {
+                    state.preferences_open = true;
+                    ui.close_menu();
+                }
+            });
 
             let build = ui.button("🛠 Build").clicked();
             if build {
                 state.build_requested = true;
             }
+
+            ui.separator();
+
+            let mode = state.play_mode;
+            if ui
+                .selectable_label(mode == PlayMode::Playing, "▶ Play")
+                .clicked()
+            {
+                state.play_requested = true;
+            }
+            let pause = egui::SelectableLabel::new(mode == PlayMode::Paused, "⏸ Pause");
+            if ui.add_enabled(mode == PlayMode::Playing, pause).clicked() {
+                state.pause_requested = true;
+            }
+            let stop = egui::SelectableLabel::new(false, "⏹ Stop");
+            if ui.add_enabled(mode != PlayMode::Stopped, stop).clicked() {
+                state.stop_requested = true;
+            }
         });
     });
 
-    egui::SidePanel::left("Scene Hierarchy").show(egui_ctx, |ui| {
+    let mut hierarchy_layout = state.layout.hierarchy;
+    layout::show_panel(egui_ctx, "Scene Hierarchy", &mut hierarchy_layout, |ui| {
         struct EntityDetails<'a> {
             id: Entity,
             tag: &'a String,
+            depth: usize,
+            visible: bool,
+            visibility_toggled: bool,
         }
 
         impl<'a> EntityDetails<'a> {
-            fn ui(&mut self, ui: &mut Ui, state: &mut EditorState) {
-                if ui.button(self.tag).clicked() {
-                    state.active_entity = Some(self.id);
-                }
+            fn ui(&mut self, ui: &mut Ui, state: &mut EditorState) -> Response {
+                ui.horizontal(|ui| {
+                    ui.add_space(self.depth as f32 * ui.spacing().indent);
+
+                    let eye = if self.visible { "👁" } else { "-" };
+                    if ui
+                        .small_button(eye)
+                        .on_hover_text("Toggle visibility")
+                        .clicked()
+                    {
+                        self.visibility_toggled = true;
+                    }
+
+                    let button = Button::new(self.tag).sense(Sense::click_and_drag());
+                    let response = ui.add(button).context_menu(|ui| {
+                        if ui.button("📋 Copy").clicked() {
+                            state.active_entity = Some(self.id);
+                            state.copy_requested = true;
+                            ui.close_menu();
+                        }
+                        if ui.button("⧉ Duplicate").clicked() {
+                            state.active_entity = Some(self.id);
+                            state.duplicate_requested = true;
+                            ui.close_menu();
+                        }
+                        if ui.button("⎘ Create Prefab Variant...").clicked() {
+                            state.active_entity = Some(self.id);
+                            state.variant_dialog.open = true;
+                            ui.close_menu();
+                        }
+                        if ui.button("🗑 Delete").clicked() {
+                            state.active_entity = Some(self.id);
+                            state.delete_requested = true;
+                            ui.close_menu();
+                        }
+                    });
+                    if response.clicked() {
+                        state.active_entity = Some(self.id);
+                    }
+                    if response.drag_started() {
+                        state.dragged_entity = Some(self.id);
+                    }
+
+                    response
+                })
+                .inner
             }
         }
 
-        for entity_ref in game.world.iter() {
-            let entity = entity_ref.entity();
-            let tag = game
+        refresh_hierarchy(game, state);
+
+        let search = egui::TextEdit::singleline(&mut state.hierarchy_search).hint_text("🔍 Search");
+        ui.add(search);
+
+        // Search results are listed flat, as their ancestors might not match.
+        let rows = if state.hierarchy_search.is_empty() {
+            None
+        } else {
+            let search = state.hierarchy_search.to_lowercase();
+            let rows: Vec<(Entity, usize)> = state
+                .hierarchy
+                .iter()
+                .filter(|(entity, _)| match game.world.get::<Tag>(*entity) {
+                    Ok(tag) => tag.0.to_lowercase().contains(&search),
+                    Err(_) => false,
+                })
+                .map(|(entity, _)| (*entity, 0))
+                .collect();
+            Some(rows)
+        };
+
+        // Only the rows scrolled into view are laid out, which keeps large scenes responsive.
+        let row_height = ui.spacing().interact_size.y;
+        let total_rows = rows.as_ref().unwrap_or(&state.hierarchy).len();
+        let pointer_pos = ui.input().pointer.hover_pos();
+        let mut drop_target = None;
+        let mut visibility_toggled = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show_rows(ui, row_height, total_rows, |ui, row_range| {
+                for row in row_range {
+                    let (entity, depth) = rows.as_ref().unwrap_or(&state.hierarchy)[row];
+                    if !game.world.contains(entity) {
+                        continue;
+                    }
+
+                    let tag = match game.world.get::<Tag>(entity) {
+                        Ok(tag) => tag.0.clone(),
+                        Err(_) => String::from("<untagged>"),
+                    };
+
+                    let visible = match game.world.get::<Visible>(entity) {
+                        Ok(visible) => visible.0,
+                        Err(_) => true,
+                    };
+
+                    let mut entity_details = EntityDetails {
+                        id: entity,
+                        tag: &tag,
+                        depth,
+                        visible,
+                        visibility_toggled: false,
+                    };
+                    let response = entity_details.ui(ui, state);
+                    if entity_details.visibility_toggled {
+                        visibility_toggled = Some((entity, !visible));
+                    }
+
+                    if let Some(pointer_pos) = pointer_pos {
+                        if response.rect.contains(pointer_pos) {
+                            drop_target = Some(entity);
+                        }
+                    }
+                }
+            });
+
+        if let Some((entity, visible)) = visibility_toggled {
+            let before = game
                 .world
-                .get::<Tag>(entity)
-                .expect("All entities should have tags");
+                .get::<Visible>(entity)
+                .ok()
+                .map(|v| v.serialise());
+            game.world.insert_one(entity, Visible(visible)).ok();
+            let after = Some(Visible(visible).serialise());
+            state
+                .undo
+                .record_component(&game.world, entity, Visible::NAME, before, after);
+            state.changed_since_last_save = true;
+        }
 
-            let mut entity_details = EntityDetails {
-                id: entity,
-                tag: &tag.0,
-            };
-            entity_details.ui(ui, state);
+        // Dropping onto another entity re-parents to it, dropping onto empty space makes a root entity.
+        if let Some(dragged) = state.dragged_entity {
+            if ui.input().pointer.any_released() {
+                state.dragged_entity = None;
+
+                let dropped_in_panel = pointer_pos
+                    .map(|pos| ui.max_rect().contains(pos))
+                    .unwrap_or(false);
+                let parent = hierarchy::parent(&game.world, dragged);
+                if dropped_in_panel
+                    && drop_target != Some(dragged)
+                    && hierarchy::set_parent(&mut game.world, dragged, drop_target)
+                {
+                    state.undo.record_reparent(&game.world, dragged, parent);
+                    state.changed_since_last_save = true;
+                    state.hierarchy_refreshed_at = None;
+                }
+            }
         }
     });
+    state.layout.hierarchy = hierarchy_layout;
 
-    egui::SidePanel::right("Properties Panel").show(egui_ctx, |ui| {
+    let mut properties_layout = state.layout.properties;
+    layout::show_panel(egui_ctx, "Properties", &mut properties_layout, |ui| {
         if let Some(entity) = state.active_entity {
-            if let Ok(mut tag) = game.world.get_mut::<Tag>(entity) {
-                egui::CollapsingHeader::new("Tag")
-                    .default_open(true)
-                    .show(ui, |ui| {
-                        ui.label("Tag");
-                        if ui.text_edit_singleline(&mut tag.0).changed() {
-                            state.changed_since_last_save = true;
-                        }
-                    });
-            };
+            if ui.button("🗑 Delete").clicked() {
+                state.delete_requested = true;
+            }
 
-            if let Ok(mut transform) = game.world.get_mut::<Transform>(entity) {
-                egui::CollapsingHeader::new("Transform")
+            // Entities without a Visible component are visible, so offer to hide them anyway.
+            if game.world.get::<Visible>(entity).is_err() {
+                let mut visible = true;
+                if ui.checkbox(&mut visible, "Visible").changed() {
+                    game.world.insert_one(entity, Visible(visible)).ok();
+                    let after = Some(Visible(visible).serialise());
+                    state
+                        .undo
+                        .record_component(&game.world, entity, Visible::NAME, None, after);
+                    state.changed_since_last_save = true;
+                    state.hierarchy_refreshed_at = None;
+                }
+            }
+
+            refresh_inspector(game, state, entity);
+
+            let mut removed = None;
+            for registration in game.registry.iter() {
+                if !state.inspected_components.contains(&registration.name) {
+                    continue;
+                }
+
+                egui::CollapsingHeader::new(registration.name)
                     .default_open(true)
                     .show(ui, |ui| {
-                        ui.label("Position");
-                        let slider = Slider::new(&mut transform.position.x, -2000.0..=2000.0)
-                            .text("x")
-                            .clamp_to_range(false);
-                        if ui.add(slider).changed() {
-                            state.changed_since_last_save = true;
-                        }
-                        let slider = Slider::new(&mut transform.position.y, -2000.0..=2000.0)
-                            .text("y")
-                            .clamp_to_range(false);
-                        if ui.add(slider).changed() {
+                        let before = (registration.serialise)(&game.world, entity);
+                        if (registration.inspect)(&mut game.world, entity, ui) {
+                            if registration.name == Transform::NAME {
+                                if let Ok(mut transform) = game.world.get_mut::<Transform>(entity) {
+                                    state.grid.snap_transform(&mut transform);
+                                }
+                            }
+                            let after = (registration.serialise)(&game.world, entity);
+                            state.undo.record_component(
+                                &game.world,
+                                entity,
+                                registration.name,
+                                before,
+                                after,
+                            );
                             state.changed_since_last_save = true;
                         }
 
-                        ui.label("Rotation");
-                        let slider =
-                            Slider::new(&mut transform.rotation, 0.0..=360.0).clamp_to_range(false);
-                        if ui.add(slider).changed() {
-                            state.changed_since_last_save = true;
+                        ui.horizontal(|ui| {
+                            if ui.small_button("🗑 Remove").clicked() {
+                                removed = Some(*registration);
+                            }
+                            // As a line of a scene file, e.g. to paste into one.
+                            let copy = ui.small_button("📋 Copy");
+                            if copy.on_hover_text("Copy as text").clicked() {
+                                if let Some(value) = (registration.serialise)(&game.world, entity) {
+                                    ui.output().copied_text =
+                                        format!("{}: {}", registration.name, value);
+                                }
+                            }
+                        });
+
+                        // Components can't reach the renderer, so the editor adds the thumbnail.
+                        if registration.name == Sprite::NAME {
+                            if let Some((texture_id, width, height)) = state.sprite_preview {
+                                let size = egui::Vec2::new(width as f32, height as f32);
+                                let size = size * (SPRITE_PREVIEW_SIZE / size.max_elem()).min(1.0);
+                                ui.image(texture_id, size);
+                            }
                         }
 
-                        ui.label("Size");
-                        let slider = Slider::new(&mut transform.size.x, 0.0..=2000.0)
-                            .text("width")
-                            .clamp_to_range(false);
-                        if ui.add(slider).changed() {
-                            state.changed_since_last_save = true;
+                        if registration.name == Tilemap::NAME {
+                            if let Ok(tilemap) = game.world.get::<Tilemap>(entity) {
+                                state.tile_painter.ui(ui, &tilemap, state.tileset_preview);
+                            }
                         }
-                        let slider = Slider::new(&mut transform.size.y, 0.0..=2000.0)
-                            .text("height")
-                            .clamp_to_range(false);
-                        if ui.add(slider).changed() {
-                            state.changed_since_last_save = true;
+
+                        if registration.name == Script::NAME {
+                            if let Some(fault) = game.scripts.fault(entity) {
+                                ui.colored_label(egui::Color32::RED, fault);
+                            }
                         }
                     });
             }
 
-            if let Ok(mut shape) = game.world.get_mut::<Shape>(entity) {
-                egui::CollapsingHeader::new("Shape")
-                    .default_open(true)
-                    .show(ui, |ui| {
-                        ui.label("Color");
+            if let Some(registration) = removed {
+                let before = (registration.serialise)(&game.world, entity);
+                (registration.remove)(&mut game.world, entity);
+                state
+                    .undo
+                    .record_component(&game.world, entity, registration.name, before, None);
+                state.changed_since_last_save = true;
+                state.hierarchy_refreshed_at = None;
+            }
 
-                        let mut color = shape.color.to_array();
+            ui.separator();
 
-                        if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
-                            shape.color = Vec4::from_slice(&color);
-                            state.changed_since_last_save = true;
+            let mut added = None;
+            egui::ComboBox::from_id_source("Add Component")
+                .selected_text("➕ Add Component")
+                .show_ui(ui, |ui| {
+                    for registration in game.registry.iter() {
+                        if state.inspected_components.contains(&registration.name) {
+                            continue;
                         }
-                    });
+
+                        if ui.selectable_label(false, registration.name).clicked() {
+                            added = Some(*registration);
+                        }
+                    }
+                });
+
+            if let Some(registration) = added {
+                (registration.insert_default)(&mut game.world, entity);
+                let after = (registration.serialise)(&game.world, entity);
+                state
+                    .undo
+                    .record_component(&game.world, entity, registration.name, None, after);
+                state.changed_since_last_save = true;
+                state.hierarchy_refreshed_at = None;
             }
-        };
+        }
     });
+    state.layout.properties = properties_layout;
 
     egui::CentralPanel::default().show(egui_ctx, |ui| {
         egui::TopBottomPanel::bottom("Scene Info Bar").show_inside(ui, |ui| {
             egui::menu::bar(ui, |ui| {
+                match state.play_mode {
+                    PlayMode::Playing => ui.colored_label(egui::Color32::GREEN, "▶ Playing"),
+                    PlayMode::Paused => ui.colored_label(egui::Color32::YELLOW, "⏸ Paused"),
+                    PlayMode::Stopped => ui.label("⏹ Stopped"),
+                };
+
                 egui::SidePanel::right("").show_inside(ui, |ui| {
                     ui.horizontal(|ui| {
                         if input.mouse().is_some() {
@@ -173,146 +599,1282 @@ pub(crate) fn update(
         ui.spacing_mut().item_spacing.x = 0.0;
         ui.spacing_mut().item_spacing.y = 0.0;
         let scene = Image::new(game_scene_texture_id, size)
-            .sense(Sense::hover())
-            .sense(Sense::click())
+            .sense(Sense::click_and_drag())
             .ui(ui);
+        state.game_view = Some((
+            scale_factor.to_physical(Vec2::new(scene.rect.min.x, scene.rect.min.y)),
+            scale_factor.to_physical(Vec2::new(size.x, size.y)),
+        ));
+        state.game_view_hovered = scene.hovered();
 
         if let Some(Pos2 {
             x: mouse_x,
             y: mouse_y,
         }) = scene.hover_pos()
         {
-            state.mouse_window_pos.x = mouse_x * window.scale_factor() as f32;
-            state.mouse_window_pos.y = mouse_y * window.scale_factor() as f32;
+            state.mouse_window_pos = scale_factor.to_physical(Vec2::new(mouse_x, mouse_y));
+            state.mouse_viewport_pos = state.mouse_window_pos
+                - scale_factor.to_physical(Vec2::new(scene.rect.min.x, scene.rect.min.y));
 
-            state.mouse_viewport_pos.x =
-                state.mouse_window_pos.x - scene.rect.min.x * window.scale_factor() as f32; //viewport_x;
-            state.mouse_viewport_pos.y =
-                state.mouse_window_pos.y - scene.rect.min.y * window.scale_factor() as f32; //viewport_y;
-
-            let viewport_width = size.x * window.scale_factor() as f32;
-            let viewport_height = size.y * window.scale_factor() as f32;
-            let viewport_dims = Vec2::new(viewport_width, viewport_height);
+            let viewport_dims = scale_factor.to_physical(Vec2::new(size.x, size.y));
             let mut ndc = ((state.mouse_viewport_pos / viewport_dims) * 2.0) - 1.0;
             ndc.y *= -1.0; // TODO: Why is this even necessary?
             let ndc = Vec4::from((ndc, 1.0, 1.0));
 
-            let inverse_projection = game.camera.get_projection().inverse();
-            let inverse_view = game.camera.get_view().inverse();
+            let inverse_projection = game.view_camera().get_projection().inverse();
+            let inverse_view = game.view_camera().get_view().inverse();
 
             let world = inverse_view * inverse_projection * ndc;
             state.mouse_world_pos.x = world.x;
             state.mouse_world_pos.y = world.y;
         }
 
-        if scene.clicked_by(PointerButton::Primary) {
-            for (id, (transform,)) in game.world.query::<(&Transform,)>().iter() {
-                let inverse = compute_inverse_transformation_matrix(transform);
-                let test_point = (inverse * Vec4::from((state.mouse_world_pos, 0.0, 1.0))).xy();
+        // Middle mouse pans the editor camera, and scrolling zooms around the mouse.
+        if let Some(camera) = &mut game.editor_camera {
+            if scene.dragged_by(PointerButton::Middle) {
+                let delta = scene.drag_delta();
+                let delta = scale_factor.to_physical(Vec2::new(delta.x, delta.y));
+                camera.position -= Vec2::new(delta.x, -delta.y) / camera.zoom;
+            }
 
-                if test_point.x >= 0.0
-                    && test_point.x <= 1.0
-                    && test_point.y >= 0.0
-                    && test_point.y <= 1.0
-                {
-                    state.active_entity = Some(id);
+            let scroll = ui.input().scroll_delta.y;
+            if scene.hovered() && scroll != 0.0 {
+                let zoom = (camera.zoom * ZOOM_SPEED.powf(scroll)).clamp(MIN_ZOOM, MAX_ZOOM);
+                let mouse = state.mouse_world_pos;
+                camera.position = mouse - (mouse - camera.position) * camera.zoom / zoom;
+                camera.zoom = zoom;
+            }
+        }
+
+        state
+            .grid
+            .draw(ui.painter(), scene.rect, game.view_camera());
+        state.camera_bounds.draw(
+            &ui.painter().with_clip_rect(scene.rect),
+            scene.rect,
+            game.view_camera(),
+            &game.camera,
+        );
+        if let Some(entity) = state.active_entity {
+            state.selection.draw(
+                &ui.painter().with_clip_rect(scene.rect),
+                scene.rect,
+                game.view_camera(),
+                &game.world,
+                entity,
+            );
+        }
+
+        // While painting tiles, the primary button paints into the selected tilemap rather than
+        // selecting and moving entities.
+        let painted_tilemap = state.active_entity.filter(|entity| {
+            state.tile_painter.active && game.world.get::<Tilemap>(*entity).is_ok()
+        });
+        if let Some(entity) = painted_tilemap {
+            if scene.hovered() {
+                state.tile_painter.draw_cursor(
+                    &ui.painter().with_clip_rect(scene.rect),
+                    scene.rect,
+                    game.view_camera(),
+                    &game.world,
+                    entity,
+                    state.mouse_world_pos,
+                );
+            }
+
+            let painting = scene.clicked_by(PointerButton::Primary)
+                || scene.dragged_by(PointerButton::Primary);
+            if painting && state.tile_painter.before.is_none() {
+                state.tile_painter.before = game
+                    .world
+                    .get::<Tilemap>(entity)
+                    .ok()
+                    .map(|t| t.serialise());
+            }
+            if painting
+                && state
+                    .tile_painter
+                    .paint(&game.world, entity, state.mouse_world_pos)
+            {
+                state.changed_since_last_save = true;
+            }
+            if !scene.dragged() {
+                if let Some(before) = state.tile_painter.before.take() {
+                    let after = game
+                        .world
+                        .get::<Tilemap>(entity)
+                        .ok()
+                        .map(|t| t.serialise());
+                    if after.as_ref() != Some(&before) {
+                        state.undo.record_component(
+                            &game.world,
+                            entity,
+                            Tilemap::NAME,
+                            Some(before),
+                            after,
+                        );
+                    }
                 }
             }
         }
 
-        if scene.clicked_by(PointerButton::Secondary) {
-            let tag = Tag(String::from("Entity"));
+        if painted_tilemap.is_none() && scene.clicked_by(PointerButton::Primary) {
+            if let Some(entity) = entity_at(&game.world, state.mouse_world_pos) {
+                state.active_entity = Some(entity);
+            }
+        }
 
-            let transform = Transform {
-                position: state.mouse_world_pos,
-                size: Vec2::new(100.0, 100.0),
-                rotation: 0.0,
-            };
+        if painted_tilemap.is_none()
+            && scene.drag_started()
+            && scene.dragged_by(PointerButton::Primary)
+        {
+            if let Some(entity) = entity_at(&game.world, state.mouse_world_pos) {
+                state.active_entity = Some(entity);
+                if let Ok(transform) = game.world.get::<Transform>(entity) {
+                    state.translation = Some(Translation {
+                        entity,
+                        last_mouse_pos: state.mouse_world_pos,
+                        position: transform.position,
+                        before: Some(transform.serialise()),
+                    });
+                }
+            }
+        }
 
-            let shape = Shape {
-                color: Vec4::new(1.0, 0.0, 0.0, 1.0),
-            };
+        if let Some(translation) = &mut state.translation {
+            let entity = translation.entity;
+            let delta = state.mouse_world_pos - translation.last_mouse_pos;
+            translation.last_mouse_pos = state.mouse_world_pos;
+
+            if delta != Vec2::ZERO {
+                translation.position += to_parent_space(&game.world, entity, delta);
+                if let Ok(mut transform) = game.world.get_mut::<Transform>(entity) {
+                    transform.position = state.grid.snap_position(translation.position);
+                }
+                state.changed_since_last_save = true;
+            }
 
-            game.world.spawn((tag, transform, shape));
+            if !scene.dragged() {
+                let before = translation.before.take();
+                let after = game
+                    .world
+                    .get::<Transform>(entity)
+                    .ok()
+                    .map(|t| t.serialise());
+                state
+                    .undo
+                    .record_component(&game.world, entity, Transform::NAME, before, after);
+                state.translation = None;
+            }
         }
 
+        // Textures dragged in from the file manager become sprites where they're dropped, and
+        // scenes are opened.
+        let dropped: Vec<path::PathBuf> = ui
+            .input()
+            .raw
+            .dropped_files
+            .iter()
+            .filter_map(|file| file.path.clone())
+            .collect();
+        for path in dropped {
+            let is_texture = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map_or(false, |ext| TEXTURE_EXTENSIONS.contains(&ext));
+            let is_scene = path.extension().map_or(false, |ext| ext == "alpha");
+            if is_texture {
+                state.dropped_textures.push((path, state.mouse_world_pos));
+            } else if is_scene {
+                state.open_recent = Some(path);
+            }
+        }
+
+        // The mouse moves onto the menu, so remember where in the scene it was opened.
+        if scene.clicked_by(PointerButton::Secondary) {
+            state.context_menu_pos = state.mouse_world_pos;
+        }
+        scene.context_menu(|ui| {
+            for template in EntityTemplate::ALL {
+                if ui.button(format!("➕ {}", template.name())).clicked() {
+                    state.spawn_requested = Some((template, state.context_menu_pos));
+                    ui.close_menu();
+                }
+            }
+            for (i, variant) in state.variants.variants.iter().enumerate() {
+                if ui.button(format!("➕ {}", variant.name)).clicked() {
+                    state.variant_spawn_requested = Some((i, state.context_menu_pos));
+                    ui.close_menu();
+                }
+            }
+
+            if state.clipboard.is_some() {
+                ui.separator();
+                if ui.button("📋 Paste Here").clicked() {
+                    state.paste_requested = true;
+                    state.paste_position = Some(state.context_menu_pos);
+                    ui.close_menu();
+                }
+            }
+        });
+
         if state.window_resized {
-            let width = (size.x * window.scale_factor() as f32) as u32;
-            let height = (size.y * window.scale_factor() as f32) as u32;
+            let (width, height) = scale_factor.physical_size(Vec2::new(size.x, size.y));
             let resize_event = Event::WindowEvent {
                 window_id: window.id(),
                 event: WindowEvent::Resized(PhysicalSize::new(width, height)),
             };
             game.on_event(&resize_event);
 
-            let game_scene_texture_desc = wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: size.x as u32,
-                    height: size.y as u32,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                usage: wgpu::TextureUsages::COPY_SRC
-                    | wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
-                label: None,
+            // Drawn at the window's pixel density, so the view is as sharp as the rest of the UI.
+            *game_scene_texture = editor::game_scene_texture(device, width, height);
+            state.game_scene_size = (width, height);
+        }
+    });
+
+    let mut find_replace_open = state.find_replace.open;
+    egui::Window::new("Find and Replace")
+        .open(&mut find_replace_open)
+        .show(egui_ctx, |ui| {
+            let find_replace = &mut state.find_replace;
+
+            egui::Grid::new("Find and Replace Grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Find");
+                    ui.text_edit_singleline(&mut find_replace.pattern);
+                    ui.end_row();
+
+                    ui.label("Replace with");
+                    ui.text_edit_singleline(&mut find_replace.replacement);
+                    ui.end_row();
+
+                    ui.label("Component");
+                    egui::ComboBox::from_id_source("Find and Replace Component")
+                        .selected_text(find_replace.component.as_deref().unwrap_or("Any"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut find_replace.component, None, "Any");
+                            for registration in game.registry.iter() {
+                                ui.selectable_value(
+                                    &mut find_replace.component,
+                                    Some(registration.name.to_string()),
+                                    registration.name,
+                                );
+                            }
+                        });
+                    ui.end_row();
+                });
+
+            let directory = match state.scene_path.parent() {
+                Some(directory) if directory != path::Path::new("") => directory.to_path_buf(),
+                _ => path::PathBuf::from("."),
             };
-            *game_scene_texture = device.create_texture(&game_scene_texture_desc);
+
+            ui.horizontal(|ui| {
+                if ui.button("🔍 Find").clicked() {
+                    find_replace.find(&directory);
+                }
+
+                // Replacing in the open scene reloads it, which would throw away unsaved changes.
+                let can_replace = !state.changed_since_last_save
+                    && find_replace.results.iter().any(|r| r.selected);
+                let replace = ui
+                    .add_enabled(can_replace, Button::new("Replace Selected"))
+                    .on_disabled_hover_text("Select some results and save the open scene first");
+                if replace.clicked() {
+                    let changed = find_replace.replace_selected();
+                    let scene_path = fs::canonicalize(&state.scene_path).ok();
+                    if changed
+                        .iter()
+                        .any(|p| fs::canonicalize(p).ok() == scene_path)
+                    {
+                        match game
+                            .scenes
+                            .load(&state.scene_path)
+                            .and_then(|_| game.load_scene(&state.scene_path))
+                        {
+                            Ok(_) => {
+                                state.active_entity = None;
+                                state.hierarchy_refreshed_at = None;
+                            }
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                }
+            });
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for result in find_replace.results.iter_mut() {
+                    let file = result
+                        .path
+                        .strip_prefix(&directory)
+                        .unwrap_or(result.path.as_path())
+                        .display();
+                    let tag = result.found.tag.as_deref().unwrap_or("<untagged>");
+                    let preview = result
+                        .found
+                        .value
+                        .replace(&find_replace.pattern, &find_replace.replacement);
+                    let text = format!(
+                        "{} • {} • {}: {} → {}",
+                        file, tag, result.found.name, result.found.value, preview
+                    );
+                    ui.checkbox(&mut result.selected, text);
+                }
+            });
+        });
+    state.find_replace.open = find_replace_open;
+
+    egui::Window::new("Migrate Project")
+        .open(&mut state.migrate_project_open)
+        .show(egui_ctx, |ui| {
+            ui.label(format!(
+                "Upgrades every scene to version {} of the scene format. Each scene which changes \
+                 is first backed up alongside the original.",
+                migration::SCENE_VERSION
+            ));
+
+            let directory = match state.scene_path.parent() {
+                Some(directory) if directory != path::Path::new("") => directory.to_path_buf(),
+                _ => path::PathBuf::from("."),
+            };
+
+            ui.horizontal(|ui| {
+                let migrations = Migrations::default();
+                if ui.button("Dry Run").clicked() {
+                    state.migration_reports =
+                        migration::migrate_project(&migrations, &directory, true);
+                }
+
+                // Migrating the open scene rewrites it on disk, which would clash with unsaved
+                // changes.
+                let migrate = ui
+                    .add_enabled(!state.changed_since_last_save, Button::new("Migrate"))
+                    .on_disabled_hover_text("Save the open scene first");
+                if migrate.clicked() {
+                    state.migration_reports =
+                        migration::migrate_project(&migrations, &directory, false);
+                }
+            });
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for report in &state.migration_reports {
+                    let file = report
+                        .path
+                        .strip_prefix(&directory)
+                        .unwrap_or(report.path.as_path())
+                        .display();
+                    match &report.result {
+                        Ok(applied) if applied.is_empty() => {
+                            ui.label(format!("{} • up to date", file));
+                        }
+                        Ok(applied) => {
+                            ui.label(format!("{} • {}", file, applied.join(", ")));
+                        }
+                        Err(e) => {
+                            ui.colored_label(egui::Color32::RED, format!("{} • {}", file, e));
+                        }
+                    }
+                }
+            });
+        });
+
+    let mut variant_requested = false;
+    let dialog = &mut state.variant_dialog;
+    egui::Window::new("Create Prefab Variant")
+        .open(&mut dialog.open)
+        .show(egui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut dialog.name);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Base");
+                egui::ComboBox::from_id_source("Prefab Variant Base")
+                    .selected_text(dialog.base.name())
+                    .show_ui(ui, |ui| {
+                        for template in EntityTemplate::ALL {
+                            ui.selectable_value(&mut dialog.base, template, template.name());
+                        }
+                    });
+            });
+            ui.label("Only the selected entity's differences from the base are kept.");
+            let create = ui.add_enabled(!dialog.name.trim().is_empty(), Button::new("Create"));
+            variant_requested = create.clicked();
+        });
+    if variant_requested {
+        if let Some(entity) = state.active_entity {
+            let dialog = &mut state.variant_dialog;
+            let variant = PrefabVariant::capture(
+                dialog.name.trim(),
+                dialog.base,
+                &game.world,
+                &game.registry,
+                entity,
+            );
+            state.variants.add(variant);
+            dialog.open = false;
+        }
+    }
+
+    egui::Window::new("Build")
+        .open(&mut state.build_open)
+        .show(egui_ctx, |ui| match &state.build_result {
+            Some(Ok(report)) => {
+                ui.label(format!(
+                    "Built the game into {}",
+                    report.directory.display()
+                ));
+                ui.label(format!("Runtime: {}", report.runtime.display()));
+
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (source, copied) in &report.assets {
+                        ui.label(format!("{} • {}", source.display(), copied.display()));
+                    }
+
+                    if !report.excluded.is_empty() {
+                        ui.separator();
+                        ui.label("Left out, as nothing packed refers to them:");
+                        for file in &report.excluded {
+                            ui.label(file.display().to_string());
+                        }
+                    }
+                });
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e.to_string());
+            }
+            None => {}
+        });
+
+    egui::Window::new("Project Settings")
+        .open(&mut state.project_settings_open)
+        .show(egui_ctx, |ui| {
+            ui.heading("Project");
+
+            let mut changed = ui
+                .horizontal(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut game.settings.name)
+                        .on_hover_text("Saved games are kept under this name")
+                        .lost_focus()
+                })
+                .inner;
+            if changed {
+                game.set_saves(SaveData::new(&game.settings.name));
+            }
+            ui.horizontal(|ui| {
+                ui.label("Title");
+                changed |= ui
+                    .text_edit_singleline(&mut game.settings.title)
+                    .on_hover_text("The title of the game's window")
+                    .lost_focus();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Icon");
+                let mut icon = game
+                    .settings
+                    .icon
+                    .as_ref()
+                    .map(|icon| icon.display().to_string())
+                    .unwrap_or_default();
+                if ui
+                    .text_edit_singleline(&mut icon)
+                    .on_hover_text("A PNG for the game's window icon")
+                    .lost_focus()
+                {
+                    game.settings.icon = Some(icon)
+                        .filter(|icon| !icon.is_empty())
+                        .map(path::PathBuf::from);
+                    changed = true;
+                }
+            });
+
+            ui.heading("Features");
+
+            let mut features = game.settings.features;
+            if ui.checkbox(&mut features.audio, "Audio").changed() {
+                game.set_features(features);
+                changed = true;
+            }
+
+            ui.heading("Display");
+
+            ui.horizontal(|ui| {
+                ui.label("Frame rate limit");
+                let max_fps = egui::DragValue::new(&mut game.settings.display.max_fps)
+                    .clamp_range(0..=1000)
+                    .suffix(" FPS");
+                changed |= ui
+                    .add(max_fps)
+                    .on_hover_text("0 draws as many frames as possible")
+                    .changed();
+            });
+            changed |= ui
+                .checkbox(
+                    &mut game.settings.display.pause_when_minimized,
+                    "Pause when minimised",
+                )
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label("Window");
+                for mode in WindowMode::ALL {
+                    changed |= ui
+                        .radio_value(&mut game.settings.display.window_mode, mode, mode.name())
+                        .changed();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Fullscreen on");
+                let monitors: Vec<String> = window
+                    .available_monitors()
+                    .enumerate()
+                    .map(|(i, monitor)| monitor.name().unwrap_or_else(|| format!("Monitor {}", i)))
+                    .collect();
+                let monitor = &mut game.settings.display.monitor;
+                let selected = monitor
+                    .and_then(|i| monitors.get(i).cloned())
+                    .unwrap_or_else(|| String::from("Current monitor"));
+                egui::ComboBox::from_id_source("Fullscreen Monitor")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(monitor, None, "Current monitor")
+                            .changed();
+                        for (i, name) in monitors.iter().enumerate() {
+                            changed |= ui.selectable_value(monitor, Some(i), name).changed();
+                        }
+                    });
+            });
+
+            ui.heading("Logging");
+
+            ui.horizontal(|ui| {
+                ui.label("Level");
+                let level = &mut game.settings.logging.level;
+                let response = ui
+                    .text_edit_singleline(level)
+                    .on_hover_text("e.g. info, alpha::scripting=debug");
+                // Levels are only complete once they've been typed in.
+                if response.lost_focus() && logging::log_level().as_deref() != Some(level.as_str())
+                {
+                    match logging::set_log_level(level) {
+                        Ok(_) => changed = true,
+                        Err(e) => error!("invalid log level {}: {}", level, e),
+                    }
+                }
+            });
+
+            #[cfg(feature = "physics")]
+            {
+                ui.heading("Physics");
+
+                ui.horizontal(|ui| {
+                    ui.label("Gravity");
+                    let gravity = &mut game.settings.physics.gravity;
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut gravity.x).prefix("x: "))
+                        .changed();
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut gravity.y).prefix("y: "))
+                        .changed();
+                });
+            }
+
+            ui.heading("Build");
+
+            ui.horizontal(|ui| {
+                ui.label("Keep");
+                let mut keep = project::join_paths(&game.settings.build.keep);
+                if ui
+                    .text_edit_singleline(&mut keep)
+                    .on_hover_text(
+                        "Files to pack even if the startup scene doesn't refer to them, separated \
+                         by commas",
+                    )
+                    .lost_focus()
+                {
+                    game.settings.build.keep = project::parse_paths(&keep);
+                    changed = true;
+                }
+            });
+
+            if changed {
+                if let Err(e) = game.settings.save(ProjectSettings::FILENAME) {
+                    error!("unable to save {}: {}", ProjectSettings::FILENAME, e);
+                }
+            }
+        });
+
+    let mut preferences = state.preferences.clone();
+    egui::Window::new("Preferences")
+        .open(&mut state.preferences_open)
+        .show(egui_ctx, |ui| {
+            ui.heading("Appearance");
+            ui.horizontal(|ui| {
+                ui.label("Theme");
+                for theme in Theme::ALL {
+                    ui.radio_value(&mut preferences.theme, theme, theme.name());
+                }
+            });
+
+            ui.heading("Autosave");
+            ui.checkbox(&mut preferences.autosave_enabled, "Autosave");
+            let mut minutes = preferences.autosave_interval.as_secs() / 60;
+            let interval = egui::DragValue::new(&mut minutes)
+                .clamp_range(1..=60)
+                .suffix(" min");
+            if ui.add(interval).changed() {
+                preferences.autosave_interval = Duration::from_secs(minutes * 60);
+            }
+
+            ui.heading("Grid");
+            ui.checkbox(&mut preferences.grid_visible, "Show Grid");
+            ui.checkbox(&mut preferences.grid_snap, "Snap to Grid");
+            egui::Grid::new("Preferences Grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Grid spacing");
+                    let spacing = egui::DragValue::new(&mut preferences.grid_spacing)
+                        .clamp_range(1.0..=1000.0);
+                    ui.add(spacing);
+                    ui.end_row();
+
+                    ui.label("Size increment");
+                    let size = egui::DragValue::new(&mut preferences.grid_size_increment)
+                        .clamp_range(0.0..=1000.0);
+                    ui.add(size);
+                    ui.end_row();
+
+                    ui.label("Rotation increment");
+                    let rotation = egui::DragValue::new(&mut preferences.grid_rotation_increment)
+                        .clamp_range(0.0..=180.0)
+                        .suffix("°");
+                    ui.add(rotation);
+                    ui.end_row();
+                });
+
+            ui.heading("Scripts");
+            ui.horizontal(|ui| {
+                ui.label("External editor");
+                ui.text_edit_singleline(&mut preferences.external_editor)
+                    .on_hover_text("The command scripts are opened with, e.g. code --wait");
+            });
+
+            ui.heading("Recent Projects");
+            for project in &preferences.recent_projects {
+                ui.label(project.display().to_string());
+            }
+            if ui.button("Clear").clicked() {
+                preferences.recent_projects.clear();
+            }
+        });
+    if preferences != state.preferences {
+        preferences.apply(state, egui_ctx);
+        if let Err(e) = preferences.save() {
+            error!("unable to save editor preferences: {}", e);
+        }
+        state.preferences = preferences;
+    }
+
+    let mut close_requested = state.close_requested;
+    egui::Window::new("Unsaved Changes")
+        .open(&mut close_requested)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_ctx, |ui| {
+            ui.label(format!(
+                "Do you want to save the changes to {} before closing?",
+                state.scene_path.display()
+            ));
+
+            ui.horizontal(|ui| {
+                if ui.button("💾 Save").clicked() {
+                    state.save_requested = true;
+                    state.exit_requested = true;
+                }
+                if ui.button("Discard").clicked() {
+                    state.changed_since_last_save = false;
+                    state.exit_requested = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    state.close_requested = false;
+                }
+            });
+        });
+    state.close_requested &= close_requested;
+
+    let mut open_pending = state.pending_open.is_some();
+    egui::Window::new("Unsaved Changes")
+        .id(egui::Id::new("Unsaved Changes Before Opening"))
+        .open(&mut open_pending)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(egui_ctx, |ui| {
+            if let Some(pending) = &state.pending_open {
+                ui.label(format!(
+                    "Do you want to save the changes to {} before opening {}?",
+                    state.scene_path.display(),
+                    pending.display()
+                ));
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("💾 Save").clicked() {
+                    state.save_requested = true;
+                    state.open_after_save = state.pending_open.take();
+                }
+                if ui.button("Discard").clicked() {
+                    // So the scene is opened rather than asked about again.
+                    state.changed_since_last_save = false;
+                    state.open_recent = state.pending_open.take();
+                }
+                if ui.button("Cancel").clicked() {
+                    state.pending_open = None;
+                }
+            });
+        });
+    if !open_pending {
+        state.pending_open = None;
+    }
+
+    egui::Window::new("Profiler").show(egui_ctx, |ui| {
+        egui::CollapsingHeader::new("Frame")
+            .default_open(true)
+            .show(ui, |ui| {
+                let profiler = &state.profiler;
+                profiler.frame_time_graph(ui);
+
+                egui::Grid::new("Frame Grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let last = profiler.frame_times.back().copied().unwrap_or_default();
+                        ui.label("Frame time");
+                        ui.label(format!("{:.2} ms", last.as_secs_f32() * 1000.0));
+                        ui.end_row();
+
+                        let total: Duration = profiler.frame_times.iter().sum();
+                        let fps = match total.as_secs_f32() {
+                            secs if secs > 0.0 => profiler.frame_times.len() as f32 / secs,
+                            _ => 0.0,
+                        };
+                        ui.label("Average FPS");
+                        ui.label(format!("{:.1}", fps));
+                        ui.end_row();
+
+                        ui.label("Entities");
+                        ui.label(game.world.len().to_string());
+                        ui.end_row();
+
+                        ui.label("Frame");
+                        ui.label(game.time.frame().to_string());
+                        ui.end_row();
+
+                        ui.label("Time scale");
+                        let mut scale = game.time_scale();
+                        let drag = egui::DragValue::new(&mut scale)
+                            .clamp_range(0.0..=10.0)
+                            .speed(0.01);
+                        if ui.add(drag).changed() {
+                            game.set_time_scale(scale);
+                        }
+                        ui.end_row();
+                    });
+            });
+
+        egui::CollapsingHeader::new("Systems")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("Systems Grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (name, time) in &state.profiler.system_times {
+                            ui.label(*name);
+                            ui.label(format!("{:.3} ms", time.as_secs_f32() * 1000.0));
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        egui::CollapsingHeader::new("Rendering")
+            .default_open(true)
+            .show(ui, |ui| {
+                let stats = &state.render_stats;
+                egui::Grid::new("Rendering Grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("Draw calls");
+                        ui.label(stats.draw_calls.to_string());
+                        ui.end_row();
+
+                        ui.label("Vertices");
+                        ui.label(stats.vertices.to_string());
+                        ui.end_row();
+
+                        ui.label("Sprite batches");
+                        ui.label(stats.sprite_batches.to_string());
+                        ui.end_row();
+
+                        ui.label("Breaks: texture switch");
+                        ui.label(stats.texture_switch_breaks.to_string());
+                        ui.end_row();
+
+                        ui.label("Breaks: batch full");
+                        ui.label(stats.batch_full_breaks.to_string());
+                        ui.end_row();
+                    });
+                // Nothing is counted for them, as they can't break a batch.
+                ui.weak("Blend mode and scissor are fixed per pass.");
+            });
+
+        if alloc_tracker::enabled() {
+            egui::CollapsingHeader::new("Allocations")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("Allocations Grid")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.label("Subsystem");
+                            ui.label("Allocations");
+                            ui.label("Bytes");
+                            ui.end_row();
+
+                            for subsystem in Subsystem::ALL {
+                                let allocations = alloc_tracker::last_frame(subsystem);
+                                ui.label(subsystem.name());
+                                ui.label(allocations.count.to_string());
+                                ui.label(allocations.bytes.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
         }
     });
 
+    if !egui_ctx.wants_keyboard_input() {
+        if input.key_pressed(VirtualKeyCode::Delete) {
+            state.delete_requested = true;
+        }
+
+        if input.key_pressed(VirtualKeyCode::F) {
+            state.frame_requested = true;
+        }
+
+        if input.key_pressed(VirtualKeyCode::Escape) {
+            state.active_entity = None;
+        }
+
+        if input.held_control() && input.key_pressed(VirtualKeyCode::S) {
+            state.save_requested = true;
+        }
+
+        if input.held_control() && input.key_pressed(VirtualKeyCode::C) {
+            state.copy_requested = true;
+        }
+
+        if input.held_control() && input.key_pressed(VirtualKeyCode::V) {
+            state.paste_requested = true;
+        }
+
+        // egui reads the system clipboard on Ctrl+V, so entities copied in another editor can be
+        // pasted here. Anything else on the clipboard leaves the editor's own copy to be pasted.
+        let pasted = egui_ctx
+            .input()
+            .events
+            .iter()
+            .find_map(|event| match event {
+                egui::Event::Paste(text) => Clipboard::from_text(text),
+                _ => None,
+            });
+        if let Some(clipboard) = pasted {
+            state.clipboard = Some(clipboard);
+        }
+
+        if input.held_control() && input.key_pressed(VirtualKeyCode::D) {
+            state.duplicate_requested = true;
+        }
+
+        if input.held_control() && input.key_pressed(VirtualKeyCode::Z) {
+            if input.held_shift() {
+                state.redo_requested = true;
+            } else {
+                state.undo_requested = true;
+            }
+        }
+    }
+
+    state.window_title = format!("{} - {}", state.editor_title, state.scene_path.display());
     if state.changed_since_last_save {
-        window.set_title(&format!("{}*", state.editor_title));
-    } else {
-        window.set_title(&state.editor_title);
+        state.window_title.push('*');
     }
 
-    if state.save_requested {
-        let mut editor_state = String::default();
+    if state.delete_requested {
+        state.delete_requested = false;
+
+        if let Some(entity) = state.active_entity.take() {
+            if game.world.contains(entity) {
+                state
+                    .undo
+                    .record_delete(&game.world, &game.registry, entity);
+            }
+            if game.despawn(entity) {
+                state.changed_since_last_save = true;
+                state.hierarchy_refreshed_at = None;
+            }
+        }
+    }
+
+    if state.duplicate_requested {
+        state.duplicate_requested = false;
+
+        if let Some(copy) = state
+            .active_entity
+            .and_then(|entity| duplicate(game, entity))
+        {
+            state.undo.record_spawn(&game.world, &game.registry, copy);
+            state.active_entity = Some(copy);
+            state.changed_since_last_save = true;
+            state.hierarchy_refreshed_at = None;
+        }
+    }
+
+    if state.copy_requested {
+        state.copy_requested = false;
+
+        if let Some(entity) = state.active_entity {
+            if let Ok(guid) = game.world.get::<Guid>(entity) {
+                let clipboard = Clipboard {
+                    guid: *guid,
+                    scene: SceneDescription::capture(&game.world, &game.registry, entity),
+                };
+                egui_ctx.output().copied_text = clipboard.to_text();
+                state.clipboard = Some(clipboard);
+            }
+        }
+    }
+
+    if state.paste_requested {
+        state.paste_requested = false;
 
-        for e_ref in game.world.iter() {
-            // We know we only have 2 entities, both with the same components, so let's hack this in for now.
-            // TODO: Implement hecs serde.
-            let entity = e_ref.entity();
+        let position = state.paste_position.take();
+        let copy = state
+            .clipboard
+            .as_ref()
+            .and_then(|clipboard| spawn_copy(game, &clipboard.scene, clipboard.guid));
+        if let Some(copy) = copy {
+            if let (Some(position), Ok(mut transform)) =
+                (position, game.world.get_mut::<Transform>(copy))
+            {
+                transform.position = state.grid.snap_position(position);
+            }
+
+            state.undo.record_spawn(&game.world, &game.registry, copy);
+            state.active_entity = Some(copy);
+            state.changed_since_last_save = true;
+            state.hierarchy_refreshed_at = None;
+        }
+    }
+
+    for (texture, position) in std::mem::take(&mut state.dropped_textures) {
+        let position = state.grid.snap_position(position);
+        let entity = EntityTemplate::Sprite.spawn(&mut game.world, position);
+
+        if let Some(name) = texture.file_stem() {
+            let tag = query::unique_tag(&game.world, &name.to_string_lossy());
+            game.world.insert_one(entity, Tag(tag)).ok();
+        }
+        if let Ok((width, height)) = image::image_dimensions(&texture) {
+            if let Ok(mut transform) = game.world.get_mut::<Transform>(entity) {
+                transform.size = Vec2::new(width as f32, height as f32);
+            }
+        }
+        game.world.insert_one(entity, Sprite::new(texture)).ok();
+
+        state.undo.record_spawn(&game.world, &game.registry, entity);
+        state.active_entity = Some(entity);
+        state.changed_since_last_save = true;
+        state.hierarchy_refreshed_at = None;
+    }
+
+    if let Some((template, position)) = state.spawn_requested.take() {
+        let position = state.grid.snap_position(position);
+        let entity = template.spawn(&mut game.world, position);
+
+        state.undo.record_spawn(&game.world, &game.registry, entity);
+        state.active_entity = Some(entity);
+        state.changed_since_last_save = true;
+        state.hierarchy_refreshed_at = None;
+    }
+
+    if let Some((index, position)) = state.variant_spawn_requested.take() {
+        if let Some(variant) = state.variants.variants.get(index) {
+            let position = state.grid.snap_position(position);
+            let entity = variant.spawn(&mut game.world, &game.registry, position);
+
+            state.undo.record_spawn(&game.world, &game.registry, entity);
+            state.active_entity = Some(entity);
+            state.changed_since_last_save = true;
+            state.hierarchy_refreshed_at = None;
+        }
+    }
+
+    if state.frame_requested {
+        state.frame_requested = false;
+
+        if let (Some(entity), Some(camera)) = (state.active_entity, &mut game.editor_camera) {
+            frame_entity(camera, &game.world, entity);
+        }
+    }
+
+    let undone = if state.undo_requested {
+        state.undo_requested = false;
+        state.undo.undo(&mut game.world, &game.registry)
+    } else if state.redo_requested {
+        state.redo_requested = false;
+        state.undo.redo(&mut game.world, &game.registry)
+    } else {
+        false
+    };
+    if undone {
+        // Undoing a delete or a spawn changes which entities exist.
+        if let Some(entity) = state.active_entity {
+            if !game.world.contains(entity) {
+                state.active_entity = None;
+            }
+        }
+        state.changed_since_last_save = true;
+        state.hierarchy_refreshed_at = None;
+    }
 
-            let tag = game.world.get::<Tag>(entity).unwrap();
-            let tag = tag.0.to_string();
+    let mut file = state.open_recent.take();
+    if state.open_requested {
+        state.open_requested = false;
 
-            let transform = game.world.get::<Transform>(entity).unwrap();
-            let x = transform.position.x;
-            let y = transform.position.y;
-            let width = transform.size.x;
-            let height = transform.size.y;
-            let rotation = transform.rotation;
-            let transform = format!("{} {} {} {} {}", x, y, width, height, rotation);
+        file = rfd::FileDialog::new()
+            .add_filter("Alpha Scene", &["alpha"])
+            .pick_file();
+    }
+    if state.changed_since_last_save && file.is_some() {
+        state.pending_open = file;
+    } else if let Some(path) = file {
+        // Always re-read the file, the cached copy may be older than what's on disk.
+        let loaded = game.scenes.load(&path).and_then(|_| game.load_scene(&path));
+        match loaded {
+            Ok(_) => {
+                state.recent.add(&path);
+                state.scene_path = path;
+                state.active_entity = None;
+                state.undo.clear();
+                // The new scene mustn't be replaced by the old one when play stops.
+                state.play_snapshot = None;
+                state.stop_requested = true;
+                state.changed_since_last_save = false;
+                state.thumbnail_requested = true;
+            }
+            Err(e) => error!("{}", e),
+        }
+    }
 
-            let shape = game.world.get::<Shape>(entity).unwrap();
-            let r = shape.color.x;
-            let g = shape.color.y;
-            let b = shape.color.z;
-            let a = shape.color.w;
-            let color = format!("{} {} {} {}", r, g, b, a);
+    if state.save_as_requested {
+        state.save_as_requested = false;
 
-            editor_state = format!("{}{}\n{}\n{}\n---\n", editor_state, tag, transform, color);
+        let file = rfd::FileDialog::new()
+            .add_filter("Alpha Scene", &["alpha"])
+            .set_file_name(&state.scene_path.to_string_lossy())
+            .save_file();
+        if let Some(path) = file {
+            state.scene_path = path;
+            state.save_requested = true;
         }
+    }
 
-        let path = path::Path::new("alpha_game.alpha");
-        fs::write(path, editor_state).expect("Unable to write file alpha_game.alpha");
+    if state.save_requested {
+        let editor_state = scene::serialise(&game.world, &game.registry);
 
         state.save_requested = false;
-        state.changed_since_last_save = false;
+        let open_after_save = state.open_after_save.take();
+        match fs::write(&state.scene_path, editor_state) {
+            Ok(_) => {
+                state.open_recent = open_after_save;
+                state.changed_since_last_save = false;
+                state.recent.add(&state.scene_path);
+                state.thumbnail_requested = true;
+            }
+            Err(e) => error!("unable to save {}: {}", state.scene_path.display(), e),
+        }
     }
 
     if state.build_requested {
         state.build_requested = false;
-        let copy_src = path::Path::new("alpha_game.alpha");
-        let copy_dst = path::Path::new("alpha_game.ini");
-        fs::copy(copy_src, copy_dst).expect("Unable to copy alpha_game.alpha to alpha_game.ini");
+
+        // While playing, the world has been changed by the game, so build the scene as it was.
+        let scene = match &state.play_snapshot {
+            Some(snapshot) => snapshot.clone(),
+            None => SceneDescription::capture_world(&game.world, &game.registry),
+        };
+        let directory = path::Path::new(BUILD_DIRECTORY);
+        state.build_result = Some(build::build(
+            &scene,
+            &state.scene_path,
+            &game.registry,
+            &game.settings.build,
+            directory,
+        ));
+        state.build_open = true;
     }
 
     egui_ctx.end_frame()
     // egui_platform.handle_platform_output(window, &egui_ctx, egui_ctx.output().deref());
 }
+
+const SPRITE_PREVIEW_SIZE: f32 = 128.0;
+
+const BUILD_DIRECTORY: &str = "alpha_build";
+
+/// The image formats sprites can use, which can be dropped onto the scene view.
+const TEXTURE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// How much the editor camera zooms for each point scrolled.
+const ZOOM_SPEED: f32 = 1.002;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 20.0;
+
+/// How much of the view a framed entity fills.
+const FRAME_FILL: f32 = 0.6;
+
+const RECENT_THUMBNAIL_HEIGHT: f32 = 45.0;
+
+const HIERARCHY_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Finds the topmost visible entity at a position in the world.
+fn entity_at(world: &World, position: Vec2) -> Option<Entity> {
+    let mut found = None;
+    for (id, (transform,)) in world.query::<(&GlobalTransform,)>().iter() {
+        if !hierarchy::is_visible(world, id) {
+            continue;
+        }
+
+        let test_point = (transform.inverse * Vec4::from((position, 0.0, 1.0))).xy();
+
+        if test_point.x >= 0.0 && test_point.x <= 1.0 && test_point.y >= 0.0 && test_point.y <= 1.0
+        {
+            found = Some(id);
+        }
+    }
+
+    found
+}
+
+/// Converts a movement in world space into the space of the entity's parent, which its position
+/// is relative to.
+fn to_parent_space(world: &World, entity: Entity, delta: Vec2) -> Vec2 {
+    let parent_inverse = match (
+        world.get::<Transform>(entity),
+        world.get::<GlobalTransform>(entity),
+    ) {
+        // The global inverse is the entity's own inverse followed by its parent's.
+        (Ok(transform), Ok(global)) => compute_transformation_matrix(&transform) * global.inverse,
+        _ => Mat4::IDENTITY,
+    };
+
+    (parent_inverse * Vec4::from((delta, 0.0, 0.0))).xy()
+}
+
+/// Copies the entity and its descendants, giving the copy a unique tag and the same parent.
+fn duplicate(game: &mut Game, entity: Entity) -> Option<Entity> {
+    let guid = *game.world.get::<Guid>(entity).ok()?;
+    let scene = SceneDescription::capture(&game.world, &game.registry, entity);
+    let copy = spawn_copy(game, &scene, guid)?;
+
+    let parent = hierarchy::parent(&game.world, entity);
+    hierarchy::set_parent(&mut game.world, copy, parent);
+
+    Some(copy)
+}
+
+/// Spawns the captured entities with new GUIDs, so they can sit alongside the originals, and gives
+/// the copy of the entity with `guid` a unique tag. Returns that copy.
+fn spawn_copy(game: &mut Game, scene: &SceneDescription, guid: Guid) -> Option<Entity> {
+    let mut scene = scene.clone();
+    let guids = scene.regenerate_guids();
+    scene.spawn(&mut game.world, &game.registry);
+
+    let copy = query::find_by_guid(&game.world, *guids.get(&guid)?)?;
+    let tag = game
+        .world
+        .get::<Tag>(copy)
+        .ok()
+        .map(|tag| query::unique_tag(&game.world, &tag.0));
+    if let (Some(tag), Ok(mut copy_tag)) = (tag, game.world.get_mut::<Tag>(copy)) {
+        copy_tag.0 = tag;
+    }
+
+    Some(copy)
+}
+
+/// Centres the camera on the entity, zooming so that it fills most of the view.
+fn frame_entity(camera: &mut Camera, world: &World, entity: Entity) {
+    let transform = match world.get::<GlobalTransform>(entity) {
+        Ok(transform) => transform,
+        Err(_) => return,
+    };
+
+    let corners = [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE]
+        .map(|corner| (transform.matrix * Vec4::from((corner, 0.0, 1.0))).xy());
+    let min = corners
+        .iter()
+        .fold(Vec2::splat(f32::MAX), |min, c| min.min(*c));
+    let max = corners
+        .iter()
+        .fold(Vec2::splat(f32::MIN), |max, c| max.max(*c));
+
+    let screen = camera.view_size() * camera.zoom;
+    let size = (max - min).max(Vec2::ONE);
+    camera.zoom = (screen * FRAME_FILL / size)
+        .min_element()
+        .clamp(MIN_ZOOM, MAX_ZOOM);
+    camera.centre_on((min + max) / 2.0);
+}
+
+fn refresh_hierarchy(game: &Game, state: &mut EditorState) {
+    let stale = match state.hierarchy_refreshed_at {
+        Some(refreshed_at) => refreshed_at.elapsed() >= HIERARCHY_REFRESH_INTERVAL,
+        None => true,
+    };
+
+    if stale || game.world.len() as usize != state.hierarchy.len() {
+        state.hierarchy = hierarchy::flatten(&game.world);
+        state.hierarchy_refreshed_at = Some(Instant::now());
+    }
+}
+
+/// Looks up which components `entity` has whenever the hierarchy is refreshed, or a different
+/// entity is inspected, rather than asking after every registered component each frame.
+fn refresh_inspector(game: &Game, state: &mut EditorState, entity: Entity) {
+    let fresh = match (state.inspected_at, state.hierarchy_refreshed_at) {
+        (Some((inspected, at)), Some(refreshed_at)) => inspected == entity && at == refreshed_at,
+        _ => false,
+    };
+
+    if !fresh {
+        state.inspected_components = game
+            .registry
+            .iter()
+            .filter(|registration| (registration.has)(&game.world, entity))
+            .map(|registration| registration.name)
+            .collect();
+        state.inspected_at = state.hierarchy_refreshed_at.map(|at| (entity, at));
+    }
+}
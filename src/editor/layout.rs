@@ -0,0 +1,168 @@
+use crate::error::Error;
+use crate::platform::WindowGeometry;
+use egui::{Context, Ui};
+use log::warn;
+use std::fs;
+
+const FILENAME: &str = "alpha_editor_layout.txt";
+
+/// Where a panel is docked around the scene view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Dock {
+    Left,
+    Right,
+    Bottom,
+    /// Shown in its own window, which can be moved anywhere.
+    Floating,
+}
+
+impl Dock {
+    pub const ALL: [Dock; 4] = [Dock::Left, Dock::Right, Dock::Bottom, Dock::Floating];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dock::Left => "left",
+            Dock::Right => "right",
+            Dock::Bottom => "bottom",
+            Dock::Floating => "floating",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|dock| dock.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct PanelLayout {
+    pub dock: Dock,
+    /// The width of a panel docked to the side, or the height of one docked to the bottom.
+    pub size: f32,
+}
+
+/// The arrangement of the editor's panels, and where its window is. Kept between sessions in a
+/// file in the working directory, with a `panel = dock size` line for each panel and a
+/// `window = x y width height maximized` line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Layout {
+    pub hierarchy: PanelLayout,
+    pub properties: PanelLayout,
+    /// `None` until the window has been opened, to leave it where the platform puts it.
+    pub window: Option<WindowGeometry>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            hierarchy: PanelLayout {
+                dock: Dock::Left,
+                size: 200.0,
+            },
+            properties: PanelLayout {
+                dock: Dock::Right,
+                size: 250.0,
+            },
+            window: None,
+        }
+    }
+}
+
+impl Layout {
+    pub fn load() -> Self {
+        let mut layout = Self::default();
+        let contents = match fs::read_to_string(FILENAME) {
+            Ok(contents) => contents,
+            Err(_) => return layout,
+        };
+
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            let (name, value) = line.split_once('=').unwrap_or((line, ""));
+            let valid = match name.trim() {
+                "hierarchy" => parse_panel(value)
+                    .map(|panel| layout.hierarchy = panel)
+                    .is_some(),
+                "properties" => parse_panel(value)
+                    .map(|panel| layout.properties = panel)
+                    .is_some(),
+                "window" => parse_window(value)
+                    .map(|window| layout.window = Some(window))
+                    .is_some(),
+                _ => false,
+            };
+            if !valid {
+                warn!("ignoring invalid editor layout: {}", line);
+            }
+        }
+
+        layout
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let line = |name: &str, panel: &PanelLayout| {
+            format!("{} = {} {}\n", name, panel.dock.name(), panel.size)
+        };
+        let mut contents =
+            line("hierarchy", &self.hierarchy) + &line("properties", &self.properties);
+        if let Some(window) = &self.window {
+            contents += &format!(
+                "window = {} {} {} {} {}\n",
+                window.x, window.y, window.width, window.height, window.maximized
+            );
+        }
+        fs::write(FILENAME, contents).map_err(|e| Error::Io(FILENAME.into(), e))
+    }
+}
+
+fn parse_panel(value: &str) -> Option<PanelLayout> {
+    let (dock, size) = value.trim().split_once(' ')?;
+    Some(PanelLayout {
+        dock: Dock::parse(dock)?,
+        size: size.parse().ok()?,
+    })
+}
+
+fn parse_window(value: &str) -> Option<WindowGeometry> {
+    let mut values = value.split_whitespace();
+    let window = WindowGeometry {
+        x: values.next()?.parse().ok()?,
+        y: values.next()?.parse().ok()?,
+        width: values.next()?.parse().ok()?,
+        height: values.next()?.parse().ok()?,
+        maximized: values.next()?.parse().ok()?,
+    };
+    // A window with no size can't be seen, or used.
+    Some(window).filter(|window| window.width > 0 && window.height > 0)
+}
+
+/// Shows a panel docked wherever its layout says, remembering the size the user drags it to.
+///
+/// Panels docked to the sides or bottom must be shown before the scene view's central panel.
+pub(crate) fn show_panel(
+    ctx: &Context,
+    title: &str,
+    layout: &mut PanelLayout,
+    add_contents: impl FnOnce(&mut Ui),
+) {
+    let size = match layout.dock {
+        Dock::Left => {
+            let panel = egui::SidePanel::left(title).default_width(layout.size);
+            panel.show(ctx, add_contents).response.rect.width()
+        }
+        Dock::Right => {
+            let panel = egui::SidePanel::right(title).default_width(layout.size);
+            panel.show(ctx, add_contents).response.rect.width()
+        }
+        Dock::Bottom => {
+            let panel = egui::TopBottomPanel::bottom(title)
+                .resizable(true)
+                .default_height(layout.size);
+            panel.show(ctx, add_contents).response.rect.height()
+        }
+        Dock::Floating => {
+            egui::Window::new(title).show(ctx, add_contents);
+            return;
+        }
+    };
+
+    layout.size = size;
+}
@@ -0,0 +1,234 @@
+use crate::editor::EditorState;
+use crate::error::Error;
+use crate::registry;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const MAX_PROJECTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    pub fn visuals(self) -> egui::Visuals {
+        match self {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        }
+    }
+}
+
+/// The user's editor preferences, which apply to every project. Kept in an ini style file in the
+/// platform's config directory, e.g. `~/.config/alpha/editor.ini` on Linux:
+///
+/// ```text
+/// [appearance]
+/// theme = dark
+///
+/// [autosave]
+/// enabled = true
+/// interval_minutes = 5
+///
+/// [grid]
+/// visible = false
+/// snap = false
+/// spacing = 50
+/// size_increment = 10
+/// rotation_increment = 15
+///
+/// [scripts]
+/// editor = code --wait
+///
+/// [projects]
+/// recent = /home/me/games/platformer
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Preferences {
+    pub theme: Theme,
+    pub autosave_enabled: bool,
+    pub autosave_interval: Duration,
+    pub grid_visible: bool,
+    pub grid_snap: bool,
+    pub grid_spacing: f32,
+    pub grid_size_increment: f32,
+    pub grid_rotation_increment: f32,
+    /// The command scripts are opened with, which is given the script's path.
+    pub external_editor: String,
+    /// The directories the editor was most recently run in, newest first.
+    pub recent_projects: Vec<PathBuf>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Dark,
+            autosave_enabled: true,
+            autosave_interval: Duration::from_secs(5 * 60),
+            grid_visible: false,
+            grid_snap: false,
+            grid_spacing: 50.0,
+            grid_size_increment: 10.0,
+            grid_rotation_increment: 15.0,
+            external_editor: String::new(),
+            recent_projects: Vec::new(),
+        }
+    }
+}
+
+impl Preferences {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("alpha").join("editor.ini"))
+    }
+
+    /// Reads the preferences, falling back to the defaults for anything missing.
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(source) => Self::parse(&source),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("unable to read {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(source: &str) -> Self {
+        let mut preferences = Self::default();
+        let mut section = "";
+
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name;
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => {
+                    warn!("ignoring invalid editor preference: {}", line);
+                    continue;
+                }
+            };
+
+            let p = &mut preferences;
+            let valid = match (section, key) {
+                ("appearance", "theme") => match Theme::parse(value) {
+                    Some(theme) => {
+                        p.theme = theme;
+                        true
+                    }
+                    None => false,
+                },
+                ("autosave", "enabled") => value.parse().map(|v| p.autosave_enabled = v).is_ok(),
+                ("autosave", "interval_minutes") => value
+                    .parse()
+                    .map(|v| p.autosave_interval = Duration::from_secs(v * 60))
+                    .is_ok(),
+                ("grid", "visible") => value.parse().map(|v| p.grid_visible = v).is_ok(),
+                ("grid", "snap") => value.parse().map(|v| p.grid_snap = v).is_ok(),
+                ("grid", "spacing") => value.parse().map(|v| p.grid_spacing = v).is_ok(),
+                ("grid", "size_increment") => {
+                    value.parse().map(|v| p.grid_size_increment = v).is_ok()
+                }
+                ("grid", "rotation_increment") => {
+                    value.parse().map(|v| p.grid_rotation_increment = v).is_ok()
+                }
+                ("scripts", "editor") => {
+                    p.external_editor = value.to_string();
+                    true
+                }
+                ("projects", "recent") => {
+                    p.recent_projects.push(PathBuf::from(value));
+                    true
+                }
+                _ => false,
+            };
+            if !valid {
+                warn!("ignoring invalid editor preference: [{}] {}", section, line);
+            }
+        }
+
+        preferences
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let mut contents = format!(
+            "[appearance]\ntheme = {}\n\n\
+             [autosave]\nenabled = {}\ninterval_minutes = {}\n\n\
+             [grid]\nvisible = {}\nsnap = {}\nspacing = {}\nsize_increment = {}\n\
+             rotation_increment = {}\n\n\
+             [scripts]\neditor = {}\n\n\
+             [projects]\n",
+            self.theme.name(),
+            self.autosave_enabled,
+            self.autosave_interval.as_secs() / 60,
+            self.grid_visible,
+            self.grid_snap,
+            self.grid_spacing,
+            self.grid_size_increment,
+            self.grid_rotation_increment,
+            self.external_editor,
+        );
+        for project in &self.recent_projects {
+            contents.push_str(&format!("recent = {}\n", project.display()));
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::Io(dir.to_path_buf(), e))?;
+        }
+        fs::write(&path, contents).map_err(|e| Error::Io(path, e))
+    }
+
+    /// Moves the project to the top of the recent projects.
+    pub fn add_recent_project(&mut self, project: PathBuf) {
+        self.recent_projects.retain(|p| *p != project);
+        self.recent_projects.insert(0, project);
+        self.recent_projects.truncate(MAX_PROJECTS);
+    }
+
+    /// Applies the preferences to the editor, overriding any changes made this session.
+    pub fn apply(&self, state: &mut EditorState, ctx: &egui::Context) {
+        ctx.set_visuals(self.theme.visuals());
+        registry::set_external_editor(ctx, &self.external_editor);
+        state.autosave.enabled = self.autosave_enabled;
+        state.autosave.interval = self.autosave_interval;
+        state.grid.visible = self.grid_visible;
+        state.grid.snap = self.grid_snap;
+        state.grid.spacing = self.grid_spacing;
+        state.grid.size_increment = self.grid_size_increment;
+        state.grid.rotation_increment = self.grid_rotation_increment;
+    }
+}
@@ -0,0 +1,64 @@
+use egui::{Color32, Sense, Shape, Stroke, Ui};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How many frames are shown in the frame time graph.
+const FRAME_HISTORY: usize = 240;
+
+/// The frame time the graph is scaled to, which is two frames at 60 FPS.
+const GRAPH_MAX: Duration = Duration::from_millis(33);
+
+const GRAPH_HEIGHT: f32 = 60.0;
+
+/// Frame times and system timings, recorded while the game is playing in the editor.
+#[derive(Default)]
+pub(crate) struct Profiler {
+    pub frame_times: VecDeque<Duration>,
+    pub system_times: Vec<(&'static str, Duration)>,
+    recording: bool,
+}
+
+impl Profiler {
+    /// Records a frame, given how long it really took, whatever the time scale.
+    pub fn record(&mut self, frame_time: Duration, system_times: &[(&'static str, Duration)]) {
+        // The first frame after a pause began while the editor was idle, so it's left out.
+        if self.recording {
+            if self.frame_times.len() == FRAME_HISTORY {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(frame_time);
+        }
+        self.recording = true;
+
+        self.system_times = system_times.to_vec();
+    }
+
+    /// Stops recording, so the time spent paused or stopped isn't counted as a long frame.
+    pub fn pause(&mut self) {
+        self.recording = false;
+    }
+
+    /// Draws the frame times as a line graph, newest on the right.
+    pub fn frame_time_graph(&self, ui: &mut Ui) {
+        let width = ui.available_width().max(FRAME_HISTORY as f32);
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(width, GRAPH_HEIGHT), Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, Color32::from_black_alpha(100));
+
+        let step = rect.width() / (FRAME_HISTORY - 1) as f32;
+        let offset = FRAME_HISTORY - self.frame_times.len();
+        let points = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(i, time)| {
+                let height = (time.as_secs_f32() / GRAPH_MAX.as_secs_f32()).min(1.0);
+                egui::pos2(
+                    rect.left() + (offset + i) as f32 * step,
+                    rect.bottom() - height * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(Shape::line(points, Stroke::new(1.0, Color32::GREEN)));
+    }
+}
@@ -0,0 +1,39 @@
+use log::warn;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FILENAME: &str = "alpha_editor_recent.txt";
+const MAX_SCENES: usize = 8;
+
+/// The scenes most recently opened or saved in the editor, newest first. Kept between sessions in
+/// a file in the working directory.
+#[derive(Default)]
+pub(crate) struct RecentScenes {
+    pub paths: Vec<PathBuf>,
+}
+
+impl RecentScenes {
+    pub fn load() -> Self {
+        let paths = match fs::read_to_string(FILENAME) {
+            Ok(contents) => contents
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        Self { paths }
+    }
+
+    pub fn add(&mut self, path: &Path) {
+        self.paths.retain(|p| p != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(MAX_SCENES);
+
+        let contents: Vec<String> = self.paths.iter().map(|p| p.display().to_string()).collect();
+        if let Err(e) = fs::write(FILENAME, contents.join("\n")) {
+            warn!("unable to save recent scenes: {}", e);
+        }
+    }
+}
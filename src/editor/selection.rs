@@ -0,0 +1,61 @@
+use crate::components::GlobalTransform;
+use crate::editor::grid;
+use crate::renderer::camera::Camera;
+use egui::{Color32, Painter, Rect, Shape, Stroke};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+use hecs::{Entity, World};
+
+const OUTLINE_WIDTH: f32 = 2.0;
+const OUTLINE_COLOR: Color32 = Color32::from_rgb(255, 160, 0);
+const FILL_COLOR: Color32 = Color32::from_rgba_premultiplied(64, 40, 0, 64);
+
+/// How the selected entity is highlighted in the scene view.
+pub(crate) struct SelectionHighlight {
+    pub visible: bool,
+    /// Fills the entity with a translucent tint, as well as outlining it.
+    pub fill: bool,
+}
+
+impl Default for SelectionHighlight {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            fill: false,
+        }
+    }
+}
+
+impl SelectionHighlight {
+    /// Outlines the entity's bounds as they're shown in `rect`, which shows what the camera sees.
+    pub fn draw(
+        &self,
+        painter: &Painter,
+        rect: Rect,
+        camera: &Camera,
+        world: &World,
+        entity: Entity,
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        let transform = match world.get::<GlobalTransform>(entity) {
+            Ok(transform) => transform,
+            Err(_) => return,
+        };
+
+        let to_screen = grid::world_to_screen(camera, rect);
+        let corners = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y]
+            .iter()
+            .map(|corner| to_screen((transform.matrix * Vec4::from((*corner, 0.0, 1.0))).xy()))
+            .collect();
+
+        let fill = if self.fill {
+            FILL_COLOR
+        } else {
+            Color32::TRANSPARENT
+        };
+        let stroke = Stroke::new(OUTLINE_WIDTH, OUTLINE_COLOR);
+        painter.add(Shape::convex_polygon(corners, fill, stroke));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::components::{Guid, Shape, Sprite, Tag, Transform};
+use crate::query;
+use glam::{const_vec2, Vec2, Vec4};
+use hecs::{Entity, EntityBuilder, World};
+
+const SIZE: Vec2 = const_vec2!([100.0, 100.0]);
+
+/// The kinds of entity which can be created from the scene view's context menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EntityTemplate {
+    Empty,
+    Rect,
+    Sprite,
+}
+
+impl EntityTemplate {
+    pub const ALL: [EntityTemplate; 3] = [
+        EntityTemplate::Empty,
+        EntityTemplate::Rect,
+        EntityTemplate::Sprite,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EntityTemplate::Empty => "Empty",
+            EntityTemplate::Rect => "Rect",
+            EntityTemplate::Sprite => "Sprite",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|template| template.name() == name)
+    }
+
+    /// Spawns an entity from the template with its bottom left corner at `position`. Sprites are
+    /// spawned without a texture, for the user to pick in the properties panel.
+    pub fn spawn(self, world: &mut World, position: Vec2) -> Entity {
+        let mut builder = EntityBuilder::new();
+        builder.add(Guid::generate());
+        builder.add(Tag(query::unique_tag(world, self.name())));
+        builder.add(Transform {
+            position,
+            size: SIZE,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            origin: Transform::ORIGIN_BOTTOM_LEFT,
+        });
+
+        match self {
+            EntityTemplate::Empty => {}
+            EntityTemplate::Rect => {
+                builder.add(Shape {
+                    color: Vec4::new(1.0, 0.0, 0.0, 1.0),
+                });
+            }
+            EntityTemplate::Sprite => {
+                builder.add(Sprite::default());
+            }
+        }
+
+        world.spawn(builder.build())
+    }
+}
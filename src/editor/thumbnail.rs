@@ -0,0 +1,22 @@
+use image::{imageops, ImageResult, RgbaImage};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 90;
+
+/// Where the thumbnail for a scene is cached: `scene.alpha.thumbnail.png`, next to the scene.
+pub(crate) fn path(scene_path: &Path) -> PathBuf {
+    let mut filename = OsString::from(scene_path.as_os_str());
+    filename.push(".thumbnail.png");
+
+    PathBuf::from(filename)
+}
+
+/// Shrinks an RGBA image of the scene to thumbnail size and saves it.
+pub(crate) fn save(scene_path: &Path, pixels: Vec<u8>, width: u32, height: u32) -> ImageResult<()> {
+    let image = RgbaImage::from_raw(width, height, pixels)
+        .expect("pixel buffer should match the image size");
+
+    imageops::thumbnail(&image, WIDTH, HEIGHT).save(path(scene_path))
+}
@@ -0,0 +1,159 @@
+use crate::components::GlobalTransform;
+use crate::editor::grid;
+use crate::renderer::camera::Camera;
+use crate::tilemap::Tilemap;
+use egui::{Color32, ImageButton, Painter, Rect, Shape, Stroke, Ui};
+use glam::{Vec2, Vec4, Vec4Swizzles};
+use hecs::{Entity, World};
+
+const PALETTE_TILE_SIZE: f32 = 32.0;
+const CURSOR_COLOR: Color32 = Color32::from_rgb(120, 255, 160);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TileTool {
+    Brush,
+    Erase,
+    Fill,
+}
+
+impl TileTool {
+    pub const ALL: [TileTool; 3] = [TileTool::Brush, TileTool::Erase, TileTool::Fill];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TileTool::Brush => "🖌 Brush",
+            TileTool::Erase => "⌫ Erase",
+            TileTool::Fill => "🌊 Fill",
+        }
+    }
+}
+
+/// Paints tiles into the selected [`Tilemap`] with the mouse, instead of selecting and moving
+/// entities in the scene view.
+pub(crate) struct TilePainter {
+    pub active: bool,
+    pub tool: TileTool,
+    /// The tile painted by the brush and fill tools.
+    pub tile: u32,
+    /// The tilemap as it was when the current stroke started, for the undo stack.
+    pub before: Option<String>,
+}
+
+impl Default for TilePainter {
+    fn default() -> Self {
+        Self {
+            active: false,
+            tool: TileTool::Brush,
+            tile: 0,
+            before: None,
+        }
+    }
+}
+
+impl TilePainter {
+    /// Uses the tool on the cell at `position` in the world, returning `true` if the tilemap
+    /// changed.
+    pub fn paint(&self, world: &World, entity: Entity, position: Vec2) -> bool {
+        let (transform, mut tilemap) = match (
+            world.get::<GlobalTransform>(entity),
+            world.get_mut::<Tilemap>(entity),
+        ) {
+            (Ok(transform), Ok(tilemap)) => (transform, tilemap),
+            _ => return false,
+        };
+
+        let (column, row) = match tilemap.cell_at(&transform.inverse, position) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        match self.tool {
+            TileTool::Brush => tilemap.set(column, row, Some(self.tile)),
+            TileTool::Erase => tilemap.set(column, row, None),
+            TileTool::Fill => tilemap.fill(column, row, Some(self.tile)),
+        }
+    }
+
+    /// Shows the tools and a palette of the tileset's tiles to pick from.
+    pub fn ui(
+        &mut self,
+        ui: &mut Ui,
+        tilemap: &Tilemap,
+        tileset: Option<(egui::TextureId, u32, u32)>,
+    ) {
+        ui.toggle_value(&mut self.active, "Paint Tiles")
+            .on_hover_text("Paint into the tilemap with the mouse in the scene view");
+        if !self.active {
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            for tool in TileTool::ALL {
+                ui.selectable_value(&mut self.tool, tool, tool.name());
+            }
+        });
+
+        let texture_id = match tileset {
+            Some((texture_id, _, _)) => texture_id,
+            None => {
+                ui.label("The tileset hasn't been loaded.");
+                return;
+            }
+        };
+        egui::Grid::new("Tile Palette")
+            .spacing(egui::vec2(2.0, 2.0))
+            .show(ui, |ui| {
+                for tile in 0..tilemap.tile_count() {
+                    let (uv_min, uv_max) = tilemap.tile_uvs(tile);
+                    let uv = Rect::from_min_max(
+                        egui::pos2(uv_min.x, uv_min.y),
+                        egui::pos2(uv_max.x, uv_max.y),
+                    );
+                    let button = ImageButton::new(texture_id, egui::Vec2::splat(PALETTE_TILE_SIZE))
+                        .uv(uv)
+                        .selected(self.tile == tile);
+                    if ui.add(button).clicked() {
+                        self.tile = tile;
+                        if self.tool == TileTool::Erase {
+                            self.tool = TileTool::Brush;
+                        }
+                    }
+
+                    if (tile + 1) % tilemap.tileset_columns.max(1) == 0 {
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
+    /// Outlines the cell under the mouse, as shown in `rect` by `camera`.
+    pub fn draw_cursor(
+        &self,
+        painter: &Painter,
+        rect: Rect,
+        camera: &Camera,
+        world: &World,
+        entity: Entity,
+        position: Vec2,
+    ) {
+        let (transform, tilemap) = match (
+            world.get::<GlobalTransform>(entity),
+            world.get::<Tilemap>(entity),
+        ) {
+            (Ok(transform), Ok(tilemap)) => (transform, tilemap),
+            _ => return,
+        };
+        let (column, row) = match tilemap.cell_at(&transform.inverse, position) {
+            Some(cell) => cell,
+            None => return,
+        };
+
+        let matrix = tilemap.cell_matrix(&transform.matrix, column, row);
+        let to_screen = grid::world_to_screen(camera, rect);
+        let corners = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y]
+            .iter()
+            .map(|corner| to_screen((matrix * Vec4::from((*corner, 0.0, 1.0))).xy()))
+            .collect();
+        let stroke = Stroke::new(1.0, CURSOR_COLOR);
+        painter.add(Shape::convex_polygon(corners, Color32::TRANSPARENT, stroke));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::components::Guid;
+use crate::hierarchy;
+use crate::query;
+use crate::registry::ComponentRegistry;
+use crate::scene::SceneDescription;
+use hecs::{Entity, EntityBuilder, World};
+use std::time::{Duration, Instant};
+
+/// Edits to the same component closer together than this are undone in one go, so dragging a
+/// slider or typing a tag isn't undone a frame or a character at a time.
+const MERGE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Something done in the editor which can be undone and redone.
+///
+/// Entities are recreated when a delete is undone, so commands refer to them by [`Guid`] rather
+/// than by [`Entity`], which would go stale.
+enum Command {
+    /// A component was edited, added or removed, where `None` means the entity didn't have it.
+    Component {
+        guid: Guid,
+        name: &'static str,
+        before: Option<String>,
+        after: Option<String>,
+    },
+    Spawn(Snapshot),
+    Delete(Snapshot),
+    Reparent {
+        guid: Guid,
+        before: Option<Guid>,
+        after: Option<Guid>,
+    },
+}
+
+/// An entity and its descendants, as they were when they were spawned or before they were deleted.
+struct Snapshot {
+    guid: Guid,
+    parent: Option<Guid>,
+    scene: SceneDescription,
+}
+
+#[derive(Default)]
+pub(crate) struct UndoStack {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    last_recorded: Option<Instant>,
+}
+
+impl UndoStack {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forgets everything, e.g. when another scene is opened.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_recorded = None;
+    }
+
+    /// Records a change to one of an entity's components, given its serialised value before and
+    /// after the change.
+    pub fn record_component(
+        &mut self,
+        world: &World,
+        entity: Entity,
+        name: &'static str,
+        before: Option<String>,
+        after: Option<String>,
+    ) {
+        let guid = match world.get::<Guid>(entity) {
+            Ok(guid) => *guid,
+            Err(_) => return,
+        };
+
+        let recent = self
+            .last_recorded
+            .map_or(false, |at| at.elapsed() < MERGE_INTERVAL);
+        if let Some(Command::Component {
+            guid: g,
+            name: n,
+            after: a,
+            ..
+        }) = self.undo.last_mut().filter(|_| recent)
+        {
+            if *g == guid && *n == name {
+                *a = after;
+                self.redo.clear();
+                self.last_recorded = Some(Instant::now());
+                return;
+            }
+        }
+
+        self.record(Command::Component {
+            guid,
+            name,
+            before,
+            after,
+        });
+    }
+
+    /// Records an entity which has just been spawned.
+    pub fn record_spawn(&mut self, world: &World, registry: &ComponentRegistry, entity: Entity) {
+        if let Some(snapshot) = Snapshot::capture(world, registry, entity) {
+            self.record(Command::Spawn(snapshot));
+        }
+    }
+
+    /// Records an entity which is about to be deleted, along with its descendants.
+    pub fn record_delete(&mut self, world: &World, registry: &ComponentRegistry, entity: Entity) {
+        if let Some(snapshot) = Snapshot::capture(world, registry, entity) {
+            self.record(Command::Delete(snapshot));
+        }
+    }
+
+    /// Records an entity which has just been moved from the `before` parent to its current one.
+    pub fn record_reparent(&mut self, world: &World, entity: Entity, before: Option<Entity>) {
+        let guid = |entity: Entity| world.get::<Guid>(entity).ok().map(|guid| *guid);
+        if let Some(moved) = guid(entity) {
+            self.record(Command::Reparent {
+                guid: moved,
+                before: before.and_then(guid),
+                after: hierarchy::parent(world, entity).and_then(guid),
+            });
+        }
+    }
+
+    /// Undoes the most recent command, returning `false` if there was nothing to undo.
+    pub fn undo(&mut self, world: &mut World, registry: &ComponentRegistry) -> bool {
+        self.last_recorded = None;
+        match self.undo.pop() {
+            Some(command) => {
+                command.apply(world, registry, true);
+                self.redo.push(command);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redoes the most recently undone command, returning `false` if there was nothing to redo.
+    pub fn redo(&mut self, world: &mut World, registry: &ComponentRegistry) -> bool {
+        self.last_recorded = None;
+        match self.redo.pop() {
+            Some(command) => {
+                command.apply(world, registry, false);
+                self.undo.push(command);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn record(&mut self, command: Command) {
+        self.undo.push(command);
+        self.redo.clear();
+        self.last_recorded = Some(Instant::now());
+    }
+}
+
+impl Command {
+    /// Does the command again, or with `reverse`, undoes it.
+    fn apply(&self, world: &mut World, registry: &ComponentRegistry, reverse: bool) {
+        match self {
+            Command::Component {
+                guid,
+                name,
+                before,
+                after,
+            } => {
+                let value = if reverse { before } else { after };
+                if let (Some(entity), Some(registration)) =
+                    (query::find_by_guid(world, *guid), registry.get(name))
+                {
+                    match value {
+                        Some(value) => {
+                            let mut builder = EntityBuilder::new();
+                            if (registration.deserialise)(value, &mut builder) {
+                                world.insert(entity, builder.build()).ok();
+                            }
+                        }
+                        None => (registration.remove)(world, entity),
+                    }
+                }
+            }
+            Command::Spawn(snapshot) if reverse => snapshot.despawn(world),
+            Command::Spawn(snapshot) => snapshot.restore(world, registry),
+            Command::Delete(snapshot) if reverse => snapshot.restore(world, registry),
+            Command::Delete(snapshot) => snapshot.despawn(world),
+            Command::Reparent {
+                guid,
+                before,
+                after,
+            } => {
+                let parent = if reverse { before } else { after };
+                if let Some(entity) = query::find_by_guid(world, *guid) {
+                    let parent = parent.and_then(|parent| query::find_by_guid(world, parent));
+                    hierarchy::set_parent(world, entity, parent);
+                }
+            }
+        }
+    }
+}
+
+impl Snapshot {
+    fn capture(world: &World, registry: &ComponentRegistry, entity: Entity) -> Option<Self> {
+        let guid = *world.get::<Guid>(entity).ok()?;
+        let parent = hierarchy::parent(world, entity)
+            .and_then(|parent| world.get::<Guid>(parent).ok().map(|guid| *guid));
+
+        Some(Self {
+            guid,
+            parent,
+            scene: SceneDescription::capture(world, registry, entity),
+        })
+    }
+
+    fn restore(&self, world: &mut World, registry: &ComponentRegistry) {
+        self.scene.spawn(world, registry);
+
+        let parent = self
+            .parent
+            .and_then(|parent| query::find_by_guid(world, parent));
+        if let (Some(entity), Some(parent)) = (query::find_by_guid(world, self.guid), parent) {
+            hierarchy::set_parent(world, entity, Some(parent));
+        }
+    }
+
+    fn despawn(&self, world: &mut World) {
+        if let Some(entity) = query::find_by_guid(world, self.guid) {
+            hierarchy::despawn(world, entity);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::components::{Tag, Transform};
+use crate::editor::templates::EntityTemplate;
+use crate::query;
+use crate::registry::{ComponentRegistry, Reflect};
+use glam::Vec2;
+use hecs::{Entity, EntityBuilder, World};
+use log::warn;
+use std::fs;
+
+const FILENAME: &str = "alpha_prefab_variants.txt";
+
+/// An entity template with some of its components overridden, created from an entity in the
+/// scene. Only the overrides are stored, so changes to the template carry through to its variants
+/// wherever they don't override them. The entity's children aren't part of the variant.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PrefabVariant {
+    pub name: String,
+    pub base: EntityTemplate,
+    /// The components the variant sets, by name, with `None` for those of the base it removes.
+    pub overrides: Vec<(String, Option<String>)>,
+}
+
+impl PrefabVariant {
+    /// Captures how `entity` differs from an entity freshly spawned from `base`. Tags are left
+    /// out, as each entity spawned from the variant is tagged with its name.
+    pub fn capture(
+        name: &str,
+        base: EntityTemplate,
+        world: &World,
+        registry: &ComponentRegistry,
+        entity: Entity,
+    ) -> Self {
+        let mut scratch = World::new();
+        let base_entity = base.spawn(&mut scratch, Vec2::ZERO);
+
+        let overrides = registry
+            .iter()
+            .filter(|registration| registration.name != Tag::NAME)
+            .filter_map(|registration| {
+                let base_value = (registration.serialise)(&scratch, base_entity);
+                let value = (registration.serialise)(world, entity);
+                match (base_value, value) {
+                    (base_value, value) if base_value == value => None,
+                    (_, Some(value)) => Some((registration.name.to_string(), Some(value))),
+                    (Some(_), None) => Some((registration.name.to_string(), None)),
+                    (None, None) => None,
+                }
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            base,
+            overrides,
+        }
+    }
+
+    /// Spawns the base template with the overrides applied, with its bottom left corner at
+    /// `position`.
+    pub fn spawn(&self, world: &mut World, registry: &ComponentRegistry, position: Vec2) -> Entity {
+        let entity = self.base.spawn(world, position);
+
+        for (name, value) in &self.overrides {
+            let registration = match registry.get(name) {
+                Some(registration) => registration,
+                None => {
+                    warn!("unknown component {}", name);
+                    continue;
+                }
+            };
+
+            match value {
+                Some(value) => {
+                    let mut builder = EntityBuilder::new();
+                    if (registration.deserialise)(value, &mut builder) {
+                        world.insert(entity, builder.build()).ok();
+                    } else {
+                        warn!("invalid {} component: {}", name, value);
+                    }
+                }
+                None => (registration.remove)(world, entity),
+            }
+        }
+
+        if let Ok(mut transform) = world.get_mut::<Transform>(entity) {
+            transform.position = position;
+        }
+        let tag = query::unique_tag(world, &self.name);
+        world.insert_one(entity, Tag(tag)).ok();
+
+        entity
+    }
+}
+
+/// The prefab variants created in the editor, kept between sessions in a file in the working
+/// directory. Each is a block of lines, separated by `---` lines:
+///
+/// ```text
+/// Variant: Green Rect
+/// Base: Rect
+/// Shape: 0 1 0 1
+/// Remove: Visible
+/// ---
+/// ```
+#[derive(Default)]
+pub(crate) struct PrefabVariants {
+    pub variants: Vec<PrefabVariant>,
+}
+
+impl PrefabVariants {
+    pub fn load() -> Self {
+        let contents = match fs::read_to_string(FILENAME) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+
+        let mut variants = Vec::new();
+        for block in contents.split("---\n") {
+            let mut name = None;
+            let mut base = None;
+            let mut overrides = Vec::new();
+            for line in block.lines().filter(|line| !line.trim().is_empty()) {
+                let (key, value) = match line.split_once(": ") {
+                    Some(pair) => pair,
+                    None => {
+                        warn!("ignoring invalid prefab variant line: {}", line);
+                        continue;
+                    }
+                };
+                match key {
+                    "Variant" => name = Some(value.to_string()),
+                    "Base" => base = EntityTemplate::parse(value),
+                    "Remove" => overrides.push((value.to_string(), None)),
+                    _ => overrides.push((key.to_string(), Some(value.to_string()))),
+                }
+            }
+
+            match (name, base) {
+                (Some(name), Some(base)) => variants.push(PrefabVariant {
+                    name,
+                    base,
+                    overrides,
+                }),
+                (None, None) if overrides.is_empty() => {}
+                _ => warn!("ignoring prefab variant without a name and base"),
+            }
+        }
+
+        Self { variants }
+    }
+
+    /// Adds the variant, replacing any with the same name, and saves them all.
+    pub fn add(&mut self, variant: PrefabVariant) {
+        match self.variants.iter_mut().find(|v| v.name == variant.name) {
+            Some(existing) => *existing = variant,
+            None => self.variants.push(variant),
+        }
+
+        let mut contents = String::new();
+        for variant in &self.variants {
+            contents.push_str(&format!("Variant: {}\n", variant.name));
+            contents.push_str(&format!("Base: {}\n", variant.base.name()));
+            for (name, value) in &variant.overrides {
+                match value {
+                    Some(value) => contents.push_str(&format!("{}: {}\n", name, value)),
+                    None => contents.push_str(&format!("Remove: {}\n", name)),
+                }
+            }
+            contents.push_str("---\n");
+        }
+        if let Err(e) = fs::write(FILENAME, contents) {
+            warn!("unable to save prefab variants: {}", e);
+        }
+    }
+}
+
+/// The window for creating a prefab variant from the selected entity.
+pub(crate) struct VariantDialog {
+    pub open: bool,
+    pub name: String,
+    pub base: EntityTemplate,
+}
+
+impl Default for VariantDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            name: String::from("Variant"),
+            base: EntityTemplate::Rect,
+        }
+    }
+}
@@ -1,11 +1,42 @@
 use crate::error::Error;
+use crate::platform::WindowMode;
+use crate::project::ProjectSettings;
 use crate::renderer::Renderer;
-use crate::{logging, platform, renderer};
-use winit::event::{Event, WindowEvent};
+use crate::time::Time;
+#[cfg(target_arch = "wasm32")]
+use crate::web;
+use crate::{alloc_tracker, platform, renderer};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{crash, logging};
+use instant::Instant;
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use winit::event::{Event, StartCause, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
+#[cfg(not(target_arch = "wasm32"))]
+use winit::platform::run_return::EventLoopExtRunReturn;
 use winit::window::Window;
 use winit_input_helper::WinitInputHelper;
 
+/// How often an idle application is updated anyway, so that timers and file watchers still run.
+pub const IDLE_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What an application is given to update with.
+pub struct UpdateContext<'a> {
+    pub input: &'a WinitInputHelper,
+    pub time: &'a Time,
+}
+
+/// What an application is given to draw a frame with.
+pub struct FrameContext<'a> {
+    pub window: &'a Window,
+    pub renderer: &'a mut Renderer,
+    /// For user interfaces, which are laid out as they're drawn.
+    pub input: &'a WinitInputHelper,
+    pub time: &'a Time,
+}
+
 pub trait CreateApplication {
     type App: Application;
 
@@ -21,14 +52,84 @@ pub trait Application {
 
     fn on_event(&mut self, event: &Event<()>);
 
-    fn on_update(
-        &mut self,
-        window: &Window,
-        renderer: &mut Renderer,
-        input: &WinitInputHelper,
-    ) -> Result<(), Error>;
+    /// Called every [`Time::FIXED_DELTA`] before [`Application::on_update`], several times to
+    /// catch up after a slow frame or not at all after a fast one, so that physics and gameplay
+    /// logic behave the same whatever the frame rate.
+    fn on_fixed_update(&mut self, _time: &Time) {}
+
+    /// Called once a frame to move the application on by [`Time::delta`].
+    fn on_update(&mut self, ctx: &UpdateContext) -> Result<(), Error>;
+
+    /// Called after [`Application::on_update`] to draw the frame. Frames aren't always drawn, so
+    /// the simulation should only move on in `on_update`.
+    fn on_render(&mut self, ctx: &mut FrameContext) -> Result<(), Error>;
+
+    /// Called when [`Application::on_update`] or [`Application::on_render`] fails, after the error
+    /// has been logged. Returning `true` carries on running, otherwise the application is stopped
+    /// and the engine returns the error.
+    fn on_error(&mut self, _error: &Error) -> bool {
+        false
+    }
 
     fn on_stop(&mut self);
+
+    /// Called when the user asks to close the window. Returning `false` keeps it open, e.g. to ask
+    /// about unsaved changes first.
+    fn on_close_requested(&mut self) -> bool {
+        true
+    }
+
+    /// How fast time passes for the application, which is applied to the [`Time`] before each
+    /// frame. See [`Time::scale`].
+    fn time_scale(&self) -> f32 {
+        1.0
+    }
+
+    /// The most updates each second, or `None` to update as often as possible.
+    fn frame_rate_limit(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether to stop updating while the window is minimised, rather than carrying on without
+    /// drawing.
+    fn pause_when_minimized(&self) -> bool {
+        false
+    }
+
+    /// Checked after each update. Returning `true` means nothing will change until there's input,
+    /// so the engine sleeps until then, or until [`IDLE_UPDATE_INTERVAL`] has passed.
+    fn is_idle(&self) -> bool {
+        false
+    }
+
+    /// The mode the window should be in, checked after each update, or `None` to leave it in
+    /// whichever mode it's in. The window only changes mode when this does, so the user can still
+    /// switch with Alt+Enter.
+    fn requested_window_mode(&self) -> Option<WindowMode> {
+        None
+    }
+
+    /// The monitor to go fullscreen on, numbered from 0, or `None` for the one the window is on.
+    fn fullscreen_monitor(&self) -> Option<usize> {
+        None
+    }
+
+    /// The window's title, checked after each update, or `None` to leave it as it is.
+    fn window_title(&self) -> Option<&str> {
+        None
+    }
+
+    /// A PNG to use as the window's icon, checked after each update, or `None` to leave it as it
+    /// is.
+    fn window_icon(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Checked before each update. Returning `true` closes the application, as if the window had
+    /// been closed, after calling [`Application::on_stop`].
+    fn exit_requested(&self) -> bool {
+        false
+    }
 }
 
 pub struct Engine<App>
@@ -40,17 +141,37 @@ where
     input: Option<WinitInputHelper>,
     renderer: Option<Renderer>,
     window: Option<Window>,
+    time: Time,
+    started: bool,
+    minimized: bool,
+    window_state: WindowState,
 }
 
 impl<App> Engine<App>
 where
     App: CreateApplication + 'static,
 {
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn init() -> Result<Self, Error> {
-        logging::init("info")?;
+        pollster::block_on(Self::init_async())
+    }
+
+    /// As [`Engine::init`], for the browser, where the renderer can only be waited for
+    /// asynchronously. Logging and crash reports go to the browser's console there.
+    pub async fn init_async() -> Result<Self, Error> {
+        let settings = ProjectSettings::load(ProjectSettings::FILENAME);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            logging::init(&settings.logging)?;
+            crash::install(&settings);
+        }
+        #[cfg(target_arch = "wasm32")]
+        web::init(&settings.logging);
         let (event_loop, window, input) = platform::init()?;
+        #[cfg(not(target_arch = "wasm32"))]
+        crash::set_windowed(true);
 
-        let renderer = renderer::init(&window)?;
+        let renderer = renderer::init_async(&window).await?;
 
         let application = App::create(&window, &event_loop, &renderer)?;
 
@@ -60,45 +181,400 @@ where
             input: Some(input),
             renderer: Some(renderer),
             window: Some(window),
+            time: Time::default(),
+            started: false,
+            minimized: false,
+            window_state: WindowState::default(),
         };
 
         Ok(engine)
     }
 
+    /// Creates an engine which draws into a window belonging to the host's own event loop. Rather
+    /// than calling [`Engine::run`], the host drives the engine by calling [`Engine::frame`].
+    ///
+    /// Unlike [`Engine::init`], this leaves setting up logging to the host.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn embed(window: Window, event_loop: &EventLoop<()>) -> Result<Self, Error> {
+        let input = WinitInputHelper::new();
+
+        let renderer = renderer::init(&window)?;
+
+        let application = App::create(&window, event_loop, &renderer)?;
+
+        let engine = Engine {
+            application: Some(application),
+            event_loop: None,
+            input: Some(input),
+            renderer: Some(renderer),
+            window: Some(window),
+            time: Time::default(),
+            started: false,
+            minimized: false,
+            window_state: WindowState::default(),
+        };
+
+        Ok(engine)
+    }
+
+    /// Updates an embedded engine with the window events received since the previous frame, and
+    /// `dt`, the time which has passed since then by the host's own clock. Time spent minimised is
+    /// up to the host to leave out of `dt`.
+    ///
+    /// Returns `false` once the window has been asked to close, at which point the host should
+    /// call [`Engine::stop`].
+    pub fn frame(&mut self, dt: Duration, events: &[Event<()>]) -> Result<bool, Error> {
+        let app = self.application.as_mut().unwrap();
+        let input = self.input.as_mut().unwrap();
+        let renderer = self.renderer.as_mut().unwrap();
+        let window = self.window.as_ref().unwrap();
+
+        if !self.started {
+            app.on_start(None);
+            self.started = true;
+        }
+
+        // The host's event loop decides where frames begin and end, so mark them out for the input
+        // helper ourselves.
+        input.update(&Event::<()>::NewEvents(StartCause::Poll));
+        for event in events {
+            if matches!(event, Event::NewEvents(_) | Event::MainEventsCleared) {
+                continue;
+            }
+
+            resize(renderer, window, event);
+            track_minimized(&mut self.minimized, event);
+            app.on_event(event);
+            input.update(event);
+        }
+        input.update(&Event::<()>::MainEventsCleared);
+        self.window_state.update(app, input, window, renderer);
+
+        if input.quit() || app.exit_requested() {
+            return Ok(false);
+        }
+
+        let mut result = Ok(());
+        if !(self.minimized && app.pause_when_minimized()) {
+            self.time.set_scale(app.time_scale());
+            self.time.advance(dt);
+            while self.time.next_fixed_step() {
+                app.on_fixed_update(&self.time);
+            }
+            result = app.on_update(&UpdateContext {
+                input,
+                time: &self.time,
+            });
+        }
+        if result.is_ok() && !self.minimized {
+            result = app.on_render(&mut FrameContext {
+                window,
+                renderer,
+                input,
+                time: &self.time,
+            });
+        }
+
+        alloc_tracker::end_frame();
+
+        if let Err(e) = result {
+            if !handle_error(app, &e) {
+                return Err(e);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Stops an embedded engine's application and waits for the GPU to finish drawing. Does
+    /// nothing if it hasn't been started.
+    pub fn stop(&mut self) {
+        if let Some(app) = &mut self.application {
+            if self.started {
+                app.on_stop();
+                self.started = false;
+            }
+        }
+        if let Some(renderer) = &self.renderer {
+            renderer.wait_idle();
+        }
+    }
+
+    /// Runs the application until it exits or its window is closed.
+    ///
+    /// Once the event loop has finished, the application is stopped, the GPU finishes any work it
+    /// was given, and then the application, renderer and window are dropped in that order, so that
+    /// nothing is torn down while something else still depends on it.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn run(&mut self) -> Result<(), Error> {
-        let mut app = self.application.take().unwrap();
+        let mut event_loop = self.event_loop.take().unwrap();
+        let mut runner = self.runner();
+
+        event_loop.run_return(|event, _, control_flow| runner.handle(event, control_flow));
+
+        runner.shut_down()
+    }
+
+    /// Runs the application in the browser, which takes over the event loop for good, so this
+    /// never returns. The application is stopped when the page closes the event loop.
+    #[cfg(target_arch = "wasm32")]
+    pub fn run(mut self) -> !
+    where
+        App::App: 'static,
+    {
         let event_loop = self.event_loop.take().unwrap();
-        let mut input = self.input.take().unwrap();
-        let mut renderer = self.renderer.take().unwrap();
-        let window = self.window.take().unwrap();
+        let mut runner = Some(self.runner());
 
+        event_loop.run(move |event, _, control_flow| {
+            if let Event::LoopDestroyed = event {
+                if let Some(Err(e)) = runner.take().map(Runner::shut_down) {
+                    error!("{}", e);
+                }
+                return;
+            }
+            if let Some(runner) = &mut runner {
+                runner.handle(event, control_flow);
+            }
+        })
+    }
+
+    fn runner(&mut self) -> Runner<App::App> {
+        let mut app = self.application.take().unwrap();
         app.on_start(None);
 
-        event_loop.run(move |event, _, control_flow| {
-            if let Event::WindowEvent {
-                event: WindowEvent::Resized(size),
-                ..
-            } = event
-            {
-                renderer.resize(size.width, size.height, window.scale_factor());
+        Runner {
+            app,
+            input: self.input.take().unwrap(),
+            renderer: self.renderer.take().unwrap(),
+            window: self.window.take().unwrap(),
+            time: self.time,
+            minimized: self.minimized,
+            window_state: std::mem::take(&mut self.window_state),
+            failure: None,
+        }
+    }
+}
+
+/// Everything a running event loop owns, so the same loop can be run natively, where it returns
+/// once it's finished, and in the browser, where it doesn't.
+struct Runner<A: Application> {
+    app: A,
+    input: WinitInputHelper,
+    renderer: Renderer,
+    window: Window,
+    time: Time,
+    minimized: bool,
+    window_state: WindowState,
+    failure: Option<Error>,
+}
+
+impl<A: Application> Runner<A> {
+    fn handle(&mut self, event: Event<()>, control_flow: &mut ControlFlow) {
+        if *control_flow == ControlFlow::Exit {
+            return;
+        }
+
+        let Runner {
+            app,
+            input,
+            renderer,
+            window,
+            time,
+            minimized,
+            window_state,
+            failure,
+        } = self;
+
+        resize(renderer, window, &event);
+        if track_minimized(minimized, &event) && app.pause_when_minimized() {
+            time.resume();
+        }
+
+        app.on_event(&event);
+
+        let processed_all_events = input.update(&event);
+
+        if processed_all_events {
+            window_state.update(&*app, input, window, renderer);
+
+            if (input.quit() && app.on_close_requested()) || app.exit_requested() {
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+
+            if *minimized && app.pause_when_minimized() {
+                // Nothing happens until the window is restored, so there's no need to wake
+                // up until there's another event.
+                *control_flow = ControlFlow::Wait;
+                return;
             }
 
-            app.on_event(&event);
+            let frame_start = Instant::now();
+            time.set_scale(app.time_scale());
+            time.tick();
+            while time.next_fixed_step() {
+                app.on_fixed_update(time);
+            }
+            let result = app.on_update(&UpdateContext { input, time }).and_then(|_| {
+                if *minimized {
+                    return Ok(());
+                }
+                app.on_render(&mut FrameContext {
+                    window,
+                    renderer,
+                    input,
+                    time,
+                })
+            });
 
-            let processed_all_events = input.update(&event);
+            alloc_tracker::end_frame();
 
-            if processed_all_events {
-                if input.quit() {
+            if let Err(e) = result {
+                if !handle_error(app, &e) {
+                    *failure = Some(e);
                     *control_flow = ControlFlow::Exit;
-                    app.on_stop();
                     return;
                 }
+            }
 
-                app.on_update(&window, &mut renderer, &input)
-                    .expect("Handle error - exit or recover?"); // TODO
+            *control_flow = if app.is_idle() {
+                ControlFlow::WaitUntil(Instant::now() + IDLE_UPDATE_INTERVAL)
+            } else {
+                match app.frame_rate_limit() {
+                    Some(fps) if fps > 0 => ControlFlow::WaitUntil(
+                        frame_start + Duration::from_secs_f64(1.0 / fps as f64),
+                    ),
+                    _ => ControlFlow::Poll,
+                }
+            };
+        }
+    }
+
+    fn shut_down(self) -> Result<(), Error> {
+        let Runner {
+            mut app,
+            renderer,
+            window,
+            failure,
+            ..
+        } = self;
+
+        app.on_stop();
+        renderer.wait_idle();
+
+        drop(app);
+        drop(renderer);
+        drop(window);
+        info!("engine shut down");
+
+        failure.map_or(Ok(()), Err)
+    }
+}
+
+/// What the window is like, and what the application last asked for it to be like, so the
+/// window is only changed when the application asks for something different.
+#[derive(Clone, Debug, Default)]
+struct WindowState {
+    mode: WindowMode,
+    requested_mode: Option<WindowMode>,
+    monitor: Option<usize>,
+    title: Option<String>,
+    icon: Option<PathBuf>,
+}
+
+impl WindowState {
+    /// Switches between windowed and borderless on Alt+Enter, or to the mode the application asks
+    /// for when it asks for a different one, and changes the title and icon when they change.
+    fn update(
+        &mut self,
+        app: &impl Application,
+        input: &WinitInputHelper,
+        window: &Window,
+        renderer: &mut Renderer,
+    ) {
+        let alt = input.key_held(VirtualKeyCode::LAlt) || input.key_held(VirtualKeyCode::RAlt);
+        let mode = if alt && input.key_pressed(VirtualKeyCode::Return) {
+            match self.mode {
+                WindowMode::Windowed => WindowMode::Borderless,
+                _ => WindowMode::Windowed,
             }
+        } else {
+            match app.requested_window_mode() {
+                Some(mode) if Some(mode) != self.requested_mode => {
+                    self.requested_mode = Some(mode);
+                    mode
+                }
+                _ => self.mode,
+            }
+        };
+        let monitor = app.fullscreen_monitor();
+        let moved = monitor != self.monitor && mode != WindowMode::Windowed;
+        self.monitor = monitor;
+        if mode != self.mode || moved {
+            self.mode = mode;
+            platform::set_window_mode(window, mode, monitor);
+            // The surface has to match the window's new size before the next frame is drawn.
+            let size = window.inner_size();
+            renderer.resize(size.width, size.height, window.scale_factor());
+        }
+
+        if let Some(title) = app.window_title() {
+            if self.title.as_deref() != Some(title) {
+                platform::set_title(window, title);
+                self.title = Some(title.to_string());
+            }
+        }
+
+        if let Some(icon) = app.window_icon() {
+            if self.icon.as_deref() != Some(icon) {
+                if let Err(e) = platform::set_icon(window, icon) {
+                    error!("{}", e);
+                }
+                // Failures aren't tried again until the icon changes.
+                self.icon = Some(icon.to_path_buf());
+            }
+        }
+    }
+}
+
+/// Logs an error from the application, returning whether the application wants to carry on.
+fn handle_error(app: &mut impl Application, error: &Error) -> bool {
+    error!("{}", error);
+    app.on_error(error)
+}
+
+/// Notes when the window is minimised or restored, returning `true` when it's restored. Windows
+/// are resized to nothing while they're minimised.
+fn track_minimized(minimized: &mut bool, event: &Event<()>) -> bool {
+    if let Event::WindowEvent {
+        event: WindowEvent::Resized(size),
+        ..
+    } = event
+    {
+        let was_minimized = *minimized;
+        *minimized = size.width == 0 || size.height == 0;
+        return was_minimized && !*minimized;
+    }
+
+    false
+}
 
-            *control_flow = ControlFlow::Poll;
-        });
+/// Keeps the renderer the same size as the window, and at the same scale factor, including when
+/// the window moves to a monitor with a different one.
+fn resize(renderer: &mut Renderer, window: &Window, event: &Event<()>) {
+    match event {
+        Event::WindowEvent {
+            event: WindowEvent::Resized(size),
+            ..
+        } => renderer.resize(size.width, size.height, window.scale_factor()),
+        Event::WindowEvent {
+            event:
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    new_inner_size,
+                },
+            ..
+        } => renderer.resize(new_inner_size.width, new_inner_size.height, *scale_factor),
+        _ => {}
     }
 }
@@ -1,7 +1,85 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[cfg(not(target_arch = "wasm32"))]
     #[error("logging system failure")]
     Logging(#[from] flexi_logger::FlexiLoggerError),
+    #[error("unable to access {0}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("unable to create window")]
+    Window(#[from] winit::error::OsError),
+    #[cfg(target_arch = "wasm32")]
+    #[error("unable to add the game's canvas to the page")]
+    Canvas,
+    #[cfg(target_arch = "wasm32")]
+    #[error("unable to fetch {0}: {1}")]
+    Fetch(PathBuf, String),
+    #[error("unable to use {0} as the window icon")]
+    Icon(PathBuf, #[source] winit::window::BadIcon),
+    #[error("unable to load audio clip {0}")]
+    AudioClip(PathBuf, #[source] std::io::Error),
+    #[error("unable to decode audio clip")]
+    AudioDecode(#[from] rodio::decoder::DecoderError),
+    #[error("unable to play audio clip")]
+    AudioPlay(#[from] rodio::PlayError),
+    #[error("unable to load scene {0}")]
+    SceneLoad(PathBuf, #[source] std::io::Error),
+    #[error("unable to save scene {0}")]
+    SceneSave(PathBuf, #[source] std::io::Error),
+    #[error("unable to parse scene at line {0}: unexpected '{1}'")]
+    SceneParse(usize, String),
+    #[error("unsupported scene version {0}")]
+    SceneVersion(u32),
+    #[error("unable to migrate scene {0}")]
+    SceneMigration(PathBuf, #[source] std::io::Error),
+    #[error("unable to load script {0}")]
+    ScriptLoad(PathBuf, #[source] std::io::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("unable to compile script {0}")]
+    ScriptCompile(PathBuf, #[source] wasmer::CompileError),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("unable to instantiate script {0}")]
+    ScriptInstantiate(PathBuf, #[source] wasmer::InstantiationError),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("unable to set up WASI for script {0}")]
+    ScriptWasi(PathBuf, #[source] wasmer_wasi::WasiStateCreationError),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("script {0} failed")]
+    ScriptRuntime(PathBuf, #[source] wasmer::RuntimeError),
+    #[error("script {0} ran out of fuel")]
+    ScriptFuel(PathBuf),
+    #[cfg(all(feature = "lua", not(target_arch = "wasm32")))]
+    #[error("script {0} failed")]
+    ScriptLua(PathBuf, #[source] mlua::Error),
+    #[cfg(feature = "network")]
+    #[error("unable to open network socket")]
+    Network(#[source] std::io::Error),
+    #[error("no graphics adapter available")]
+    GraphicsAdapter,
+    #[error("renderer failure: {0}")]
+    Renderer(String),
+    #[error("unable to load asset {0}")]
+    Asset(PathBuf, #[source] std::io::Error),
+    #[error("unable to load texture {0}")]
+    Texture(PathBuf, #[source] image::ImageError),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("unable to build {0}")]
+    Build(PathBuf, #[source] std::io::Error),
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("unable to find the {0} runtime next to the editor")]
+    BuildRuntime(PathBuf),
+    #[error("no directory to keep saved games in")]
+    SaveDirectory,
+    #[error("unable to read saved game {0}")]
+    SaveRead(PathBuf, #[source] std::io::Error),
+    #[error("unable to write saved game {0}")]
+    SaveWrite(PathBuf, #[source] std::io::Error),
+    #[error("saved game {0} isn't valid")]
+    SaveFormat(PathBuf, #[source] serde_json::Error),
+    #[error("unable to parse scenario at line {0}: unexpected '{1}'")]
+    ScenarioParse(usize, String),
+    #[error("scenario assertion failed at line {0}: {1}")]
+    ScenarioAssertion(usize, String),
 }
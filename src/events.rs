@@ -0,0 +1,114 @@
+use crate::timers::TimerId;
+use hecs::Entity;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Queues of events of any type, so that systems, scripts and the editor can tell each other
+/// what happened without knowing about each other.
+///
+/// Events published during one frame can be read throughout the next, after which they're
+/// dropped. Reading doesn't use them up, so any number of systems can read the same events.
+#[derive(Default)]
+pub struct Events {
+    queues: HashMap<TypeId, Box<dyn Queue>>,
+}
+
+impl Events {
+    pub fn publish<T: 'static>(&mut self, event: T) {
+        self.queues
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(EventQueue::<T>::default()))
+            .as_any_mut()
+            .downcast_mut::<EventQueue<T>>()
+            .expect("queue should hold its own type of event")
+            .current
+            .push(event);
+    }
+
+    /// The events of type `T` which were published during the previous frame.
+    pub fn read<T: 'static>(&self) -> &[T] {
+        self.queues
+            .get(&TypeId::of::<T>())
+            .and_then(|queue| queue.as_any().downcast_ref::<EventQueue<T>>())
+            .map_or(&[], |queue| queue.previous.as_slice())
+    }
+
+    /// Makes the events published this frame readable, dropping the ones from the frame before.
+    pub(crate) fn next_frame(&mut self) {
+        for queue in self.queues.values_mut() {
+            queue.next_frame();
+        }
+    }
+}
+
+/// Lets [`Events`] move every type of event on to the next frame without knowing their types.
+trait Queue {
+    fn next_frame(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct EventQueue<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+}
+
+impl<T> Default for EventQueue<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+}
+
+impl<T: 'static> Queue for EventQueue<T> {
+    fn next_frame(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Published when the game switches to a scene.
+#[derive(Clone, Debug)]
+pub struct SceneLoaded {
+    pub path: PathBuf,
+}
+
+/// Published when the colliders of two entities start to overlap.
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionStarted {
+    pub a: Entity,
+    pub b: Entity,
+}
+
+/// Published when the colliders of two entities stop overlapping, or one of them is removed.
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionEnded {
+    pub a: Entity,
+    pub b: Entity,
+}
+
+/// Published when one of the [`Timers`](crate::Timers) fires, with the entity it belongs to if it
+/// does.
+#[derive(Clone, Copy, Debug)]
+pub struct TimerFired {
+    pub id: TimerId,
+    pub entity: Option<Entity>,
+}
+
+/// Published when a script calls `emit_event`, with the number the script passed to tell its
+/// events apart.
+#[derive(Clone, Copy, Debug)]
+pub struct ScriptEvent {
+    pub entity: Entity,
+    pub id: u32,
+}
@@ -0,0 +1,55 @@
+//! Reading the game's own files: its scenes, settings and assets. On the desktop they're read from
+//! disk as they're needed. The browser has no disk to read, so [`web::start`](crate::web::start)
+//! fetches them from the server before the game starts, and they're read from memory instead.
+
+#[cfg(target_arch = "wasm32")]
+use once_cell::sync::Lazy;
+#[cfg(target_arch = "wasm32")]
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+#[cfg(target_arch = "wasm32")]
+use std::path::{Component, PathBuf};
+#[cfg(target_arch = "wasm32")]
+use std::sync::Mutex;
+
+/// Lists every file in a build, one path per line relative to the build's directory, so the
+/// browser knows what to fetch.
+pub(crate) const MANIFEST: &str = "alpha_manifest.txt";
+
+/// The files fetched from the server, by their normalised paths.
+#[cfg(target_arch = "wasm32")]
+static FETCHED: Lazy<Mutex<HashMap<PathBuf, Vec<u8>>>> = Lazy::new(Default::default);
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn read(path: &Path) -> io::Result<Vec<u8>> {
+    std::fs::read(path)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn read(path: &Path) -> io::Result<Vec<u8>> {
+    FETCHED
+        .lock()
+        .unwrap()
+        .get(&normalise(path))
+        .cloned()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not in the build's manifest"))
+}
+
+pub(crate) fn read_to_string(path: &Path) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Keeps a file fetched from the server, to be read as though it were at `path`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn insert(path: &Path, bytes: Vec<u8>) {
+    FETCHED.lock().unwrap().insert(normalise(path), bytes);
+}
+
+/// Leaves out `.` components, so `./assets/a.png` and `assets/a.png` are the same file.
+#[cfg(target_arch = "wasm32")]
+fn normalise(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
@@ -1,14 +1,52 @@
-use crate::components::{Shape, Tag, Transform};
+use crate::alloc_tracker::{self, Subsystem};
+use crate::animation::{system_animation, system_sprite_animation};
+use crate::assets::{
+    system_assets, AssetWatcher, Assets, AudioFile, Image, LoadState, SceneFile, ScriptFile,
+};
+use crate::audio::{self, Audio};
+use crate::collision::Collisions;
+use crate::components::{
+    compute_inverse_space_matrix, compute_inverse_transformation_matrix, compute_space_matrix,
+    compute_transformation_matrix, Acceleration, Children, CollisionLayers, GlobalTransform,
+    Parent, Shape, Sprite, Transform, Velocity,
+};
+#[cfg(not(target_arch = "wasm32"))]
 use crate::editor::Pause;
-use crate::engine::{Application, CreateApplication};
+use crate::engine::{Application, CreateApplication, FrameContext, UpdateContext};
 use crate::error::Error;
+use crate::events::{Events, SceneLoaded};
+use crate::hierarchy;
+use crate::navigation::{system_navigation, Navigation};
+#[cfg(feature = "network")]
+use crate::network::{Client, Network, Server};
+#[cfg(feature = "physics")]
+use crate::physics::Physics;
+use crate::platform::WindowMode;
+use crate::project::{Features, ProjectSettings};
+use crate::random::Random;
+use crate::registry::ComponentRegistry;
 use crate::renderer::camera::Camera;
 use crate::renderer::{rect::Rect, Renderer};
-use glam::{Vec2, Vec4};
-use hecs::World;
-use log::info;
-use std::str::FromStr;
-use std::{fs, path};
+use crate::save::SaveData;
+use crate::scene::SceneManager;
+use crate::schedule::{DrawContext, Schedule, Stage, SystemContext};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scripting::SystemWasmer;
+use crate::sub_scene::SubScenes;
+use crate::tilemap::Tilemap;
+use crate::time::Time;
+use crate::timers::Timers;
+use crate::transition::{SceneTransition, Transition};
+use crate::ui::GameUi;
+#[cfg(target_arch = "wasm32")]
+use crate::web::SystemWasmer;
+use glam::Mat4;
+use hecs::{Entity, World};
+use instant::Instant;
+use log::{info, warn};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::window::Window;
@@ -16,23 +54,280 @@ use winit_input_helper::WinitInputHelper;
 
 pub struct Game {
     paused: bool,
+    exit_requested: bool,
+    pending_despawns: RefCell<Vec<Entity>>,
+    pub assets: Assets,
+    pub audio: Audio,
     pub camera: Camera,
+    collisions: Collisions,
+    /// When set, the world is drawn from this camera instead, so the editor can look around the
+    /// scene without moving the game's own camera.
+    pub editor_camera: Option<Camera>,
+    #[cfg(feature = "physics")]
+    physics: Physics,
+    /// Events published this frame, which systems, scripts and the editor can read next frame.
+    pub events: Events,
+    /// Finds paths around the world's obstacles, for entities with a
+    /// [`PathFollower`](crate::PathFollower) and for scripts.
+    pub navigation: Navigation,
+    /// Random numbers for the game's systems, which start again from the same seed whenever the
+    /// game is stopped.
+    pub random: Random,
+    /// Whether the game is serving its world to clients, or is a client of another game.
+    #[cfg(feature = "network")]
+    pub network: Option<Network>,
+    pub registry: ComponentRegistry,
+    /// Where the game's progress is saved, by the game and by its scripts.
+    saves: SaveData,
+    pub scenes: SceneManager,
+    /// Systems added to the game on top of the engine's own.
+    pub schedule: Schedule,
+    pub scripts: SystemWasmer,
+    pub settings: ProjectSettings,
+    sub_scenes: SubScenes,
+    /// The time of the current frame, for anything which needs it outside of the systems.
+    pub time: Time,
+    pub timers: Timers,
+    time_scale: f32,
+    /// The change of scene in progress, if there is one.
+    transition: Option<SceneTransition>,
+    window_icon: Option<PathBuf>,
+    window_mode: WindowMode,
+    window_title: String,
+    /// The game's HUD and menus, which its systems add widgets to each frame.
+    pub ui: GameUi,
+    /// How long each system took during the last update, including the fixed updates before it.
+    pub timings: Vec<(&'static str, Duration)>,
+    fixed_timings: Vec<(&'static str, Duration)>,
+    /// Reports asset files which have changed on disk, once the game is watching for them.
+    watcher: Option<AssetWatcher>,
     pub world: World,
 }
 
 impl Game {
     pub fn new(_window: &Window, renderer: &Renderer) -> Self {
+        let settings = ProjectSettings::load(ProjectSettings::FILENAME);
+        Self::with_settings(settings, renderer.width, renderer.height)
+    }
+
+    /// Creates a game which isn't shown in a window, with its camera `width` by `height` pixels.
+    /// Headless games don't play audio.
+    pub fn headless(width: u32, height: u32) -> Self {
+        let mut settings = ProjectSettings::load(ProjectSettings::FILENAME);
+        settings.features.audio = false;
+        Self::with_settings(settings, width, height)
+    }
+
+    fn with_settings(settings: ProjectSettings, width: u32, height: u32) -> Self {
         let paused = false;
-        let camera = Camera::new(renderer.width, renderer.height);
+        let pending_despawns = RefCell::new(Vec::new());
+        let audio = audio::init(settings.features.audio);
+        let camera = Camera::new(width, height);
 
+        let mut assets = Assets::default();
+        let sub_scenes = SubScenes::new(&mut assets);
+        let registry = ComponentRegistry::default();
+        let scenes = SceneManager::default();
+        let saves = SaveData::new(&settings.name);
+        let random = Random::default();
+        let mut scripts = SystemWasmer::new(settings.scripts);
+        scripts.set_save_data(saves.clone());
+        scripts.reseed(random.seed());
+        info!("random seed {}", random.seed());
+        let window_icon = settings.icon.clone();
+        let window_mode = settings.display.window_mode;
+        let window_title = settings.title.clone();
         let world = World::new();
 
         Self {
             paused,
+            exit_requested: false,
+            pending_despawns,
+            assets,
+            audio,
             camera,
+            collisions: Collisions::default(),
+            editor_camera: None,
+            #[cfg(feature = "physics")]
+            physics: Physics::default(),
+            events: Events::default(),
+            navigation: Navigation::default(),
+            random,
+            #[cfg(feature = "network")]
+            network: None,
+            registry,
+            saves,
+            scenes,
+            schedule: Schedule::default(),
+            scripts,
+            settings,
+            sub_scenes,
+            time: Time::default(),
+            timers: Timers::default(),
+            time_scale: 1.0,
+            transition: None,
+            window_icon,
+            window_mode,
+            window_title,
+            ui: GameUi::default(),
+            timings: Vec::new(),
+            fixed_timings: Vec::new(),
+            watcher: None,
             world,
         }
     }
+
+    /// Serves the world's [`Replicated`](crate::Replicated) entities to clients which connect to
+    /// `address`, e.g. `0.0.0.0:7777`.
+    #[cfg(feature = "network")]
+    pub fn host<A: std::net::ToSocketAddrs>(&mut self, address: A) -> Result<(), Error> {
+        self.network = Some(Network::Server(Server::bind(address)?));
+        Ok(())
+    }
+
+    /// Mirrors the world of the game serving at `address`.
+    #[cfg(feature = "network")]
+    pub fn connect<A: std::net::ToSocketAddrs>(&mut self, address: A) -> Result<(), Error> {
+        self.network = Some(Network::Client(Client::connect(address)?));
+        Ok(())
+    }
+
+    /// Starts the random numbers the game's systems and scripts get again from `seed`, e.g. the
+    /// seed logged by an earlier run, to play it back.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random.reseed(seed);
+        self.scripts.reseed(seed);
+    }
+
+    pub fn saves(&self) -> &SaveData {
+        &self.saves
+    }
+
+    /// Changes where the game's progress is saved, e.g. after the project has been renamed.
+    pub fn set_saves(&mut self, saves: SaveData) {
+        self.scripts.set_save_data(saves.clone());
+        self.saves = saves;
+    }
+
+    /// Reloads textures, scenes and sounds from `directory` as their files change, e.g. so that
+    /// artists see their changes straight away while the game runs in the editor.
+    pub fn watch_assets<P: AsRef<Path>>(&mut self, directory: P) {
+        self.watcher = AssetWatcher::new(directory.as_ref());
+    }
+
+    /// Replaces the contents of the world with the entities from the given scene file.
+    pub fn load_scene<P: AsRef<Path>>(&mut self, filename: P) -> Result<(), Error> {
+        let path = filename.as_ref().to_path_buf();
+        self.scenes
+            .switch(filename, &mut self.world, &self.registry)?;
+        self.scripts.instantiate_all(&self.world);
+        self.unload_unused_assets();
+        // The navigation grid is built from where the obstacles are in the world.
+        system_transform_propagation(&mut self.world);
+        self.navigation.invalidate();
+        self.timers.clear();
+        self.collisions.clear();
+        #[cfg(feature = "physics")]
+        self.physics.clear();
+        self.ui.clear();
+        self.events.publish(SceneLoaded { path });
+
+        Ok(())
+    }
+
+    /// Changes to the given scene with a transition lasting `seconds`, rather than straight away as
+    /// with [`Game::load_scene`], replacing any transition already in progress. The scene is loaded
+    /// part way through, once the old one has faded out or been kept to crossfade from, so it has
+    /// to exist by then: if it can't be loaded the error is returned from the update it happens in.
+    pub fn transition_to<P: Into<PathBuf>>(
+        &mut self,
+        filename: P,
+        transition: Transition,
+        seconds: f32,
+    ) {
+        self.transition = Some(SceneTransition::new(filename, transition, seconds));
+    }
+
+    pub fn transition(&self) -> Option<&SceneTransition> {
+        self.transition.as_ref()
+    }
+
+    /// The mode the game last asked its window to be in, which the user may have switched from
+    /// since with Alt+Enter.
+    pub fn window_mode(&self) -> WindowMode {
+        self.window_mode
+    }
+
+    /// Switches the window to `mode` after the next update, e.g. from an options menu.
+    pub fn set_window_mode(&mut self, mode: WindowMode) {
+        self.window_mode = mode;
+    }
+
+    pub fn set_window_title<S: Into<String>>(&mut self, title: S) {
+        self.window_title = title.into();
+    }
+
+    /// Uses the image as the window's icon after the next update, typically a small square PNG.
+    pub fn set_window_icon<P: Into<PathBuf>>(&mut self, icon: P) {
+        self.window_icon = Some(icon.into());
+    }
+
+    /// Pauses the game and destroys its scripts, so that they start from scratch when the game is
+    /// next unpaused.
+    pub fn stop(&mut self) {
+        self.paused = true;
+        self.exit_requested = false;
+        self.time_scale = 1.0;
+        self.collisions.clear();
+        self.navigation.invalidate();
+        self.timers.clear();
+        self.transition = None;
+        self.set_seed(self.random.seed());
+        #[cfg(feature = "physics")]
+        self.physics.clear();
+        self.ui.clear();
+        self.scripts.stop(&mut self.world);
+    }
+
+    /// Closes the game at the end of the frame, as if its window had been closed. Scripts can do
+    /// the same by calling `quit`.
+    pub fn exit(&mut self) {
+        self.exit_requested = true;
+    }
+
+    /// Speeds time up or slows it down from the next frame on, as in [`Time::scale`], e.g. for
+    /// slow motion. Scripts can do the same by calling `set_time_scale`.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_scale = scale.max(0.0);
+    }
+
+    /// The camera the world is drawn from.
+    pub fn view_camera(&self) -> &Camera {
+        self.editor_camera.as_ref().unwrap_or(&self.camera)
+    }
+
+    /// Switches subsystems on or off while the game is running.
+    pub fn set_features(&mut self, features: Features) {
+        self.audio.set_enabled(features.audio);
+        self.settings.features = features;
+    }
+
+    /// Despawns the entity and all of its children. Returns `false` if the entity doesn't exist.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        hierarchy::despawn(&mut self.world, entity)
+    }
+
+    /// Despawns the entity and all of its children once the game systems have run, so it can be
+    /// called while iterating over the world.
+    pub fn despawn_deferred(&self, entity: Entity) {
+        self.pending_despawns.borrow_mut().push(entity);
+    }
+
+    fn flush_despawns(&mut self) {
+        for entity in self.pending_despawns.get_mut().drain(..) {
+            hierarchy::despawn(&mut self.world, entity);
+        }
+    }
 }
 
 impl CreateApplication for Game {
@@ -51,93 +346,519 @@ impl Application for Game {
     fn on_start(&mut self, config_filename: Option<&str>) {
         let filename = config_filename.unwrap_or("alpha_game.ini");
 
-        let path = path::Path::new(filename);
-        let file = fs::read_to_string(path);
-
-        if let Ok(config) = file {
-            let entities: Vec<&str> = config
-                .trim()
-                .split("---\n")
-                .filter(|e| !e.is_empty())
-                .collect();
-
-            for entity in entities {
-                let components: Vec<&str> = entity.split('\n').collect();
-
-                let tag = components[0].to_string();
-
-                let transform: Vec<&str> = components[1].split_whitespace().collect();
-                let x = f32::from_str(transform[0]).unwrap();
-                let y = f32::from_str(transform[1]).unwrap();
-                let width = f32::from_str(transform[2]).unwrap();
-                let height = f32::from_str(transform[3]).unwrap();
-                let rotation = f32::from_str(transform[4]).unwrap();
-
-                let colors: Vec<&str> = components[2].split_whitespace().collect();
-                let r = f32::from_str(colors[0]).unwrap();
-                let g = f32::from_str(colors[1]).unwrap();
-                let b = f32::from_str(colors[2]).unwrap();
-                let a = f32::from_str(colors[3]).unwrap();
-                let color = Vec4::new(r, g, b, a);
-
-                let tag = Tag(tag);
-                let transform = Transform {
-                    position: Vec2::new(x, y),
-                    size: Vec2::new(width, height),
-                    rotation,
-                };
-                let shape = Shape { color };
-                self.world.spawn((tag, transform, shape));
-            }
+        if let Err(e) = self.load_scene(filename) {
+            warn!("{}", e);
         }
     }
 
     fn on_event(&mut self, event: &Event<()>) {
-        if let Event::WindowEvent {
-            event: WindowEvent::Resized(size),
-            ..
-        } = event
-        {
-            self.camera.resize(size.width, size.height);
+        let size = match event {
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => *size,
+            // Moving to a monitor with a different scale factor resizes the window in pixels.
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { new_inner_size, .. },
+                ..
+            } => **new_inner_size,
+            _ => return,
+        };
+
+        // The window is resized to nothing while it's minimised, which would leave nothing in
+        // view, so keep the camera as it was until the window is restored.
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        self.camera.resize(size.width, size.height);
+        if let Some(camera) = &mut self.editor_camera {
+            camera.resize(size.width, size.height);
         }
     }
 
-    fn on_update(
-        &mut self,
-        _window: &Window,
-        renderer: &mut Renderer,
-        _input: &WinitInputHelper,
-    ) -> Result<(), Error> {
-        system_render(&self.world, &self.camera, renderer);
+    fn on_fixed_update(&mut self, time: &Time) {
+        if self.paused {
+            return;
+        }
+
+        let dt = time.fixed_delta_seconds();
+        measure(&mut self.fixed_timings, "Movement", || {
+            system_movement(&mut self.world, dt)
+        });
+        #[cfg(feature = "physics")]
+        measure(&mut self.fixed_timings, "Physics", || {
+            self.physics
+                .step(&mut self.world, self.settings.physics.gravity)
+        });
+        self.run_stage(Stage::FixedUpdate, None, time);
+    }
+
+    fn on_update(&mut self, ctx: &UpdateContext) -> Result<(), Error> {
+        let UpdateContext { input, time } = *ctx;
+        let _scope = alloc_tracker::scope(Subsystem::Game);
+
+        self.time = *time;
+        self.events.next_frame();
+        if let Some(watcher) = &self.watcher {
+            for path in watcher.changed() {
+                self.assets.reload_changed(&path);
+                self.audio.reload(&path);
+            }
+        }
+        self.assets.update();
+        system_assets(&mut self.world, &mut self.assets);
+        self.sub_scenes
+            .update(&mut self.world, &self.assets, &self.registry);
+        if !self.paused {
+            self.update_transition(time)?;
+        }
+
+        self.timings.clear();
+        self.timings.append(&mut self.fixed_timings);
+        measure(&mut self.timings, "Scripts", || {
+            self.scripts.maintain(&mut self.world)
+        });
+        if !self.paused {
+            self.ui.begin_frame(input, self.camera.screen_size());
+            #[cfg(feature = "network")]
+            if let Some(network) = &mut self.network {
+                measure(&mut self.timings, "Network", || {
+                    network.receive(&mut self.world, &self.registry, time.delta_seconds())
+                });
+            }
+            self.run_stage(Stage::PreUpdate, Some(input), time);
+            let navigation = self.navigation.grid(&self.world);
+            measure(&mut self.timings, "Scripts", || {
+                self.scripts.run(
+                    &mut self.world,
+                    input,
+                    &self.camera,
+                    time,
+                    &self.events,
+                    navigation,
+                )
+            });
+            self.time_scale = self.scripts.time_scale();
+            for request in self.scripts.take_timer_requests() {
+                self.timers.apply(request);
+            }
+            self.run_stage(Stage::Update, Some(input), time);
+            measure(&mut self.timings, "Timers", || {
+                self.timers
+                    .update(&self.world, &mut self.events, time.delta_seconds())
+            });
+            measure(&mut self.timings, "Navigation", || {
+                system_navigation(&mut self.world, &mut self.navigation, time.delta_seconds())
+            });
+            measure(&mut self.timings, "Animation", || {
+                system_animation(&mut self.world, time.delta_seconds())
+            });
+        }
+        measure(&mut self.timings, "Sprite Animation", || {
+            system_sprite_animation(&mut self.world, time.delta_seconds(), self.paused)
+        });
+        for event in self.scripts.take_events() {
+            self.events.publish(event);
+        }
+        for sound in self.scripts.take_sound_requests() {
+            if let Err(e) = self.audio.play(sound) {
+                warn!("unable to play sound: {}", e);
+            }
+        }
+        if self.scripts.take_quit_request() {
+            self.exit();
+        }
+        self.flush_despawns();
+        measure(&mut self.timings, "Transforms", || {
+            system_transform_propagation(&mut self.world)
+        });
+        measure(&mut self.timings, "Audio", || {
+            self.audio
+                .update_sources(&self.world, &self.assets, self.camera.centre(), self.paused)
+        });
+        if !self.paused {
+            measure(&mut self.timings, "Collisions", || {
+                self.collisions.update(&self.world, &mut self.events)
+            });
+            self.run_stage(Stage::PostUpdate, Some(input), time);
+            self.ui.end_frame();
+            self.ui.track_assets(&mut self.assets);
+            #[cfg(feature = "network")]
+            if let Some(network) = &mut self.network {
+                measure(&mut self.timings, "Network", || {
+                    network.send(&self.world, &self.registry)
+                });
+            }
+        }
 
         Ok(())
     }
 
+    fn on_render(&mut self, ctx: &mut FrameContext) -> Result<(), Error> {
+        self.render(ctx.renderer)
+    }
+
     fn on_stop(&mut self) {
+        self.stop();
         info!("GAME on_stop");
     }
+
+    fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    fn pause_when_minimized(&self) -> bool {
+        self.settings.display.pause_when_minimized
+    }
+
+    fn frame_rate_limit(&self) -> Option<u32> {
+        Some(self.settings.display.max_fps).filter(|fps| *fps > 0)
+    }
+
+    fn requested_window_mode(&self) -> Option<WindowMode> {
+        Some(self.window_mode)
+    }
+
+    fn fullscreen_monitor(&self) -> Option<usize> {
+        self.settings.display.monitor
+    }
+
+    fn window_title(&self) -> Option<&str> {
+        Some(&self.window_title)
+    }
+
+    fn window_icon(&self) -> Option<&Path> {
+        self.window_icon.as_deref()
+    }
 }
 
+impl Game {
+    /// Draws the world, which doesn't need a window, unlike [`Application::on_render`].
+    pub fn render(&mut self, renderer: &mut Renderer) -> Result<(), Error> {
+        let _scope = alloc_tracker::scope(Subsystem::Game);
+
+        for (path, image) in self.assets.take_changed::<Image>() {
+            renderer.insert_texture(&path, &image.image);
+        }
+        // A lost device takes its textures with it, so the new one is given them again.
+        for (path, image) in self.assets.loaded::<Image>() {
+            if renderer.texture(path).is_none() {
+                renderer.insert_texture(path, &image.image);
+            }
+        }
+
+        let camera = self.editor_camera.as_ref().unwrap_or(&self.camera);
+        if let Some(transition) = &mut self.transition {
+            transition.capture(renderer, |renderer| {
+                system_render(
+                    &self.world,
+                    camera,
+                    renderer,
+                    &mut self.schedule,
+                    &self.ui,
+                    &self.assets,
+                    None,
+                )
+            })?;
+        }
+        if self
+            .transition
+            .as_ref()
+            .map_or(false, SceneTransition::is_finished)
+        {
+            self.transition = None;
+            SceneTransition::release(renderer);
+        }
+
+        let transition = self.transition.as_ref();
+        let mut result = Ok(());
+        measure(&mut self.timings, "Render", || {
+            result = system_render(
+                &self.world,
+                camera,
+                renderer,
+                &mut self.schedule,
+                &self.ui,
+                &self.assets,
+                transition,
+            )
+        });
+
+        result
+    }
+
+    /// Moves the transition in progress on, switching scene when it's time to.
+    fn update_transition(&mut self, time: &Time) -> Result<(), Error> {
+        let dt = time.unscaled_delta().as_secs_f32();
+        let switch = match &mut self.transition {
+            Some(transition) => transition.update(dt),
+            None => return Ok(()),
+        };
+
+        if switch {
+            let scene = self.transition.as_ref().map(|t| t.scene().to_path_buf());
+            if let Some(scene) = scene {
+                let loaded = self.load_scene(&scene);
+                match &mut self.transition {
+                    Some(transition) if loaded.is_ok() => transition.switched(),
+                    _ => self.transition = None,
+                }
+                loaded?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Unloads the assets which nothing in the world, nor the game, refers to any more.
+    fn unload_unused_assets(&mut self) {
+        system_assets(&mut self.world, &mut self.assets);
+        self.assets.unload_unused::<Image>();
+        self.assets.unload_unused::<SceneFile>();
+        self.assets.unload_unused::<ScriptFile>();
+        self.assets.unload_unused::<AudioFile>();
+    }
+
+    fn run_stage(&mut self, stage: Stage, input: Option<&WinitInputHelper>, time: &Time) {
+        let timings = match stage {
+            Stage::FixedUpdate => &mut self.fixed_timings,
+            _ => &mut self.timings,
+        };
+        let mut ctx = SystemContext {
+            world: &mut self.world,
+            events: &mut self.events,
+            time,
+            input,
+            ui: &mut self.ui,
+            random: &mut self.random,
+            timers: &mut self.timers,
+        };
+        self.schedule.run(stage, &mut ctx, timings);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 impl Pause for Game {
     fn pause(&mut self, paused: bool) {
         self.paused = paused;
+        if !paused {
+            // The obstacles may have been edited while the game was paused.
+            self.navigation.invalidate();
+        }
     }
 }
 
-fn system_render(world: &World, camera: &Camera, renderer: &mut Renderer) {
-    let mut render_ctx = renderer.prepare();
-    let mut scene = renderer.begin_scene(camera); // TODO: Add camera as a resource in the World.
+/// Runs `system`, adding how long it took to its entry in `timings`.
+pub(crate) fn measure(
+    timings: &mut Vec<(&'static str, Duration)>,
+    name: &'static str,
+    system: impl FnOnce(),
+) {
+    let start = Instant::now();
+    system();
+    let elapsed = start.elapsed();
+
+    match timings.iter_mut().find(|(n, _)| *n == name) {
+        Some((_, total)) => *total += elapsed,
+        None => timings.push((name, elapsed)),
+    }
+}
+
+fn system_movement(world: &mut World, dt: f32) {
+    for (_id, (transform, velocity, acceleration)) in
+        world.query_mut::<(&mut Transform, &mut Velocity, Option<&Acceleration>)>()
+    {
+        if let Some(acceleration) = acceleration {
+            velocity.linear += acceleration.linear * dt;
+            velocity.angular += acceleration.angular * dt;
+        }
 
-    for (_id, (transform, shape)) in world.query::<(&Transform, &Shape)>().iter() {
-        let rect = Rect::new(
-            transform.position,
-            transform.rotation,
-            transform.size,
-            shape.color,
+        transform.position += velocity.linear * dt;
+        transform.rotation += velocity.angular * dt;
+    }
+}
+
+/// What an entity's [`GlobalTransform`] was last worked out from, so it's only worked out again
+/// when the entity, or one of its ancestors, has moved.
+struct TransformCache {
+    local: Transform,
+    parent_space: (Mat4, Mat4),
+    /// The space the entity's children are positioned in.
+    space: (Mat4, Mat4),
+}
+
+fn system_transform_propagation(world: &mut World) {
+    fn propagate(
+        world: &World,
+        entity: Entity,
+        parent_space: (Mat4, Mat4),
+        updates: &mut Vec<(Entity, GlobalTransform, TransformCache)>,
+    ) {
+        let transform = match world.get::<Transform>(entity) {
+            Ok(transform) => transform,
+            Err(_) => return,
+        };
+
+        // Comparing the matrices is cheaper than multiplying them, and also catches the entity
+        // being moved to another parent.
+        let cached = world.get::<TransformCache>(entity).ok().filter(|cache| {
+            cache.local == *transform
+                && cache.parent_space == parent_space
+                && world.get::<GlobalTransform>(entity).is_ok()
+        });
+        let space = match cached {
+            Some(cache) => cache.space,
+            None => {
+                let (parent_matrix, parent_inverse) = parent_space;
+                let space = (
+                    parent_matrix * compute_space_matrix(&transform),
+                    compute_inverse_space_matrix(&transform) * parent_inverse,
+                );
+                updates.push((
+                    entity,
+                    GlobalTransform {
+                        matrix: parent_matrix * compute_transformation_matrix(&transform),
+                        inverse: compute_inverse_transformation_matrix(&transform) * parent_inverse,
+                    },
+                    TransformCache {
+                        local: transform.clone(),
+                        parent_space,
+                        space,
+                    },
+                ));
+                space
+            }
+        };
+
+        if let Ok(children) = world.get::<Children>(entity) {
+            for child in children.0.iter() {
+                propagate(world, *child, space, updates);
+            }
+        }
+    }
+
+    let mut updates = Vec::new();
+    for (entity, _) in world.query::<&Transform>().without::<Parent>().iter() {
+        propagate(
+            world,
+            entity,
+            (Mat4::IDENTITY, Mat4::IDENTITY),
+            &mut updates,
         );
+    }
+
+    for (entity, global_transform, cache) in updates {
+        match world.query_one_mut::<(&mut GlobalTransform, &mut TransformCache)>(entity) {
+            Ok((global, cached)) => {
+                *global = global_transform;
+                *cached = cache;
+            }
+            // Moving the entity to another archetype is only needed the first time.
+            Err(_) => {
+                world.insert(entity, (global_transform, cache)).ok();
+            }
+        }
+    }
+
+    // Entities whose Transform has been removed shouldn't keep rendering where they last were.
+    let stale: Vec<Entity> = world
+        .query::<&GlobalTransform>()
+        .without::<Transform>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in stale {
+        world.remove_one::<GlobalTransform>(entity).ok();
+        world.remove_one::<TransformCache>(entity).ok();
+    }
+}
+
+fn system_render(
+    world: &World,
+    camera: &Camera,
+    renderer: &mut Renderer,
+    schedule: &mut Schedule,
+    ui: &GameUi,
+    assets: &Assets,
+    transition: Option<&SceneTransition>,
+) -> Result<(), Error> {
+    let _scope = alloc_tracker::scope(Subsystem::Renderer);
+
+    let mut render_ctx = match renderer.prepare()? {
+        Some(render_ctx) => render_ctx,
+        None => return Ok(()),
+    };
+    let mut scene = renderer.begin_scene(camera); // TODO: Add camera as a resource in the World.
+
+    for (id, (transform, shape)) in world.query::<(&GlobalTransform, &Shape)>().iter() {
+        if !is_drawn(world, camera, id) {
+            continue;
+        }
+
+        let rect = Rect::new(transform.matrix, shape.color);
         renderer.draw_rect(&mut scene, &rect);
     }
 
+    // Tilemaps are drawn first, so that sprites are drawn over them.
+    for (id, (transform, tilemap)) in world.query::<(&GlobalTransform, &Tilemap)>().iter() {
+        if !is_drawn(world, camera, id) || assets.state(&tilemap.tileset) != LoadState::Loaded {
+            continue;
+        }
+
+        let mut tile_sprite = Sprite {
+            texture: tilemap.tileset.clone(),
+            ..Sprite::default()
+        };
+        for (i, tile) in tilemap.tiles().iter().enumerate() {
+            if let Some(tile) = tile {
+                let (column, row) = (i as u32 % tilemap.columns(), i as u32 / tilemap.columns());
+                let matrix = tilemap.cell_matrix(&transform.matrix, column, row);
+                let (uv_min, uv_max) = tilemap.tile_uvs(*tile);
+                tile_sprite.uv_min = uv_min;
+                tile_sprite.uv_max = uv_max;
+                renderer.draw_sprite(&mut scene, &matrix, &tile_sprite);
+            }
+        }
+    }
+
+    scene.next_layer();
+    for (id, (transform, sprite)) in world.query::<(&GlobalTransform, &Sprite)>().iter() {
+        if !is_drawn(world, camera, id) || assets.state(&sprite.texture) != LoadState::Loaded {
+            continue;
+        }
+
+        renderer.draw_sprite(&mut scene, &transform.matrix, sprite);
+    }
+
+    // Plugins draw over everything else.
+    scene.next_layer();
+    schedule.draw(&mut DrawContext {
+        world,
+        camera,
+        renderer,
+        scene: &mut scene,
+    });
+
     renderer.end_scene(scene, &mut render_ctx);
+    ui.draw(renderer, assets, &mut render_ctx);
+    if let Some(transition) = transition {
+        transition.draw(renderer, &mut render_ctx);
+    }
     renderer.finalise(render_ctx);
+
+    Ok(())
+}
+
+fn is_drawn(world: &World, camera: &Camera, entity: Entity) -> bool {
+    let on_camera_layer = match world.get::<CollisionLayers>(entity) {
+        Ok(layers) => layers.layers & camera.layers != 0,
+        Err(_) => true,
+    };
+
+    on_camera_layer && hierarchy::is_visible(world, entity)
 }
@@ -0,0 +1,46 @@
+use crate::components::Guid;
+use hecs::{Entity, World};
+
+/// A weak reference to an entity, suitable for handing to scripts and holding across frames.
+///
+/// hecs recycles entity ids after a despawn and a scene reload spawns everything with new ids, so
+/// a raw `Entity` can end up pointing at the wrong thing. A handle is resolved by [`Guid`] instead
+/// and reports the entity as gone once it has been despawned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntityHandle {
+    entity: Entity,
+    guid: Guid,
+}
+
+impl EntityHandle {
+    pub fn new(world: &World, entity: Entity) -> Option<Self> {
+        let guid = *world.get::<Guid>(entity).ok()?;
+
+        Some(Self { entity, guid })
+    }
+
+    pub fn guid(&self) -> Guid {
+        self.guid
+    }
+
+    pub fn resolve(&mut self, world: &World) -> Option<Entity> {
+        if let Ok(guid) = world.get::<Guid>(self.entity) {
+            if *guid == self.guid {
+                return Some(self.entity);
+            }
+        }
+
+        // The cached id is stale, e.g. because the scene was reloaded, so look the GUID up again.
+        let (entity, _) = world
+            .query::<&Guid>()
+            .iter()
+            .find(|(_, guid)| **guid == self.guid)?;
+        self.entity = entity;
+
+        Some(entity)
+    }
+
+    pub fn is_valid(&mut self, world: &World) -> bool {
+        self.resolve(world).is_some()
+    }
+}
@@ -0,0 +1,84 @@
+use crate::alloc_tracker;
+use crate::engine::{Application, UpdateContext};
+use crate::error::Error;
+use crate::game::Game;
+use crate::renderer::{self, Renderer};
+use crate::time::Time;
+use winit_input_helper::WinitInputHelper;
+
+const WIDTH: u32 = 1280;
+const HEIGHT: u32 = 720;
+
+/// Runs a [`Game`] without a window, so that game logic and scene loading can be tested in CI
+/// without a display.
+///
+/// Each frame comes exactly [`Time::FIXED_DELTA`] after the last, so runs are repeatable. Nothing
+/// is drawn unless the game is created [`Headless::with_renderer`], which draws into a texture
+/// rather than a window, but still needs a graphics adapter.
+pub struct Headless {
+    pub game: Game,
+    renderer: Option<Renderer>,
+    input: WinitInputHelper,
+    time: Time,
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Headless {
+    pub fn new() -> Self {
+        Self {
+            game: Game::headless(WIDTH, HEIGHT),
+            renderer: None,
+            input: WinitInputHelper::new(),
+            time: Time::default(),
+        }
+    }
+
+    pub fn with_renderer(width: u32, height: u32) -> Result<Self, Error> {
+        Ok(Self {
+            game: Game::headless(width, height),
+            renderer: Some(renderer::init_headless(width, height)?),
+            input: WinitInputHelper::new(),
+            time: Time::default(),
+        })
+    }
+
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Runs the game for the given number of frames, or until it exits.
+    pub fn step(&mut self, frames: u32) -> Result<(), Error> {
+        for _ in 0..frames {
+            if self.game.exit_requested() {
+                break;
+            }
+
+            self.time.set_scale(self.game.time_scale());
+            self.time.advance(Time::FIXED_DELTA);
+            while self.time.next_fixed_step() {
+                self.game.on_fixed_update(&self.time);
+            }
+            self.game.on_update(&UpdateContext {
+                input: &self.input,
+                time: &self.time,
+            })?;
+            if let Some(renderer) = &mut self.renderer {
+                self.game.render(renderer)?;
+            }
+
+            alloc_tracker::end_frame();
+        }
+
+        Ok(())
+    }
+
+    /// The last frame drawn, as tightly packed RGBA pixels, or `None` if nothing is drawn.
+    pub fn read_frame(&self) -> Option<Vec<u8>> {
+        self.renderer.as_ref()?.read_frame_rgba()
+    }
+}
@@ -0,0 +1,112 @@
+use crate::components::{Children, Parent, Visible};
+use hecs::{Entity, World};
+
+/// Makes `child` a child of `parent`, or a root entity if `parent` is `None`.
+///
+/// Returns `false` and leaves the hierarchy untouched if this would create a cycle.
+pub fn set_parent(world: &mut World, child: Entity, parent: Option<Entity>) -> bool {
+    if let Some(parent) = parent {
+        if parent == child || is_ancestor(world, child, parent) {
+            return false;
+        }
+    }
+
+    if let Ok(Parent(old_parent)) = world.remove_one::<Parent>(child) {
+        if let Ok(mut children) = world.get_mut::<Children>(old_parent) {
+            children.0.retain(|c| *c != child);
+        }
+    }
+
+    if let Some(parent) = parent {
+        if world.insert_one(child, Parent(parent)).is_err() {
+            return false;
+        }
+
+        let has_children = world.get::<Children>(parent).is_ok();
+        if has_children {
+            world.get_mut::<Children>(parent).unwrap().0.push(child);
+        } else {
+            world.insert_one(parent, Children(vec![child])).ok();
+        }
+    }
+
+    true
+}
+
+/// Despawns `entity` along with all of its descendants, detaching it from its parent first.
+///
+/// Returns `false` if the entity doesn't exist.
+pub fn despawn(world: &mut World, entity: Entity) -> bool {
+    if !world.contains(entity) {
+        return false;
+    }
+
+    set_parent(world, entity, None);
+
+    let mut entities = vec![entity];
+    while let Some(entity) = entities.pop() {
+        if let Ok(children) = world.get::<Children>(entity) {
+            entities.extend(children.0.iter().copied());
+        }
+        world.despawn(entity).ok();
+    }
+
+    true
+}
+
+/// The entity's parent, or `None` if it's a root entity.
+pub fn parent(world: &World, entity: Entity) -> Option<Entity> {
+    world.get::<Parent>(entity).ok().map(|parent| parent.0)
+}
+
+/// Is `ancestor` the parent of `entity`, or the parent of its parent, and so on?
+pub fn is_ancestor(world: &World, ancestor: Entity, entity: Entity) -> bool {
+    let mut current = entity;
+    while let Ok(parent) = world.get::<Parent>(current) {
+        if parent.0 == ancestor {
+            return true;
+        }
+        current = parent.0;
+    }
+
+    false
+}
+
+/// Is `entity` visible, taking the [`Visible`] component of each of its ancestors into account?
+pub fn is_visible(world: &World, entity: Entity) -> bool {
+    let mut current = entity;
+    loop {
+        if let Ok(visible) = world.get::<Visible>(current) {
+            if !visible.0 {
+                return false;
+            }
+        }
+
+        match world.get::<Parent>(current) {
+            Ok(parent) => current = parent.0,
+            Err(_) => return true,
+        }
+    }
+}
+
+/// Lists every entity in depth first order alongside its depth in the hierarchy.
+pub fn flatten(world: &World) -> Vec<(Entity, usize)> {
+    fn visit(world: &World, entity: Entity, depth: usize, entities: &mut Vec<(Entity, usize)>) {
+        entities.push((entity, depth));
+        if let Ok(children) = world.get::<Children>(entity) {
+            for child in children.0.iter() {
+                visit(world, *child, depth + 1, entities);
+            }
+        }
+    }
+
+    let mut entities = Vec::with_capacity(world.len() as usize);
+    for entity_ref in world.iter() {
+        let entity = entity_ref.entity();
+        if world.get::<Parent>(entity).is_err() {
+            visit(world, entity, 0, &mut entities);
+        }
+    }
+
+    entities
+}
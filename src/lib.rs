@@ -1,12 +1,102 @@
+pub use animation::{
+    play_preset, tween, AnimatedProperty, AnimatedSprite, Animation, AnimationPreset, Easing,
+    Keyframe, SpriteClip, Track,
+};
+pub use assets::{Asset, Assets, AudioFile, Handle, Image, LoadState, SceneFile, ScriptFile};
+pub use audio::{Audio, AudioSource, Occlusion, Selection, SoundHandle, SoundVariation};
+#[cfg(not(target_arch = "wasm32"))]
+pub use build::{build, BuildReport};
+pub use components::{
+    Acceleration, Children, Collider, ColliderShape, CollisionLayers, GlobalTransform, Guid,
+    Parent, Script, ScriptParameter, Shape, Sprite, Tag, Transform, Velocity, Visible,
+};
+#[cfg(feature = "physics")]
+pub use components::{BodyKind, RigidBody};
+#[cfg(not(target_arch = "wasm32"))]
 pub use editor::Editor;
 pub use engine::Engine;
+pub use error::Error;
+pub use events::{CollisionEnded, CollisionStarted, Events, SceneLoaded, ScriptEvent, TimerFired};
 pub use game::Game;
+pub use handle::EntityHandle;
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::Headless;
+#[cfg(not(target_arch = "wasm32"))]
+pub use logging::{log_level, set_log_level};
+pub use migration::{Migration, MigrationReport, Migrations, SCENE_VERSION};
+pub use navigation::{NavGrid, Navigation, PathFollower};
+#[cfg(feature = "network")]
+pub use network::{Client, Network, Replicated, Server};
+pub use platform::{ScaleFactor, WindowMode};
+pub use project::{
+    BuildSettings, DisplaySettings, Features, LogSettings, PhysicsSettings, ProjectSettings,
+    ScriptLimits,
+};
+pub use query::{find_by_guid, find_by_tag, unique_tag};
+pub use random::Random;
+pub use registry::{ComponentRegistry, Reflect, Registration};
+pub use save::SaveData;
+#[cfg(not(target_arch = "wasm32"))]
+pub use scenario::Scenario;
+pub use scene::{SceneDescription, SceneManager, SceneMatch};
+pub use schedule::{DrawContext, Plugin, Schedule, Stage, SystemContext};
+pub use sub_scene::SubScene;
+pub use tilemap::Tilemap;
+pub use time::Time;
+pub use timers::{Delay, TimerId, Timers};
+pub use transition::{SceneTransition, Transition};
+pub use ui::{
+    AccessAction, AccessNode, AccessRole, AccessTree, AccessibilityAdapter, Anchor, BitmapFont,
+    GameUi, UiRect,
+};
 
+mod alloc_tracker;
+mod animation;
+mod assets;
+mod audio;
+#[cfg(not(target_arch = "wasm32"))]
+mod build;
+mod collision;
 mod components;
+#[cfg(not(target_arch = "wasm32"))]
+mod crash;
+#[cfg(not(target_arch = "wasm32"))]
 mod editor;
 mod engine;
 mod error;
+mod events;
+mod files;
 mod game;
+mod handle;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod hierarchy;
+#[cfg(not(target_arch = "wasm32"))]
 mod logging;
+mod migration;
+mod navigation;
+#[cfg(feature = "network")]
+mod network;
+#[cfg(feature = "physics")]
+mod physics;
 mod platform;
+mod project;
+mod query;
+mod random;
+mod registry;
 mod renderer;
+mod save;
+#[cfg(not(target_arch = "wasm32"))]
+mod scenario;
+mod scene;
+mod schedule;
+#[cfg(not(target_arch = "wasm32"))]
+mod scripting;
+mod sub_scene;
+mod tilemap;
+mod time;
+mod timers;
+mod transition;
+mod ui;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
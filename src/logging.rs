@@ -1,8 +1,76 @@
 use crate::error::Error;
-use flexi_logger::Logger;
+use crate::project::LogSettings;
+use flexi_logger::{Cleanup, Criterion, Duplicate, FileSpec, Logger, LoggerHandle, Naming};
+use once_cell::sync::OnceCell;
+use std::env;
+use std::sync::Mutex;
 
-pub fn init(max_level: &str) -> Result<(), Error> {
-    Logger::try_with_str(format!("{}, wgpu_core=warn", max_level))?.start()?;
+/// Overrides [`LogSettings::level`], like `RUST_LOG`, e.g. `ALPHA_LOG=debug`.
+const LEVEL_VARIABLE: &str = "ALPHA_LOG";
+
+/// wgpu logs a lot at info level, so it's quietened down whatever the level.
+const QUIETENED: &str = "wgpu_core=warn";
+
+/// The logger, once it's started, and the level it's logging at.
+static LOGGER: OnceCell<Mutex<(LoggerHandle, String)>> = OnceCell::new();
+
+pub fn init(settings: &LogSettings) -> Result<(), Error> {
+    let level = env::var(LEVEL_VARIABLE).unwrap_or_else(|_| settings.level.clone());
+
+    let mut logger = Logger::try_with_str(spec(&level))?;
+    if let Some(directory) = &settings.directory {
+        let duplicate = if settings.stderr {
+            Duplicate::All
+        } else {
+            Duplicate::None
+        };
+        logger = logger
+            .log_to_file(FileSpec::default().directory(directory))
+            .duplicate_to_stderr(duplicate);
+        if settings.max_file_mb > 0 {
+            logger = logger.rotate(
+                Criterion::Size(settings.max_file_mb * 1024 * 1024),
+                Naming::Numbers,
+                Cleanup::KeepLogFiles(settings.keep_files),
+            );
+        }
+    }
+
+    let handle = logger.start()?;
+    LOGGER.set(Mutex::new((handle, level))).ok();
+
+    Ok(())
+}
+
+/// The level the engine is logging at, or `None` if logging hasn't been started, e.g. by a host
+/// which embeds the engine and has a logger of its own.
+pub fn log_level() -> Option<String> {
+    let logger = LOGGER.get()?.lock().ok()?;
+    Some(logger.1.clone())
+}
+
+/// Changes the level the engine logs at while it runs, in the same form as
+/// [`LogSettings::level`], e.g. `warn, alpha::scripting=debug`.
+pub fn set_log_level(level: &str) -> Result<(), Error> {
+    let mut logger = match LOGGER.get().and_then(|logger| logger.lock().ok()) {
+        Some(logger) => logger,
+        None => return Ok(()),
+    };
+
+    logger.0.parse_new_spec(&spec(level))?;
+    logger.1 = level.to_string();
 
     Ok(())
 }
+
+/// Writes out anything the logger is holding on to, e.g. before the process exits.
+pub(crate) fn flush() {
+    // Without waiting, in case whatever panicked was changing the level.
+    if let Some(logger) = LOGGER.get().and_then(|logger| logger.try_lock().ok()) {
+        logger.0.flush();
+    }
+}
+
+fn spec(level: &str) -> String {
+    format!("{}, {}", level, QUIETENED)
+}
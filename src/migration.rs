@@ -0,0 +1,287 @@
+use crate::components::Guid;
+use crate::error::Error;
+use crate::scene;
+use log::{error, info};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The version of the scene format written by this version of alpha.
+pub const SCENE_VERSION: u32 = 3;
+
+/// Upgrades a scene from one version of the format to the next.
+#[derive(Clone, Copy)]
+pub struct Migration {
+    pub from_version: u32,
+    pub description: &'static str,
+    pub apply: fn(&str) -> String,
+}
+
+/// The migrations needed to bring a scene written by any earlier version of alpha up to date.
+pub struct Migrations {
+    steps: Vec<Migration>,
+}
+
+impl Default for Migrations {
+    fn default() -> Self {
+        let mut migrations = Self { steps: Vec::new() };
+        migrations.register(Migration {
+            from_version: 1,
+            description: "store components as keyed lines",
+            apply: keyed_components,
+        });
+        migrations.register(Migration {
+            from_version: 2,
+            description: "store script parameters",
+            apply: script_parameters,
+        });
+        migrations
+    }
+}
+
+impl Migrations {
+    /// Registers a migration, replacing any existing migration from the same version.
+    pub fn register(&mut self, migration: Migration) {
+        self.steps
+            .retain(|step| step.from_version != migration.from_version);
+        self.steps.push(migration);
+    }
+
+    /// Upgrades the scene to [`SCENE_VERSION`], returning the upgraded scene alongside a
+    /// description of each migration that was applied.
+    pub fn upgrade(&self, source: &str) -> Result<(String, Vec<&'static str>), Error> {
+        let mut version = scene_version(source);
+        if version > SCENE_VERSION {
+            return Err(Error::SceneVersion(version));
+        }
+
+        let mut source = source.to_string();
+        let mut applied = Vec::new();
+        while version < SCENE_VERSION {
+            let step = self
+                .steps
+                .iter()
+                .find(|step| step.from_version == version)
+                .ok_or(Error::SceneVersion(version))?;
+
+            source = (step.apply)(&source);
+            applied.push(step.description);
+            version += 1;
+        }
+
+        Ok((source, applied))
+    }
+}
+
+/// Works out which version of the format a scene was written in.
+///
+/// Scenes have started with a `Version: N` line since version 2. Before that, the only format was
+/// the positional one, where the first line of each entity is its tag.
+pub fn scene_version(source: &str) -> u32 {
+    let first_line = source.trim_start().lines().next().unwrap_or_default();
+    match first_line.strip_prefix("Version:") {
+        Some(version) => version.trim().parse().unwrap_or(0),
+        None if first_line.starts_with("Guid:") => 2,
+        None => 1,
+    }
+}
+
+/// What happened, or with a dry run would happen, to a scene when migrating a project.
+pub struct MigrationReport {
+    pub path: PathBuf,
+    pub result: Result<Vec<&'static str>, Error>,
+}
+
+/// Upgrades every scene under `directory`, first copying each file which needs upgrading to
+/// `scene.alpha.vN.bak`. With `dry_run`, only reports what would be done.
+pub fn migrate_project(
+    migrations: &Migrations,
+    directory: &Path,
+    dry_run: bool,
+) -> Vec<MigrationReport> {
+    let paths = match scene::scene_files(directory) {
+        Ok(paths) => paths,
+        Err(e) => {
+            error!("unable to search {}: {}", directory.display(), e);
+            return Vec::new();
+        }
+    };
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let result = migrate_file(migrations, &path, dry_run);
+            MigrationReport { path, result }
+        })
+        .collect()
+}
+
+fn migrate_file(
+    migrations: &Migrations,
+    path: &Path,
+    dry_run: bool,
+) -> Result<Vec<&'static str>, Error> {
+    let source = fs::read_to_string(path).map_err(|e| Error::SceneLoad(path.to_path_buf(), e))?;
+    let (upgraded, applied) = migrations.upgrade(&source)?;
+
+    if !dry_run && !applied.is_empty() {
+        let mut backup = OsString::from(path.as_os_str());
+        backup.push(format!(".v{}.bak", scene_version(&source)));
+
+        let write = fs::copy(path, &backup).and_then(|_| fs::write(path, upgraded));
+        write.map_err(|e| Error::SceneMigration(path.to_path_buf(), e))?;
+
+        info!("migrated scene {}", path.display());
+    }
+
+    Ok(applied)
+}
+
+/// Version 1 scenes list each entity's tag, transform, shape color, GUID and parent GUID on
+/// consecutive lines, with the GUIDs missing from scenes saved before they were introduced.
+fn keyed_components(source: &str) -> String {
+    let mut scene = String::from("Version: 2\n");
+
+    for entity in source.trim().split("---\n").filter(|e| !e.is_empty()) {
+        let lines: Vec<&str> = entity
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| *line != "---")
+            .collect();
+
+        let guid = lines
+            .get(3)
+            .filter(|guid| u64::from_str_radix(guid.trim(), 16).is_ok())
+            .map(|guid| guid.trim().to_string())
+            .unwrap_or_else(|| format!("{:016x}", Guid::generate().0));
+        scene.push_str(&format!("Guid: {}\n", guid));
+
+        if let Some(parent) = lines.get(4).filter(|p| !p.trim().is_empty()) {
+            scene.push_str(&format!("Parent: {}\n", parent.trim()));
+        }
+
+        for (name, value) in ["Tag", "Transform", "Shape"].iter().zip(lines.iter()) {
+            scene.push_str(&format!("{}: {}\n", name, value));
+        }
+
+        scene.push_str("---\n");
+    }
+
+    scene
+}
+
+/// Version 2 scenes store only the path of an entity's script, without any parameters.
+fn script_parameters(source: &str) -> String {
+    let mut scene = String::from("Version: 3\n");
+
+    for line in source.lines().filter(|line| !line.starts_with("Version:")) {
+        match line.strip_prefix("Script: ") {
+            Some(path) => scene.push_str(&format!("Script: 0 {}\n", path)),
+            None => scene.push_str(&format!("{}\n", line)),
+        }
+    }
+
+    scene
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// A version 1 scene with one entity, which has a GUID but no parent.
+    const V1_SCENE: &str = "Player\n0 0 0 1 1\n1 0 0 1\n00000000000000ab\n\n---\n";
+
+    /// A directory of its own for each test, emptied of anything a previous run left behind.
+    fn project_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("alpha_migration_{}", name));
+        fs::remove_dir_all(&directory).ok();
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn upgrades_v1_scene_to_current_version() {
+        let (upgraded, applied) = Migrations::default().upgrade(V1_SCENE).unwrap();
+
+        assert_eq!(
+            upgraded,
+            "Version: 3\n\
+             Guid: 00000000000000ab\n\
+             Tag: Player\n\
+             Transform: 0 0 0 1 1\n\
+             Shape: 1 0 0 1\n\
+             ---\n"
+        );
+        assert_eq!(scene_version(&upgraded), SCENE_VERSION);
+        assert_eq!(
+            applied,
+            ["store components as keyed lines", "store script parameters"]
+        );
+    }
+
+    #[test]
+    fn upgrades_v2_scripts_to_have_no_parameters() {
+        let source = "Version: 2\nGuid: 00000000000000ab\nScript: scripts/player.wasm\n---\n";
+
+        let (upgraded, applied) = Migrations::default().upgrade(source).unwrap();
+
+        assert_eq!(
+            upgraded,
+            "Version: 3\nGuid: 00000000000000ab\nScript: 0 scripts/player.wasm\n---\n"
+        );
+        assert_eq!(applied, ["store script parameters"]);
+    }
+
+    #[test]
+    fn leaves_current_scene_alone() {
+        let source = "Version: 3\nGuid: 00000000000000ab\n---\n";
+
+        let (upgraded, applied) = Migrations::default().upgrade(source).unwrap();
+
+        assert_eq!(upgraded, source);
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn rejects_scene_from_newer_version() {
+        let source = format!("Version: {}\n---\n", SCENE_VERSION + 1);
+
+        let result = Migrations::default().upgrade(&source);
+
+        assert!(matches!(result, Err(Error::SceneVersion(v)) if v == SCENE_VERSION + 1));
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let directory = project_directory("dry_run");
+        let scene = directory.join("level.alpha");
+        fs::write(&scene, V1_SCENE).unwrap();
+
+        let reports = migrate_project(&Migrations::default(), &directory, true);
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].result.as_ref().unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(&scene).unwrap(), V1_SCENE);
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        fs::remove_dir_all(&directory).ok();
+    }
+
+    #[test]
+    fn migration_backs_up_scene_before_upgrading_it() {
+        let directory = project_directory("backup");
+        let scene = directory.join("level.alpha");
+        fs::write(&scene, V1_SCENE).unwrap();
+
+        let reports = migrate_project(&Migrations::default(), &directory, false);
+
+        assert!(reports[0].result.is_ok());
+        let backup = directory.join("level.alpha.v1.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), V1_SCENE);
+        let upgraded = fs::read_to_string(&scene).unwrap();
+        assert_eq!(scene_version(&upgraded), SCENE_VERSION);
+
+        fs::remove_dir_all(&directory).ok();
+    }
+}
@@ -0,0 +1,408 @@
+use crate::components::{Collider, ColliderShape, GlobalTransform, Transform};
+use crate::tilemap::Tilemap;
+use glam::{Mat4, Vec2, Vec3};
+use hecs::World;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+/// How many cells are left around the obstacles, so paths can go around the outside of them.
+const MARGIN: u32 = 4;
+
+/// Which parts of the world can be walked through, as a grid of square cells covering the
+/// obstacles. Anything outside the grid is open.
+///
+/// An entity with a [`Collider`] blocks the cells its collider covers, unless it's following a
+/// path itself. A [`Tilemap`] with a collider blocks only the cells which have a tile, so walls
+/// can be painted into one.
+#[derive(Clone, Debug, Default)]
+pub struct NavGrid {
+    /// The bottom left corner of the grid in the world.
+    origin: Vec2,
+    cell_size: f32,
+    columns: u32,
+    rows: u32,
+    /// Whether each cell is blocked, row by row from the bottom.
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    pub fn from_world(world: &World, cell_size: f32) -> Self {
+        let cell_size = cell_size.max(1.0);
+        let mut query = world
+            .query::<(&GlobalTransform, &Collider, Option<&Tilemap>)>()
+            .without::<PathFollower>();
+        let obstacles: Vec<_> = query.iter().map(|(_, obstacle)| obstacle).collect();
+
+        let bounds = obstacles
+            .iter()
+            .map(|(transform, _, _)| bounds(&transform.matrix))
+            .reduce(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)));
+        let (min, max) = match bounds {
+            Some(bounds) => bounds,
+            None => return Self::default(),
+        };
+
+        let margin = cell_size * MARGIN as f32;
+        let origin = ((min - margin) / cell_size).floor() * cell_size;
+        let size = ((max + margin - origin) / cell_size).ceil();
+        let mut grid = Self {
+            origin,
+            cell_size,
+            columns: size.x as u32,
+            rows: size.y as u32,
+            blocked: vec![false; (size.x * size.y) as usize],
+        };
+
+        for (transform, collider, tilemap) in obstacles {
+            let (min, max) = bounds(&transform.matrix);
+            let (min, max) = (grid.cell_at(min), grid.cell_at(max));
+            for row in min.1..=max.1 {
+                for column in min.0..=max.0 {
+                    let centre = grid.cell_centre(column, row);
+                    if blocks(&transform.inverse, collider.shape, tilemap, centre) {
+                        let index = grid.index(column, row);
+                        grid.blocked[index] = true;
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn is_walkable(&self, position: Vec2) -> bool {
+        let (column, row) = self.cell_at(position);
+        !self.contains(column, row) || !self.blocked[self.index(column, row)]
+    }
+
+    /// Finds the shortest way from one position to another, going through the centres of the
+    /// cells in between and ending exactly at `to`. Returns the points to head for in turn, not
+    /// including `from`, or `None` if `to` can't be reached.
+    ///
+    /// Paths move diagonally too, but don't cut the corners of obstacles. Where either end is
+    /// outside the grid, the path goes straight there.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        if !self.is_walkable(to) {
+            return None;
+        }
+
+        let (start, goal) = (self.cell_at(from), self.cell_at(to));
+        if !self.contains(start.0, start.1) || !self.contains(goal.0, goal.1) || start == goal {
+            return Some(vec![to]);
+        }
+
+        let (start, goal) = (self.index(start.0, start.1), self.index(goal.0, goal.1));
+        let mut costs = vec![f32::INFINITY; self.blocked.len()];
+        let mut came_from = vec![usize::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        costs[start] = 0.0;
+        open.push(Node {
+            index: start,
+            estimate: self.heuristic(start, goal),
+        });
+
+        while let Some(Node { index, estimate }) = open.pop() {
+            if index == goal {
+                return Some(self.path(&came_from, goal, to));
+            }
+            if estimate > costs[index] + self.heuristic(index, goal) {
+                // A cheaper way to this cell was found after this one was queued.
+                continue;
+            }
+
+            for (neighbour, step) in self.neighbours(index) {
+                let cost = costs[index] + step;
+                if cost < costs[neighbour] {
+                    costs[neighbour] = cost;
+                    came_from[neighbour] = index;
+                    open.push(Node {
+                        index: neighbour,
+                        estimate: cost + self.heuristic(neighbour, goal),
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Follows the cells back from the goal, keeping only those where the path turns.
+    fn path(&self, came_from: &[usize], goal: usize, to: Vec2) -> Vec<Vec2> {
+        let mut cells = vec![goal];
+        while came_from[*cells.last().unwrap()] != usize::MAX {
+            cells.push(came_from[*cells.last().unwrap()]);
+        }
+        cells.reverse();
+
+        let centres: Vec<Vec2> = cells
+            .iter()
+            .map(|i| self.cell_centre(*i as u32 % self.columns, *i as u32 / self.columns))
+            .collect();
+        let mut path: Vec<Vec2> = centres
+            .windows(3)
+            .filter(|w| (w[1] - w[0]).normalize() != (w[2] - w[1]).normalize())
+            .map(|w| w[1])
+            .collect();
+        path.push(to);
+        path
+    }
+
+    /// The open cells next to a cell, with the cost of moving to each.
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let (column, row) = (index as u32 % self.columns, index as u32 / self.columns);
+        let open = move |dx: i32, dy: i32| {
+            let (column, row) = (column as i32 + dx, row as i32 + dy);
+            column >= 0
+                && row >= 0
+                && self.contains(column as u32, row as u32)
+                && !self.blocked[self.index(column as u32, row as u32)]
+        };
+
+        [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (1, -1),
+            (-1, 1),
+            (1, 1),
+        ]
+        .into_iter()
+        .filter(move |(dx, dy)| {
+            let diagonal = *dx != 0 && *dy != 0;
+            open(*dx, *dy) && (!diagonal || (open(*dx, 0) && open(0, *dy)))
+        })
+        .map(move |(dx, dy)| {
+            let neighbour = ((row as i32 + dy) as u32 * self.columns) + (column as i32 + dx) as u32;
+            let step = if dx != 0 && dy != 0 { 2f32.sqrt() } else { 1.0 };
+            (neighbour as usize, step)
+        })
+    }
+
+    /// The cost of the shortest path between two cells if nothing were in the way.
+    fn heuristic(&self, a: usize, b: usize) -> f32 {
+        let columns = self.columns as i64;
+        let dx = (a as i64 % columns - b as i64 % columns).abs() as f32;
+        let dy = (a as i64 / columns - b as i64 / columns).abs() as f32;
+        dx.max(dy) + (2f32.sqrt() - 1.0) * dx.min(dy)
+    }
+
+    /// The cell at a position, which may be outside of the grid.
+    fn cell_at(&self, position: Vec2) -> (u32, u32) {
+        let cell = ((position - self.origin) / self.cell_size).floor();
+        if cell.x < 0.0 || cell.y < 0.0 {
+            return (u32::MAX, u32::MAX);
+        }
+        (cell.x as u32, cell.y as u32)
+    }
+
+    fn cell_centre(&self, column: u32, row: u32) -> Vec2 {
+        self.origin + (Vec2::new(column as f32, row as f32) + 0.5) * self.cell_size
+    }
+
+    fn contains(&self, column: u32, row: u32) -> bool {
+        column < self.columns && row < self.rows
+    }
+
+    fn index(&self, column: u32, row: u32) -> usize {
+        (row * self.columns + column) as usize
+    }
+}
+
+/// A cell waiting to be searched, ordered so the one estimated to be on the shortest path comes
+/// out of the heap first.
+struct Node {
+    index: usize,
+    estimate: f32,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// The corners of the box around an entity in the world.
+fn bounds(matrix: &Mat4) -> (Vec2, Vec2) {
+    [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE]
+        .iter()
+        .map(|corner| {
+            matrix
+                .transform_point3(Vec3::new(corner.x, corner.y, 0.0))
+                .truncate()
+        })
+        .fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), corner| (min.min(corner), max.max(corner)),
+        )
+}
+
+fn blocks(inverse: &Mat4, shape: ColliderShape, tilemap: Option<&Tilemap>, point: Vec2) -> bool {
+    if let Some(tilemap) = tilemap {
+        return tilemap
+            .cell_at(inverse, point)
+            .map_or(false, |(column, row)| tilemap.get(column, row).is_some());
+    }
+
+    let local = inverse
+        .transform_point3(Vec3::new(point.x, point.y, 0.0))
+        .truncate();
+    match shape {
+        ColliderShape::Box => (0.0..=1.0).contains(&local.x) && (0.0..=1.0).contains(&local.y),
+        ColliderShape::Circle => local.distance(Vec2::splat(0.5)) <= 0.5,
+    }
+}
+
+/// Builds the [`NavGrid`] for the world when it's first needed, and keeps it until the obstacles
+/// change.
+pub struct Navigation {
+    /// The size of the grid's cells, in world units. Smaller cells fit through narrower gaps, but
+    /// take longer to search.
+    pub cell_size: f32,
+    grid: Option<Arc<NavGrid>>,
+}
+
+impl Default for Navigation {
+    fn default() -> Self {
+        Self {
+            cell_size: 32.0,
+            grid: None,
+        }
+    }
+}
+
+impl Navigation {
+    /// The grid for the world, building it if the obstacles have changed since it was last built.
+    pub fn grid(&mut self, world: &World) -> Arc<NavGrid> {
+        let cell_size = self.cell_size;
+        self.grid
+            .get_or_insert_with(|| Arc::new(NavGrid::from_world(world, cell_size)))
+            .clone()
+    }
+
+    /// Builds the grid again the next time it's needed. Call this after adding, moving or removing
+    /// obstacles.
+    pub fn invalidate(&mut self) {
+        self.grid = None;
+    }
+
+    pub fn find_path(&mut self, world: &World, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        self.grid(world).find_path(from, to)
+    }
+}
+
+/// Moves an entity along the shortest path to its target, around the obstacles in the
+/// [`NavGrid`], at `speed` units per second.
+///
+/// The entity's [`Transform`] position is moved, so it should be at the top of its hierarchy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathFollower {
+    pub speed: f32,
+    target: Option<Vec2>,
+    /// What's left of the path to the target.
+    waypoints: Vec<Vec2>,
+    /// Whether the path to the target has been found yet.
+    planned: bool,
+}
+
+impl Default for PathFollower {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+impl PathFollower {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            target: None,
+            waypoints: Vec::new(),
+            planned: false,
+        }
+    }
+
+    /// Heads for `target`, finding the way there on the next update.
+    pub fn go_to(&mut self, target: Vec2) {
+        self.target = Some(target);
+        self.waypoints.clear();
+        self.planned = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.waypoints.clear();
+    }
+
+    /// Where the entity is heading, until it gets there or finds it can't.
+    pub fn target(&self) -> Option<Vec2> {
+        self.target
+    }
+
+    pub fn waypoints(&self) -> &[Vec2] {
+        &self.waypoints
+    }
+}
+
+pub(crate) fn system_navigation(world: &mut World, navigation: &mut Navigation, dt: f32) {
+    if world
+        .query_mut::<&PathFollower>()
+        .into_iter()
+        .all(|(_, f)| f.target.is_none())
+    {
+        return;
+    }
+
+    let grid = navigation.grid(world);
+    for (_, (follower, transform)) in world.query_mut::<(&mut PathFollower, &mut Transform)>() {
+        let target = match follower.target {
+            Some(target) => target,
+            None => continue,
+        };
+        if !follower.planned {
+            follower.waypoints = grid
+                .find_path(transform.position, target)
+                .unwrap_or_default();
+            follower.planned = true;
+        }
+
+        let mut distance = follower.speed * dt;
+        while let Some(next) = follower.waypoints.first().copied() {
+            let to_next = next - transform.position;
+            if to_next.length() > distance {
+                transform.position += to_next.normalize_or_zero() * distance;
+                break;
+            }
+
+            transform.position = next;
+            distance -= to_next.length();
+            follower.waypoints.remove(0);
+        }
+
+        if follower.waypoints.is_empty() {
+            follower.target = None;
+        }
+    }
+}
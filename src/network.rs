@@ -0,0 +1,344 @@
+use crate::components::{Guid, Transform};
+use crate::error::Error;
+use crate::query;
+use crate::registry::{ComponentRegistry, Reflect};
+use hecs::{EntityBuilder, World};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Snapshots are split into datagrams no bigger than this, so they aren't fragmented on the way.
+const MAX_DATAGRAM: usize = 1200;
+/// How long a client can go without being heard from before it's forgotten, and how long a client
+/// can go without a snapshot before it's considered disconnected.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// How often clients remind the server that they're still there.
+const HELLO_INTERVAL: Duration = Duration::from_secs(1);
+const HELLO: &str = "hello";
+
+/// Marks an entity whose components are sent from the server to its clients each tick, by name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replicated {
+    pub components: Vec<String>,
+}
+
+impl Default for Replicated {
+    fn default() -> Self {
+        Self {
+            components: vec![Transform::NAME.to_string()],
+        }
+    }
+}
+
+/// Runs the game as a server or a client of one. The server's world is the one which matters: it
+/// sends the [`Replicated`] components of its entities to every client, which apply them to the
+/// entities with the same [`Guid`] in their own world, spawning any they don't have.
+///
+/// Everything is sent over UDP in the same text format as scene files, as a snapshot of each
+/// entity rather than what changed, so lost datagrams are made up for by the next tick. Clients
+/// smooth out the movement of entities between ticks by interpolating their transforms.
+pub enum Network {
+    Server(Server),
+    Client(Client),
+}
+
+impl Network {
+    /// Takes in what's been received since the last update, before the game updates.
+    pub(crate) fn receive(&mut self, world: &mut World, registry: &ComponentRegistry, dt: f32) {
+        match self {
+            Network::Server(server) => server.receive(),
+            Network::Client(client) => client.receive(world, registry, dt),
+        }
+    }
+
+    /// Sends what's due to be sent, once the game has updated.
+    pub(crate) fn send(&mut self, world: &World, registry: &ComponentRegistry) {
+        match self {
+            Network::Server(server) => server.send(world, registry),
+            Network::Client(client) => client.send(),
+        }
+    }
+}
+
+pub struct Server {
+    socket: UdpSocket,
+    /// When each client was last heard from.
+    clients: HashMap<SocketAddr, Instant>,
+    /// How many snapshots are sent each second.
+    pub tick_rate: u32,
+    tick: u64,
+    last_tick: Option<Instant>,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(address)
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(Error::Network)?;
+        if let Ok(address) = socket.local_addr() {
+            info!("serving on {}", address);
+        }
+
+        Ok(Self {
+            socket,
+            clients: HashMap::new(),
+            tick_rate: 20,
+            tick: 0,
+            last_tick: None,
+        })
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &SocketAddr> {
+        self.clients.keys()
+    }
+
+    fn receive(&mut self) {
+        let mut buffer = [0; MAX_DATAGRAM];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, address)) if &buffer[..len] == HELLO.as_bytes() => {
+                    if self.clients.insert(address, Instant::now()).is_none() {
+                        info!("client {} connected", address);
+                    }
+                }
+                Ok(_) => {}
+                // Either there's nothing left to read or, on some platforms, a client has gone
+                // away, in which case the rest can wait until the next update.
+                Err(_) => break,
+            }
+        }
+
+        self.clients.retain(|address, last_heard| {
+            let connected = last_heard.elapsed() < TIMEOUT;
+            if !connected {
+                info!("client {} disconnected", address);
+            }
+            connected
+        });
+    }
+
+    fn send(&mut self, world: &World, registry: &ComponentRegistry) {
+        let interval = Duration::from_secs_f32(1.0 / self.tick_rate.max(1) as f32);
+        if self.clients.is_empty() || self.last_tick.map_or(false, |t| t.elapsed() < interval) {
+            return;
+        }
+        self.last_tick = Some(Instant::now());
+        self.tick += 1;
+
+        for datagram in snapshot(world, registry, self.tick) {
+            for address in self.clients.keys() {
+                if let Err(e) = self.socket.send_to(datagram.as_bytes(), address) {
+                    warn!("unable to send snapshot to {}: {}", address, e);
+                }
+            }
+        }
+    }
+}
+
+pub struct Client {
+    socket: UdpSocket,
+    server: SocketAddr,
+    last_hello: Option<Instant>,
+    last_snapshot: Option<Instant>,
+    tick: u64,
+    /// The time between the last two ticks, which transforms are interpolated over.
+    interval: f32,
+}
+
+impl Client {
+    pub fn connect<A: ToSocketAddrs>(server: A) -> Result<Self, Error> {
+        let server = server
+            .to_socket_addrs()
+            .and_then(|mut addresses| {
+                addresses
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no server address"))
+            })
+            .map_err(Error::Network)?;
+        let local: SocketAddr = match server {
+            SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+            SocketAddr::V6(_) => ([0; 8], 0).into(),
+        };
+        let socket = UdpSocket::bind(local)
+            .and_then(|socket| socket.connect(server).map(|_| socket))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(Error::Network)?;
+        info!("connecting to {}", server);
+
+        Ok(Self {
+            socket,
+            server,
+            last_hello: None,
+            last_snapshot: None,
+            tick: 0,
+            interval: 0.0,
+        })
+    }
+
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    /// Whether a snapshot has been received from the server recently.
+    pub fn is_connected(&self) -> bool {
+        self.last_snapshot.map_or(false, |t| t.elapsed() < TIMEOUT)
+    }
+
+    fn receive(&mut self, world: &mut World, registry: &ComponentRegistry, dt: f32) {
+        let mut buffer = [0; MAX_DATAGRAM];
+        loop {
+            let len = match self.socket.recv(&mut buffer) {
+                Ok(len) => len,
+                // Including when the server isn't there yet, which the next hello tries again.
+                Err(_) => break,
+            };
+
+            let datagram = String::from_utf8_lossy(&buffer[..len]);
+            let mut lines = datagram.lines();
+            let tick = match lines.next().and_then(|line| line.strip_prefix("Tick: ")) {
+                Some(tick) => tick.parse().unwrap_or(0),
+                None => continue,
+            };
+            // Datagrams can arrive out of order, and an older snapshot would move things back.
+            if tick < self.tick {
+                continue;
+            }
+            if tick > self.tick {
+                let now = Instant::now();
+                if let Some(last) = self.last_snapshot {
+                    self.interval = (now - last).as_secs_f32();
+                }
+                if self.last_snapshot.is_none() {
+                    info!("connected to {}", self.server);
+                }
+                self.last_snapshot = Some(now);
+                self.tick = tick;
+            }
+
+            apply(world, registry, lines, self.interval);
+        }
+
+        interpolate(world, dt);
+    }
+
+    fn send(&mut self) {
+        if self
+            .last_hello
+            .map_or(true, |t| t.elapsed() >= HELLO_INTERVAL)
+        {
+            self.last_hello = Some(Instant::now());
+            if let Err(e) = self.socket.send(HELLO.as_bytes()) {
+                warn!("unable to reach {}: {}", self.server, e);
+            }
+        }
+    }
+}
+
+/// Writes the replicated components of every entity, split into datagrams which each start with
+/// the tick and only hold whole entities.
+fn snapshot(world: &World, registry: &ComponentRegistry, tick: u64) -> Vec<String> {
+    let header = format!("Tick: {}\n", tick);
+    let mut datagrams = Vec::new();
+    let mut datagram = header.clone();
+
+    for (entity, (guid, replicated)) in world.query::<(&Guid, &Replicated)>().iter() {
+        let mut lines = format!("Guid: {:x}\n", guid.0);
+        for name in &replicated.components {
+            let value = registry
+                .get(name)
+                .and_then(|registration| (registration.serialise)(world, entity));
+            if let Some(value) = value {
+                lines.push_str(&format!("{}: {}\n", name, value));
+            }
+        }
+
+        if datagram.len() + lines.len() > MAX_DATAGRAM && datagram.len() > header.len() {
+            datagrams.push(std::mem::replace(&mut datagram, header.clone()));
+        }
+        datagram.push_str(&lines);
+    }
+
+    if datagram.len() > header.len() {
+        datagrams.push(datagram);
+    }
+    datagrams
+}
+
+/// Applies the entities in a datagram to the world.
+fn apply<'a>(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    lines: impl Iterator<Item = &'a str>,
+    interval: f32,
+) {
+    let mut entity = None;
+    for line in lines {
+        let (name, value) = line.split_once(": ").unwrap_or((line, ""));
+        if name == "Guid" {
+            let guid = match u64::from_str_radix(value, 16) {
+                Ok(guid) => Guid(guid),
+                Err(_) => {
+                    entity = None;
+                    continue;
+                }
+            };
+            entity = Some(query::find_by_guid(world, guid).unwrap_or_else(|| world.spawn((guid,))));
+            continue;
+        }
+
+        let entity = match entity {
+            Some(entity) => entity,
+            None => continue,
+        };
+
+        // Entities which already have a transform move to the new one smoothly.
+        if name == Transform::NAME {
+            let from = world.get::<Transform>(entity).ok().map(|t| (*t).clone());
+            if let (Some(from), Some(to)) = (from, Transform::deserialise(value)) {
+                let interpolation = Interpolation {
+                    from,
+                    to,
+                    elapsed: 0.0,
+                    interval,
+                };
+                world.insert_one(entity, interpolation).ok();
+                continue;
+            }
+        }
+
+        let mut builder = EntityBuilder::new();
+        match registry.get(name) {
+            Some(registration) if (registration.deserialise)(value, &mut builder) => {
+                world.insert(entity, builder.build()).ok();
+            }
+            _ => warn!("invalid replicated {} component: {}", name, value),
+        }
+    }
+}
+
+/// Moves a client's entity smoothly from where it was to where the server last said it was.
+struct Interpolation {
+    from: Transform,
+    to: Transform,
+    elapsed: f32,
+    interval: f32,
+}
+
+fn interpolate(world: &mut World, dt: f32) {
+    for (_, (transform, i)) in world.query_mut::<(&mut Transform, &mut Interpolation)>() {
+        i.elapsed += dt;
+        let t = if i.interval > 0.0 {
+            (i.elapsed / i.interval).min(1.0)
+        } else {
+            1.0
+        };
+
+        transform.position = i.from.position.lerp(i.to.position, t);
+        transform.size = i.from.size.lerp(i.to.size, t);
+        transform.scale = i.from.scale.lerp(i.to.scale, t);
+        transform.rotation = i.from.rotation + (i.to.rotation - i.from.rotation) * t;
+        transform.origin = i.to.origin;
+    }
+}
@@ -0,0 +1,217 @@
+use crate::components::{BodyKind, Collider, ColliderShape, RigidBody, Transform, Velocity};
+use crate::time::Time;
+use glam::Vec2;
+use hecs::{Entity, World};
+use rapier2d::prelude::*;
+use std::collections::HashMap;
+
+/// Simulates the entities with a [`RigidBody`] or [`Collider`] with rapier, writing where the
+/// dynamic bodies end up back into their [`Transform`]s.
+///
+/// Bodies are created the first time an entity is seen, starting from its [`Velocity`] if it has
+/// one, and recreated if its body or collider is changed, e.g. from the inspector.
+pub struct Physics {
+    pipeline: PhysicsPipeline,
+    parameters: IntegrationParameters,
+    islands: IslandManager,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    impulse_joints: ImpulseJointSet,
+    multibody_joints: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    handles: HashMap<Entity, Handle>,
+}
+
+/// An entity's body and the components it was created from.
+struct Handle {
+    body: RigidBodyHandle,
+    rigid_body: Option<RigidBody>,
+    collider: Option<Collider>,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            pipeline: PhysicsPipeline::new(),
+            parameters: IntegrationParameters {
+                dt: Time::FIXED_DELTA.as_secs_f32(),
+                ..IntegrationParameters::default()
+            },
+            islands: IslandManager::new(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            impulse_joints: ImpulseJointSet::new(),
+            multibody_joints: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            handles: HashMap::new(),
+        }
+    }
+}
+
+impl Physics {
+    /// Forgets every body, e.g. when the world is replaced by another scene.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Moves the simulation on by one [`Time::FIXED_DELTA`].
+    pub fn step(&mut self, world: &mut World, gravity: Vec2) {
+        self.sync_bodies(world);
+
+        self.pipeline.step(
+            &vector![gravity.x, gravity.y],
+            &self.parameters,
+            &mut self.islands,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.impulse_joints,
+            &mut self.multibody_joints,
+            &mut self.ccd_solver,
+            &(),
+            &(),
+        );
+
+        for (entity, handle) in &self.handles {
+            if handle.rigid_body.map(|body| body.kind) != Some(BodyKind::Dynamic) {
+                continue;
+            }
+
+            if let (Some(body), Ok(mut transform)) = (
+                self.bodies.get(handle.body),
+                world.get_mut::<Transform>(*entity),
+            ) {
+                let translation = body.translation();
+                transform.position = Vec2::new(translation.x, translation.y);
+                transform.rotation = -body.rotation().angle().to_degrees();
+            }
+        }
+    }
+
+    /// Creates, recreates and removes bodies to match the world, and moves kinematic bodies to
+    /// where their transforms are.
+    fn sync_bodies(&mut self, world: &World) {
+        let mut query = world.query::<(&Transform, Option<&RigidBody>, Option<&Collider>)>();
+        let simulated: HashMap<Entity, _> = query
+            .iter()
+            .filter(|(_, (_, rigid_body, collider))| rigid_body.is_some() || collider.is_some())
+            .map(|(entity, (transform, rigid_body, collider))| {
+                (entity, (transform, rigid_body.copied(), collider.copied()))
+            })
+            .collect();
+
+        let stale: Vec<Entity> = self
+            .handles
+            .iter()
+            .filter(|(entity, handle)| match simulated.get(entity) {
+                Some((_, rigid_body, collider)) => {
+                    handle.rigid_body != *rigid_body || handle.collider != *collider
+                }
+                None => true,
+            })
+            .map(|(entity, _)| *entity)
+            .collect();
+        for entity in stale {
+            if let Some(handle) = self.handles.remove(&entity) {
+                self.bodies.remove(
+                    handle.body,
+                    &mut self.islands,
+                    &mut self.colliders,
+                    &mut self.impulse_joints,
+                    &mut self.multibody_joints,
+                    true,
+                );
+            }
+        }
+
+        for (entity, (transform, rigid_body, collider)) in simulated {
+            match self.handles.get(&entity) {
+                Some(handle) => {
+                    if rigid_body.map(|body| body.kind) == Some(BodyKind::Kinematic) {
+                        if let Some(body) = self.bodies.get_mut(handle.body) {
+                            body.set_next_kinematic_position(isometry(transform));
+                        }
+                    }
+                }
+                None => {
+                    let velocity = world.get::<Velocity>(entity).ok().map(|v| *v);
+                    let handle = self.insert(transform, rigid_body, collider, velocity);
+                    self.handles.insert(entity, handle);
+                }
+            }
+        }
+    }
+
+    fn insert(
+        &mut self,
+        transform: &Transform,
+        rigid_body: Option<RigidBody>,
+        collider: Option<Collider>,
+        velocity: Option<Velocity>,
+    ) -> Handle {
+        let (body_type, gravity_scale) = match rigid_body {
+            Some(RigidBody {
+                kind,
+                gravity_scale,
+            }) => {
+                let body_type = match kind {
+                    BodyKind::Dynamic => RigidBodyType::Dynamic,
+                    BodyKind::Fixed => RigidBodyType::Fixed,
+                    BodyKind::Kinematic => RigidBodyType::KinematicPositionBased,
+                };
+                (body_type, gravity_scale)
+            }
+            None => (RigidBodyType::Fixed, 0.0),
+        };
+        let velocity = velocity.unwrap_or_default();
+        let body = RigidBodyBuilder::new(body_type)
+            .position(isometry(transform))
+            .gravity_scale(gravity_scale)
+            .linvel(vector![velocity.linear.x, velocity.linear.y])
+            .angvel(-velocity.angular.to_radians())
+            .build();
+        let body_handle = self.bodies.insert(body);
+
+        if let Some(collider) = collider {
+            // The body sits at the transform's origin, so the shape is moved to be centred on the
+            // entity.
+            let size = transform.size * transform.scale;
+            let centre = (Vec2::splat(0.5) - transform.origin) * size;
+            let builder = match collider.shape {
+                ColliderShape::Box => {
+                    ColliderBuilder::cuboid(size.x.abs() / 2.0, size.y.abs() / 2.0)
+                }
+                ColliderShape::Circle => {
+                    ColliderBuilder::ball(size.x.abs().min(size.y.abs()) / 2.0)
+                }
+            };
+            let collider = builder
+                .translation(vector![centre.x, centre.y])
+                .friction(collider.friction)
+                .restitution(collider.restitution)
+                .build();
+            self.colliders
+                .insert_with_parent(collider, body_handle, &mut self.bodies);
+        }
+
+        Handle {
+            body: body_handle,
+            rigid_body,
+            collider,
+        }
+    }
+}
+
+/// Where the body for an entity with this transform goes. Transforms rotate clockwise in degrees,
+/// and rapier anticlockwise in radians.
+fn isometry(transform: &Transform) -> Isometry<Real> {
+    Isometry::new(
+        vector![transform.position.x, transform.position.y],
+        -transform.rotation.to_radians(),
+    )
+}
@@ -1,18 +1,200 @@
 use crate::error::Error;
+use glam::Vec2;
 use log::info;
+use std::path::Path;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event_loop::EventLoop;
-use winit::window::{Window, WindowBuilder};
+use winit::window::{Fullscreen, Icon, Window, WindowBuilder};
 use winit_input_helper::WinitInputHelper;
 
+/// How the window takes up the screen. Alt+Enter switches between windowed and borderless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    /// A window without a border covering the whole monitor, which is quick to switch to and from.
+    Borderless,
+    /// Takes over the monitor at its highest resolution and refresh rate.
+    Fullscreen,
+}
+
+impl Default for WindowMode {
+    fn default() -> Self {
+        WindowMode::Windowed
+    }
+}
+
+impl WindowMode {
+    pub const ALL: [WindowMode; 3] = [
+        WindowMode::Windowed,
+        WindowMode::Borderless,
+        WindowMode::Fullscreen,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowMode::Windowed => "windowed",
+            WindowMode::Borderless => "borderless",
+            WindowMode::Fullscreen => "fullscreen",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+/// How many physical pixels there are to each logical point on the monitor the window is on, for
+/// converting between the points windows and UI are laid out in and the pixels the renderer draws.
+/// The window's scale factor can change while it's open, e.g. when it's moved to another monitor,
+/// so it's best taken from the [`Renderer`](crate::renderer::Renderer) each frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaleFactor(pub f64);
+
+impl Default for ScaleFactor {
+    fn default() -> Self {
+        ScaleFactor(1.0)
+    }
+}
+
+impl ScaleFactor {
+    pub fn to_physical(self, logical: Vec2) -> Vec2 {
+        logical * self.0 as f32
+    }
+
+    pub fn to_logical(self, physical: Vec2) -> Vec2 {
+        physical / self.0 as f32
+    }
+
+    /// The size in whole pixels of something `logical` points in size, e.g. a texture to draw a
+    /// view of that size into.
+    pub fn physical_size(self, logical: Vec2) -> (u32, u32) {
+        let physical = self.to_physical(logical).round();
+        (physical.x as u32, physical.y as u32)
+    }
+}
+
 pub fn init() -> Result<(EventLoop<()>, Window, WinitInputHelper), Error> {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
         .with_title("Alpha Engine")
-        .build(&event_loop)
-        .unwrap();
+        .build(&event_loop)?;
+    #[cfg(target_arch = "wasm32")]
+    attach_canvas(&window)?;
     let input = WinitInputHelper::new();
 
     info!("platform initialised");
 
     Ok((event_loop, window, input))
 }
+
+/// In the browser, the window is a canvas, which has to be added to the page to be seen.
+#[cfg(target_arch = "wasm32")]
+fn attach_canvas(window: &Window) -> Result<(), Error> {
+    use winit::platform::web::WindowExtWebSys;
+
+    let canvas = web_sys::Element::from(window.canvas());
+    web_sys::window()
+        .and_then(|page| page.document())
+        .and_then(|document| document.body())
+        .and_then(|body| body.append_child(&canvas).ok())
+        .ok_or(Error::Canvas)?;
+
+    Ok(())
+}
+
+pub(crate) fn set_title(window: &Window, title: &str) {
+    window.set_title(title);
+}
+
+/// Loads the icon from an image file, typically a small square PNG shipped with the game.
+pub(crate) fn set_icon(window: &Window, path: &Path) -> Result<(), Error> {
+    let image = image::open(path)
+        .map_err(|e| Error::Texture(path.to_path_buf(), e))?
+        .to_rgba8();
+    let (width, height) = image.dimensions();
+    let icon = Icon::from_rgba(image.into_raw(), width, height)
+        .map_err(|e| Error::Icon(path.to_path_buf(), e))?;
+    window.set_window_icon(Some(icon));
+
+    Ok(())
+}
+
+/// Goes fullscreen on the monitor numbered `monitor`, in the order the platform lists them, or on
+/// the monitor the window is on if it's `None` or there's no such monitor.
+pub(crate) fn set_window_mode(window: &Window, mode: WindowMode, monitor: Option<usize>) {
+    let monitor = monitor
+        .and_then(|monitor| window.available_monitors().nth(monitor))
+        .or_else(|| window.current_monitor());
+    let fullscreen = match mode {
+        WindowMode::Windowed => None,
+        WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+        WindowMode::Fullscreen => {
+            let video_mode = monitor.clone().and_then(|monitor| {
+                monitor.video_modes().max_by_key(|video_mode| {
+                    let size = video_mode.size();
+                    (size.width * size.height, video_mode.refresh_rate())
+                })
+            });
+            match video_mode {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                // Not every platform can change video mode.
+                None => Some(Fullscreen::Borderless(monitor)),
+            }
+        }
+    };
+    window.set_fullscreen(fullscreen);
+
+    info!("window mode {}", mode.name());
+}
+
+/// Where a window is and how big it is, in pixels, to open it the same way next time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+impl WindowGeometry {
+    /// The window's geometry, keeping the position and size it had before it was maximised,
+    /// minimised or made fullscreen from `previous`, so it's restored to them next time.
+    pub fn of(window: &Window, previous: Option<Self>) -> Option<Self> {
+        if window.fullscreen().is_some() {
+            return previous;
+        }
+
+        let maximized = window.is_maximized();
+        let size = window.inner_size();
+        match (previous, window.outer_position()) {
+            (Some(previous), _) if maximized || size.width == 0 || size.height == 0 => Some(Self {
+                maximized,
+                ..previous
+            }),
+            (_, Ok(position)) if size.width > 0 && size.height > 0 => Some(Self {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+            }),
+            _ => previous,
+        }
+    }
+
+    /// Moves and sizes the window as it was. The position is only restored if it's still on one
+    /// of the monitors, which may have been unplugged or rearranged since.
+    pub fn restore(&self, window: &Window) {
+        let on_a_monitor = window.available_monitors().any(|monitor| {
+            let (position, size) = (monitor.position(), monitor.size());
+            (position.x..position.x + size.width as i32).contains(&self.x)
+                && (position.y..position.y + size.height as i32).contains(&self.y)
+        });
+        if on_a_monitor {
+            window.set_outer_position(PhysicalPosition::new(self.x, self.y));
+        }
+        window.set_inner_size(PhysicalSize::new(self.width, self.height));
+        window.set_maximized(self.maximized);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::error::Error;
+use crate::files;
+use crate::platform::WindowMode;
+use glam::Vec2;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Engine subsystems which a project can switch off, so that games which don't need them don't pay
+/// for initialising them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Features {
+    pub audio: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self { audio: true }
+    }
+}
+
+/// How much work scripts may do before they're stopped, so a runaway script can't hang the engine
+/// or use up all of its memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScriptLimits {
+    /// The number of instructions a script may run in each callback.
+    pub fuel: u64,
+    /// The most memory a script may use, in 64 KiB pages.
+    pub memory_pages: u32,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            memory_pages: 256,
+        }
+    }
+}
+
+/// How often the game updates and draws.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisplaySettings {
+    /// The most frames drawn each second, or 0 to draw as many as possible.
+    pub max_fps: u32,
+    /// Whether the game stops updating while its window is minimised. Nothing is drawn while
+    /// minimised either way.
+    pub pause_when_minimized: bool,
+    /// The mode the window starts in.
+    pub window_mode: WindowMode,
+    /// The monitor the window goes fullscreen on, numbered from 0, or `None` for whichever one
+    /// it's on.
+    pub monitor: Option<usize>,
+}
+
+/// How the engine logs, read before anything else starts. The `ALPHA_LOG` environment variable
+/// overrides the level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogSettings {
+    /// The level to log at, optionally followed by the levels for particular modules, e.g.
+    /// `info, alpha::scripting=debug`.
+    pub level: String,
+    /// Where to write log files, instead of only printing to the terminal.
+    pub directory: Option<PathBuf>,
+    /// Starts a new log file once the current one grows this big, or 0 to keep using one.
+    pub max_file_mb: u64,
+    /// How many log files to keep besides the current one when starting new ones.
+    pub keep_files: usize,
+    /// Whether to print to the terminal as well when writing log files.
+    pub stderr: bool,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: String::from("info"),
+            directory: None,
+            max_file_mb: 10,
+            keep_files: 5,
+            stderr: true,
+        }
+    }
+}
+
+/// How the physics simulation behaves, with the `physics` feature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsSettings {
+    /// The acceleration of every dynamic body, in units per second squared.
+    pub gravity: Vec2,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            gravity: Vec2::new(0.0, -980.0),
+        }
+    }
+}
+
+/// What goes into a build besides what the startup scene refers to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuildSettings {
+    /// Scenes, scripts and other files to pack whether anything refers to them or not, e.g. scenes
+    /// the game loads by name.
+    pub keep: Vec<PathBuf>,
+}
+
+/// Settings which apply to the whole project rather than a single scene, stored in an ini style
+/// file:
+///
+/// ```text
+/// [project]
+/// name = platformer
+/// title = Platformer
+/// icon = assets/icon.png
+///
+/// [features]
+/// audio = false
+///
+/// [scripts]
+/// fuel = 10000000
+/// memory_pages = 256
+///
+/// [display]
+/// max_fps = 60
+/// pause_when_minimized = true
+/// window_mode = borderless
+/// monitor = 1
+///
+/// [physics]
+/// gravity = 0 -980
+///
+/// [logging]
+/// level = info, alpha::scripting=debug
+/// directory = logs
+/// max_file_mb = 10
+/// keep_files = 5
+/// stderr = true
+///
+/// [build]
+/// keep = levels/bonus.alpha, scripts/cheats.wasm
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProjectSettings {
+    /// The game's name, which its saved games are kept under.
+    pub name: String,
+    /// The title of the game's window.
+    pub title: String,
+    /// A PNG to use as the window's icon.
+    pub icon: Option<PathBuf>,
+    pub features: Features,
+    pub scripts: ScriptLimits,
+    pub display: DisplaySettings,
+    pub physics: PhysicsSettings,
+    pub logging: LogSettings,
+    pub build: BuildSettings,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            name: String::from("alpha_game"),
+            title: String::from("Alpha Game"),
+            icon: None,
+            features: Features::default(),
+            scripts: ScriptLimits::default(),
+            display: DisplaySettings::default(),
+            physics: PhysicsSettings::default(),
+            logging: LogSettings::default(),
+            build: BuildSettings::default(),
+        }
+    }
+}
+
+impl ProjectSettings {
+    pub const FILENAME: &'static str = "alpha_project.ini";
+
+    /// Reads the settings from the given file, falling back to the defaults for anything missing.
+    pub fn load<P: AsRef<Path>>(filename: P) -> Self {
+        let path = filename.as_ref();
+        match files::read_to_string(path) {
+            Ok(source) => Self::parse(&source),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                warn!("unable to read {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(source: &str) -> Self {
+        let mut settings = Self::default();
+        let mut section = "";
+
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name;
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => {
+                    warn!("ignoring invalid project setting: {}", line);
+                    continue;
+                }
+            };
+
+            let valid = match (section, key) {
+                ("project", "name") if !value.is_empty() => {
+                    settings.name = value.to_string();
+                    true
+                }
+                ("project", "title") => {
+                    settings.title = value.to_string();
+                    true
+                }
+                ("project", "icon") => {
+                    settings.icon = Some(value)
+                        .filter(|value| !value.is_empty())
+                        .map(PathBuf::from);
+                    true
+                }
+                ("features", "audio") => value.parse().map(|v| settings.features.audio = v).is_ok(),
+                ("scripts", "fuel") => value.parse().map(|v| settings.scripts.fuel = v).is_ok(),
+                ("scripts", "memory_pages") => value
+                    .parse()
+                    .map(|v| settings.scripts.memory_pages = v)
+                    .is_ok(),
+                ("display", "max_fps") => {
+                    value.parse().map(|v| settings.display.max_fps = v).is_ok()
+                }
+                ("display", "pause_when_minimized") => value
+                    .parse()
+                    .map(|v| settings.display.pause_when_minimized = v)
+                    .is_ok(),
+                ("display", "window_mode") => WindowMode::parse(value)
+                    .map(|mode| settings.display.window_mode = mode)
+                    .is_some(),
+                ("display", "monitor") if value.is_empty() => {
+                    settings.display.monitor = None;
+                    true
+                }
+                ("display", "monitor") => value
+                    .parse()
+                    .map(|v| settings.display.monitor = Some(v))
+                    .is_ok(),
+                ("physics", "gravity") => match value.split_once(' ') {
+                    Some((x, y)) => match (x.trim().parse(), y.trim().parse()) {
+                        (Ok(x), Ok(y)) => {
+                            settings.physics.gravity = Vec2::new(x, y);
+                            true
+                        }
+                        _ => false,
+                    },
+                    None => false,
+                },
+                ("logging", "level") if !value.is_empty() => {
+                    settings.logging.level = value.to_string();
+                    true
+                }
+                ("logging", "directory") => {
+                    settings.logging.directory = Some(value)
+                        .filter(|value| !value.is_empty())
+                        .map(PathBuf::from);
+                    true
+                }
+                ("logging", "max_file_mb") => value
+                    .parse()
+                    .map(|v| settings.logging.max_file_mb = v)
+                    .is_ok(),
+                ("logging", "keep_files") => value
+                    .parse()
+                    .map(|v| settings.logging.keep_files = v)
+                    .is_ok(),
+                ("logging", "stderr") => value.parse().map(|v| settings.logging.stderr = v).is_ok(),
+                ("build", "keep") => {
+                    settings.build.keep = parse_paths(value);
+                    true
+                }
+                _ => false,
+            };
+            if !valid {
+                warn!("ignoring invalid project setting: [{}] {}", section, line);
+            }
+        }
+
+        settings
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, filename: P) -> Result<(), Error> {
+        let contents = format!(
+            "[project]\nname = {}\ntitle = {}\nicon = {}\n\n[features]\naudio = {}\n\n[scripts]\nfuel = {}\nmemory_pages = {}\n\n\
+             [display]\nmax_fps = {}\npause_when_minimized = {}\nwindow_mode = {}\nmonitor = {}\n\n\
+             [physics]\ngravity = {} {}\n\n\
+             [logging]\nlevel = {}\ndirectory = {}\nmax_file_mb = {}\nkeep_files = {}\nstderr = {}\n\n\
+             [build]\nkeep = {}\n",
+            self.name,
+            self.title,
+            self.icon
+                .as_ref()
+                .map(|icon| icon.display().to_string())
+                .unwrap_or_default(),
+            self.features.audio,
+            self.scripts.fuel,
+            self.scripts.memory_pages,
+            self.display.max_fps,
+            self.display.pause_when_minimized,
+            self.display.window_mode.name(),
+            self.display
+                .monitor
+                .map(|monitor| monitor.to_string())
+                .unwrap_or_default(),
+            self.physics.gravity.x,
+            self.physics.gravity.y,
+            self.logging.level,
+            self.logging
+                .directory
+                .as_ref()
+                .map(|directory| directory.display().to_string())
+                .unwrap_or_default(),
+            self.logging.max_file_mb,
+            self.logging.keep_files,
+            self.logging.stderr,
+            join_paths(&self.build.keep),
+        );
+        let path = filename.as_ref();
+        fs::write(path, contents).map_err(|e| Error::Io(path.to_path_buf(), e))
+    }
+}
+
+/// Reads a list of paths separated by commas, as written by [`join_paths`].
+pub(crate) fn parse_paths(value: &str) -> Vec<PathBuf> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+pub(crate) fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,49 @@
+use crate::components::{Guid, Tag};
+use hecs::{Entity, World};
+use std::collections::HashSet;
+
+/// Finds the first entity with the given tag.
+pub fn find_by_tag(world: &World, tag: &str) -> Option<Entity> {
+    world
+        .query::<&Tag>()
+        .iter()
+        .find(|(_, t)| t.0 == tag)
+        .map(|(entity, _)| entity)
+}
+
+/// Finds the entity with the given GUID.
+pub fn find_by_guid(world: &World, guid: Guid) -> Option<Entity> {
+    world
+        .query::<&Guid>()
+        .iter()
+        .find(|(_, g)| **g == guid)
+        .map(|(entity, _)| entity)
+}
+
+/// Returns `tag` if no entity has it yet, otherwise numbers it, e.g. `Entity (2)`, `Entity (3)`.
+pub fn unique_tag(world: &World, tag: &str) -> String {
+    let tags: HashSet<String> = world
+        .query::<&Tag>()
+        .iter()
+        .map(|(_, t)| t.0.clone())
+        .collect();
+
+    if !tags.contains(tag) {
+        return tag.to_string();
+    }
+
+    // Duplicating "Entity (2)" should give "Entity (3)" rather than "Entity (2) (2)".
+    let base = match tag.rsplit_once(" (") {
+        Some((base, suffix))
+            if suffix.ends_with(')') && suffix[..suffix.len() - 1].parse::<usize>().is_ok() =>
+        {
+            base
+        }
+        _ => tag,
+    };
+
+    (2..)
+        .map(|n| format!("{} ({})", base, n))
+        .find(|candidate| !tags.contains(candidate))
+        .unwrap()
+}
@@ -0,0 +1,97 @@
+use crate::components::Guid;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use std::collections::HashMap;
+
+/// Seeded random numbers, so that a game which uses randomness can be played again exactly the
+/// same way, e.g. to track down a bug, by giving it the same seed.
+///
+/// Besides its own stream, which it gives out as an [`RngCore`] so that all of `rand`'s [`Rng`]
+/// methods can be used, it has a separate stream for each system and each entity. Each stream
+/// only depends on the seed and its own name or [`Guid`], so a system which starts using
+/// random numbers, or an entity which is added, doesn't change the numbers the others get.
+///
+/// [`Rng`]: rand::Rng
+pub struct Random {
+    seed: u64,
+    global: StdRng,
+    streams: HashMap<String, StdRng>,
+    entities: HashMap<Guid, StdRng>,
+}
+
+impl Default for Random {
+    /// Picks a seed at random.
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            global: StdRng::seed_from_u64(seed),
+            streams: HashMap::new(),
+            entities: HashMap::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Starts every stream again from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// The stream for a system, or anything else with a name of its own.
+    pub fn stream(&mut self, name: &str) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams
+            .entry(name.to_string())
+            .or_insert_with(|| StdRng::seed_from_u64(mix(seed, fnv1a(name.as_bytes()))))
+    }
+
+    /// The stream for an entity.
+    pub fn entity(&mut self, guid: Guid) -> &mut StdRng {
+        let seed = self.seed;
+        self.entities
+            .entry(guid)
+            .or_insert_with(|| StdRng::seed_from_u64(mix(seed, guid.0)))
+    }
+}
+
+impl RngCore for Random {
+    fn next_u32(&mut self) -> u32 {
+        self.global.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.global.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.global.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.global.try_fill_bytes(dest)
+    }
+}
+
+/// Combines the seed with a stream's key, so that similar keys still get unrelated seeds.
+fn mix(seed: u64, key: u64) -> u64 {
+    // SplitMix64's finaliser.
+    let mut z = seed ^ key.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A hash which is the same on every platform and every run, unlike the standard library's.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
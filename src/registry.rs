@@ -0,0 +1,100 @@
+use egui::Ui;
+use hecs::{Component, Entity, EntityBuilder, World};
+
+mod builtin;
+
+pub(crate) use builtin::set_external_editor;
+
+/// A component which can be saved to scene files and edited in the editor's properties panel.
+pub trait Reflect: Component + Default {
+    /// The name used for the component in scene files and the editor.
+    const NAME: &'static str;
+
+    /// Writes the component as a single line of text.
+    fn serialise(&self) -> String;
+
+    /// Reads a component written by [`Reflect::serialise`].
+    fn deserialise(value: &str) -> Option<Self>;
+
+    /// Draws widgets to edit the component, returning `true` if anything was changed.
+    fn inspect(&mut self, ui: &mut Ui) -> bool;
+}
+
+/// Type erased access to a [`Reflect`] component, so that code which handles every kind of
+/// component doesn't need to know about each of them.
+#[derive(Clone, Copy)]
+pub struct Registration {
+    pub name: &'static str,
+    pub has: fn(&World, Entity) -> bool,
+    pub serialise: fn(&World, Entity) -> Option<String>,
+    pub deserialise: fn(&str, &mut EntityBuilder) -> bool,
+    pub inspect: fn(&mut World, Entity, &mut Ui) -> bool,
+    pub insert_default: fn(&mut World, Entity),
+    pub remove: fn(&mut World, Entity),
+}
+
+impl Registration {
+    fn of<T: Reflect>() -> Self {
+        Self {
+            name: T::NAME,
+            has: |world, entity| world.get::<T>(entity).is_ok(),
+            serialise: |world, entity| world.get::<T>(entity).ok().map(|c| c.serialise()),
+            deserialise: |value, builder| match T::deserialise(value) {
+                Some(component) => {
+                    builder.add(component);
+                    true
+                }
+                None => false,
+            },
+            inspect: |world, entity, ui| match world.get_mut::<T>(entity) {
+                Ok(mut component) => component.inspect(ui),
+                Err(_) => false,
+            },
+            insert_default: |world, entity| {
+                world.insert_one(entity, T::default()).ok();
+            },
+            remove: |world, entity| {
+                world.remove_one::<T>(entity).ok();
+            },
+        }
+    }
+}
+
+/// The set of components known to scene files and the editor, in the order they're listed.
+pub struct ComponentRegistry {
+    registrations: Vec<Registration>,
+}
+
+impl Default for ComponentRegistry {
+    /// Creates a registry containing all of the engine's own components.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        builtin::register(&mut registry);
+        registry
+    }
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Registers `T`, replacing any component already registered with the same name.
+    pub fn register<T: Reflect>(&mut self) {
+        let registration = Registration::of::<T>();
+        match self.registrations.iter_mut().find(|r| r.name == T::NAME) {
+            Some(existing) => *existing = registration,
+            None => self.registrations.push(registration),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Registration> {
+        self.registrations.iter().find(|r| r.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Registration> {
+        self.registrations.iter()
+    }
+}
@@ -0,0 +1,1411 @@
+use crate::animation::{
+    AnimatedProperty, AnimatedSprite, Animation, AnimationPreset, Easing, Keyframe, SpriteClip,
+    Track,
+};
+use crate::assets::Handle;
+use crate::audio::{AudioSource, Occlusion};
+use crate::components::{
+    Acceleration, Collider, ColliderShape, CollisionLayers, Script, ScriptParameter, Shape, Sprite,
+    Tag, Transform, Velocity, Visible,
+};
+#[cfg(feature = "physics")]
+use crate::components::{BodyKind, RigidBody};
+use crate::navigation::PathFollower;
+#[cfg(feature = "network")]
+use crate::network::Replicated;
+use crate::registry::{ComponentRegistry, Reflect};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scripting;
+use crate::sub_scene::SubScene;
+use crate::tilemap::Tilemap;
+use egui::{DragValue, Slider, Ui};
+use glam::{Vec2, Vec4};
+use log::error;
+use std::cmp::Ordering;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+pub(super) fn register(registry: &mut ComponentRegistry) {
+    registry.register::<Tag>();
+    registry.register::<Transform>();
+    registry.register::<Shape>();
+    registry.register::<Sprite>();
+    registry.register::<Visible>();
+    registry.register::<Velocity>();
+    registry.register::<Acceleration>();
+    registry.register::<CollisionLayers>();
+    registry.register::<Collider>();
+    #[cfg(feature = "physics")]
+    registry.register::<RigidBody>();
+    registry.register::<Script>();
+    registry.register::<Animation>();
+    registry.register::<AnimatedSprite>();
+    registry.register::<Tilemap>();
+    registry.register::<PathFollower>();
+    registry.register::<SubScene>();
+    registry.register::<AudioSource>();
+    #[cfg(feature = "network")]
+    registry.register::<Replicated>();
+}
+
+impl Reflect for Tag {
+    const NAME: &'static str = "Tag";
+
+    fn serialise(&self) -> String {
+        self.0.clone()
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        Some(Self(value.to_string()))
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        ui.label("Tag");
+        ui.text_edit_singleline(&mut self.0).changed()
+    }
+}
+
+impl Reflect for Transform {
+    const NAME: &'static str = "Transform";
+
+    fn serialise(&self) -> String {
+        format!(
+            "{} {} {} {} {} {} {} {} {}",
+            self.position.x,
+            self.position.y,
+            self.size.x,
+            self.size.y,
+            self.rotation,
+            self.scale.x,
+            self.scale.y,
+            self.origin.x,
+            self.origin.y
+        )
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        // Scale and then origin were added later, so older scenes don't have them.
+        let values = floats(value)?;
+        let (scale, origin) = match values.len() {
+            5 => (Vec2::ONE, Transform::ORIGIN_BOTTOM_LEFT),
+            7 => (
+                Vec2::new(values[5], values[6]),
+                Transform::ORIGIN_BOTTOM_LEFT,
+            ),
+            9 => (
+                Vec2::new(values[5], values[6]),
+                Vec2::new(values[7], values[8]),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            position: Vec2::new(values[0], values[1]),
+            size: Vec2::new(values[2], values[3]),
+            rotation: values[4],
+            scale,
+            origin,
+        })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let default = Transform::default();
+
+        // Whether width and height are scaled together lives in egui's memory, as it's a
+        // setting of the inspector rather than part of the component.
+        let link_id = ui.id().with("Link Size");
+        let mut linked = ui.data().get_temp::<bool>(link_id).unwrap_or(false);
+
+        egui::Grid::new("Transform Grid")
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.label("Position");
+                changed |= vec2_edit(ui, &mut self.position, ["x", "y"], 1.0);
+                changed |= reset_button(ui, &mut self.position, default.position);
+                ui.end_row();
+
+                ui.label("Rotation");
+                changed |= ui
+                    .add(DragValue::new(&mut self.rotation).speed(1.0).suffix("°"))
+                    .changed();
+                changed |= reset_button(ui, &mut self.rotation, default.rotation);
+                ui.end_row();
+
+                ui.label("Size");
+                let size = self.size;
+                changed |= vec2_edit(ui, &mut self.size, ["w", "h"], 1.0);
+                if linked && self.size.x != size.x && size.x != 0.0 {
+                    self.size.y = size.y * self.size.x / size.x;
+                } else if linked && self.size.y != size.y && size.y != 0.0 {
+                    self.size.x = size.x * self.size.y / size.y;
+                }
+                ui.horizontal(|ui| {
+                    changed |= reset_button(ui, &mut self.size, default.size);
+                    ui.toggle_value(&mut linked, "🔗")
+                        .on_hover_text("Scale width and height together");
+                });
+                ui.end_row();
+
+                ui.label("Scale");
+                changed |= vec2_edit(ui, &mut self.scale, ["x", "y"], 0.01);
+                changed |= reset_button(ui, &mut self.scale, default.scale);
+                ui.end_row();
+
+                ui.label("Origin");
+                changed |= vec2_edit(ui, &mut self.origin, ["x", "y"], 0.01);
+                changed |= reset_button(ui, &mut self.origin, default.origin);
+                ui.end_row();
+            });
+
+        ui.data().insert_temp(link_id, linked);
+
+        ui.horizontal(|ui| {
+            if ui.button("Bottom Left").clicked() {
+                self.origin = Transform::ORIGIN_BOTTOM_LEFT;
+                changed = true;
+            }
+            if ui.button("Centre").clicked() {
+                self.origin = Transform::ORIGIN_CENTRE;
+                changed = true;
+            }
+        });
+
+        changed
+    }
+}
+
+impl Reflect for Shape {
+    const NAME: &'static str = "Shape";
+
+    fn serialise(&self) -> String {
+        color(self.color)
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match floats(value)?.as_slice() {
+            [r, g, b, a] => Some(Self {
+                color: Vec4::new(*r, *g, *b, *a),
+            }),
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        ui.label("Color");
+        color_edit(ui, &mut self.color)
+    }
+}
+
+impl Reflect for Sprite {
+    const NAME: &'static str = "Sprite";
+
+    /// The texture path goes last, as it's the only value which may contain spaces.
+    fn serialise(&self) -> String {
+        format!(
+            "{} {} {} {} {} {} {} {}",
+            self.uv_min.x,
+            self.uv_min.y,
+            self.uv_max.x,
+            self.uv_max.y,
+            self.flip_x,
+            self.flip_y,
+            color(self.tint),
+            self.texture.display()
+        )
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        let values: Vec<&str> = value.splitn(11, ' ').collect();
+        if values.len() != 11 {
+            return None;
+        }
+
+        let uv = floats(&values[0..4].join(" "))?;
+        let tint = floats(&values[6..10].join(" "))?;
+
+        Some(Self {
+            texture: Handle::new(values[10]),
+            uv_min: Vec2::new(uv[0], uv[1]),
+            uv_max: Vec2::new(uv[2], uv[3]),
+            flip_x: bool::from_str(values[4]).ok()?,
+            flip_y: bool::from_str(values[5]).ok()?,
+            tint: Vec4::from_slice(&tint),
+        })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Texture");
+        ui.horizontal(|ui| {
+            ui.label(self.texture.display().to_string());
+            if ui.button("📂").clicked() {
+                let file = pick_file("Image", &["png", "jpg", "jpeg"]);
+                if let Some(path) = file {
+                    self.texture = Handle::new(path);
+                    changed = true;
+                }
+            }
+        });
+
+        ui.label("UV Min");
+        changed |= ui
+            .add(Slider::new(&mut self.uv_min.x, 0.0..=1.0).text("u"))
+            .changed();
+        changed |= ui
+            .add(Slider::new(&mut self.uv_min.y, 0.0..=1.0).text("v"))
+            .changed();
+
+        ui.label("UV Max");
+        changed |= ui
+            .add(Slider::new(&mut self.uv_max.x, 0.0..=1.0).text("u"))
+            .changed();
+        changed |= ui
+            .add(Slider::new(&mut self.uv_max.y, 0.0..=1.0).text("v"))
+            .changed();
+
+        ui.horizontal(|ui| {
+            changed |= ui.checkbox(&mut self.flip_x, "Flip X").changed();
+            changed |= ui.checkbox(&mut self.flip_y, "Flip Y").changed();
+        });
+
+        ui.label("Tint");
+        changed |= color_edit(ui, &mut self.tint);
+
+        changed
+    }
+}
+
+impl Reflect for Visible {
+    const NAME: &'static str = "Visible";
+
+    fn serialise(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        bool::from_str(value).ok().map(Self)
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        ui.checkbox(&mut self.0, "Visible").changed()
+    }
+}
+
+impl Reflect for Velocity {
+    const NAME: &'static str = "Velocity";
+
+    fn serialise(&self) -> String {
+        format!("{} {} {}", self.linear.x, self.linear.y, self.angular)
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match floats(value)?.as_slice() {
+            [x, y, angular] => Some(Self {
+                linear: Vec2::new(*x, *y),
+                angular: *angular,
+            }),
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        linear_angular_edit(ui, &mut self.linear, &mut self.angular)
+    }
+}
+
+impl Reflect for Acceleration {
+    const NAME: &'static str = "Acceleration";
+
+    fn serialise(&self) -> String {
+        format!("{} {} {}", self.linear.x, self.linear.y, self.angular)
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match floats(value)?.as_slice() {
+            [x, y, angular] => Some(Self {
+                linear: Vec2::new(*x, *y),
+                angular: *angular,
+            }),
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        linear_angular_edit(ui, &mut self.linear, &mut self.angular)
+    }
+}
+
+impl Reflect for CollisionLayers {
+    const NAME: &'static str = "CollisionLayers";
+
+    fn serialise(&self) -> String {
+        format!("{:08x} {:08x}", self.layers, self.mask)
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match value.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [layers, mask] => Some(Self {
+                layers: u32::from_str_radix(layers, 16).ok()?,
+                mask: u32::from_str_radix(mask, 16).ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Layers");
+        changed |= bitmask_edit(ui, "Layers", &mut self.layers);
+
+        ui.label("Collides With");
+        changed |= bitmask_edit(ui, "Collides With", &mut self.mask);
+
+        changed
+    }
+}
+
+#[cfg(feature = "physics")]
+impl Reflect for RigidBody {
+    const NAME: &'static str = "RigidBody";
+
+    fn serialise(&self) -> String {
+        format!("{} {}", self.kind.name(), self.gravity_scale)
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match value.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [kind, gravity_scale] => Some(Self {
+                kind: BodyKind::parse(kind)?,
+                gravity_scale: gravity_scale.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Body Type");
+        egui::ComboBox::from_id_source("Body Type")
+            .selected_text(self.kind.name())
+            .show_ui(ui, |ui| {
+                for kind in BodyKind::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.kind, kind, kind.name())
+                        .changed();
+                }
+            });
+
+        ui.label("Gravity Scale");
+        let slider = Slider::new(&mut self.gravity_scale, 0.0..=2.0).clamp_to_range(false);
+        changed |= ui.add(slider).changed();
+
+        changed
+    }
+}
+
+impl Reflect for Collider {
+    const NAME: &'static str = "Collider";
+
+    fn serialise(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.shape.name(),
+            self.friction,
+            self.restitution
+        )
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match value.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [shape, friction, restitution] => Some(Self {
+                shape: ColliderShape::parse(shape)?,
+                friction: friction.parse().ok()?,
+                restitution: restitution.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Shape");
+        egui::ComboBox::from_id_source("Collider Shape")
+            .selected_text(self.shape.name())
+            .show_ui(ui, |ui| {
+                for shape in ColliderShape::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.shape, shape, shape.name())
+                        .changed();
+                }
+            });
+
+        ui.label("Friction");
+        changed |= ui.add(Slider::new(&mut self.friction, 0.0..=1.0)).changed();
+
+        ui.label("Restitution");
+        changed |= ui
+            .add(Slider::new(&mut self.restitution, 0.0..=1.0))
+            .changed();
+
+        changed
+    }
+}
+
+impl Reflect for Animation {
+    const NAME: &'static str = "Animation";
+
+    /// The number of tracks, then each track's property, whether it loops and its number of
+    /// keyframes, followed by its keyframes' times, easings and values. A preset which is yet to
+    /// be played comes last, e.g. `preset pulse`.
+    fn serialise(&self) -> String {
+        let mut value = self.tracks.len().to_string();
+        for track in &self.tracks {
+            value.push_str(&format!(
+                " {} {} {}",
+                track.property.name(),
+                track.looping,
+                track.keyframes.len()
+            ));
+            for keyframe in &track.keyframes {
+                value.push_str(&format!(
+                    " {} {} {}",
+                    keyframe.time,
+                    keyframe.easing.name(),
+                    color(keyframe.value)
+                ));
+            }
+        }
+        if let Some(preset) = self.preset {
+            value.push_str(&format!(" preset {}", preset.name()));
+        }
+        value
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        let mut values = value.split_whitespace();
+        let track_count: usize = values.next()?.parse().ok()?;
+
+        // As the count comes from the file, it isn't used to size anything up front.
+        let mut tracks = Vec::new();
+        for _ in 0..track_count {
+            let mut track = Track::new(AnimatedProperty::parse(values.next()?)?);
+            track.looping = values.next()?.parse().ok()?;
+            let key_count: usize = values.next()?.parse().ok()?;
+            for _ in 0..key_count {
+                let time = values.next()?.parse().ok()?;
+                let easing = Easing::parse(values.next()?)?;
+                let value = floats(&values.by_ref().take(4).collect::<Vec<_>>().join(" "))?;
+                if value.len() != 4 {
+                    return None;
+                }
+                track.keyframes.push(Keyframe {
+                    time,
+                    value: Vec4::from_slice(&value),
+                    easing,
+                });
+            }
+            tracks.push(track);
+        }
+
+        let preset = match values.next() {
+            Some("preset") => Some(AnimationPreset::parse(values.next()?)?),
+            Some(_) => return None,
+            None => None,
+        };
+        if values.next().is_some() {
+            return None;
+        }
+        Some(Self { tracks, preset })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let mut removed_track = None;
+
+        for (i, track) in self.tracks.iter_mut().enumerate() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(("Animation Property", i))
+                    .selected_text(track.property.name())
+                    .show_ui(ui, |ui| {
+                        for property in AnimatedProperty::ALL {
+                            changed |= ui
+                                .selectable_value(&mut track.property, property, property.name())
+                                .changed();
+                        }
+                    });
+                changed |= ui.checkbox(&mut track.looping, "Loop").changed();
+                if ui
+                    .button("⏮")
+                    .on_hover_text("Play from the start")
+                    .clicked()
+                {
+                    track.elapsed = 0.0;
+                }
+                if ui.button("🗑").clicked() {
+                    removed_track = Some(i);
+                }
+            });
+
+            let mut removed_key = None;
+            for (j, keyframe) in track.keyframes.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut keyframe.time)
+                                .speed(0.05)
+                                .clamp_range(0.0..=f32::MAX)
+                                .suffix("s"),
+                        )
+                        .changed();
+                    egui::ComboBox::from_id_source(("Animation Easing", i, j))
+                        .selected_text(keyframe.easing.name())
+                        .show_ui(ui, |ui| {
+                            for easing in Easing::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut keyframe.easing, easing, easing.name())
+                                    .changed();
+                            }
+                        });
+                    changed |= match track.property {
+                        AnimatedProperty::Color => color_edit(ui, &mut keyframe.value),
+                        AnimatedProperty::Rotation => ui
+                            .add(DragValue::new(&mut keyframe.value.x).suffix("°"))
+                            .changed(),
+                        AnimatedProperty::Position | AnimatedProperty::Scale => {
+                            ui.add(DragValue::new(&mut keyframe.value.x).speed(0.1))
+                                .changed()
+                                | ui.add(DragValue::new(&mut keyframe.value.y).speed(0.1))
+                                    .changed()
+                        }
+                    };
+                    if ui.button("➖").clicked() {
+                        removed_key = Some(j);
+                    }
+                });
+            }
+            if let Some(j) = removed_key {
+                track.keyframes.remove(j);
+                changed = true;
+            }
+            if ui.button("Add Keyframe").clicked() {
+                let keyframe = match track.keyframes.last() {
+                    Some(last) => Keyframe {
+                        time: last.time + 1.0,
+                        ..*last
+                    },
+                    None => Keyframe {
+                        time: 0.0,
+                        value: Vec4::ONE,
+                        easing: Easing::Linear,
+                    },
+                };
+                track.keyframes.push(keyframe);
+                changed = true;
+            }
+
+            if changed {
+                track
+                    .keyframes
+                    .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+            }
+        }
+
+        if let Some(i) = removed_track {
+            self.tracks.remove(i);
+            changed = true;
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Add Track").clicked() {
+                self.tracks.push(Track::new(AnimatedProperty::Position));
+                changed = true;
+            }
+            egui::ComboBox::from_id_source("Animation Preset")
+                .selected_text(self.preset.map_or("No Preset", |preset| preset.name()))
+                .show_ui(ui, |ui| {
+                    changed |= ui
+                        .selectable_value(&mut self.preset, None, "No Preset")
+                        .changed();
+                    for preset in AnimationPreset::ALL {
+                        changed |= ui
+                            .selectable_value(&mut self.preset, Some(preset), preset.name())
+                            .changed();
+                    }
+                })
+                .response
+                .on_hover_text("Played from the entity's own values when the game next updates");
+        });
+
+        changed
+    }
+}
+
+impl Reflect for AnimatedSprite {
+    const NAME: &'static str = "AnimatedSprite";
+
+    /// The atlas' columns and rows, the current clip, whether it's playing and the number of
+    /// clips, followed by each clip's name, first frame, frame count, frames per second and
+    /// whether it loops.
+    fn serialise(&self) -> String {
+        let mut value = format!(
+            "{} {} {} {} {}",
+            self.columns,
+            self.rows,
+            self.clip,
+            self.playing,
+            self.clips.len()
+        );
+        for clip in &self.clips {
+            value.push_str(&format!(
+                " {} {} {} {} {}",
+                clip.name, clip.first_frame, clip.frame_count, clip.frames_per_second, clip.looping
+            ));
+        }
+        value
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        let mut values = value.split_whitespace();
+        let mut sprite = Self {
+            columns: values.next()?.parse().ok()?,
+            rows: values.next()?.parse().ok()?,
+            clip: values.next()?.parse().ok()?,
+            playing: values.next()?.parse().ok()?,
+            ..Self::default()
+        };
+
+        let clip_count: usize = values.next()?.parse().ok()?;
+        for _ in 0..clip_count {
+            sprite.clips.push(SpriteClip {
+                name: values.next()?.to_string(),
+                first_frame: values.next()?.parse().ok()?,
+                frame_count: values.next()?.parse().ok()?,
+                frames_per_second: values.next()?.parse().ok()?,
+                looping: values.next()?.parse().ok()?,
+            });
+        }
+
+        if values.next().is_some() {
+            return None;
+        }
+        Some(sprite)
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Atlas");
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    DragValue::new(&mut self.columns)
+                        .clamp_range(1..=256)
+                        .suffix(" columns"),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    DragValue::new(&mut self.rows)
+                        .clamp_range(1..=256)
+                        .suffix(" rows"),
+                )
+                .changed();
+        });
+
+        ui.label("Clip");
+        let selected = self.current_clip().map_or("", |clip| clip.name.as_str());
+        let mut clip = self.clip;
+        egui::ComboBox::from_id_source("AnimatedSprite Clip")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for (i, c) in self.clips.iter().enumerate() {
+                    ui.selectable_value(&mut clip, i, &c.name);
+                }
+            });
+        if clip != self.clip {
+            self.set_clip_index(clip);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            changed |= ui.checkbox(&mut self.playing, "Playing").changed();
+            ui.toggle_value(&mut self.preview, "👁 Preview")
+                .on_hover_text("Play the clip in the scene view");
+            if ui
+                .button("⏮")
+                .on_hover_text("Play from the start")
+                .clicked()
+            {
+                self.elapsed = 0.0;
+            }
+        });
+        if self.preview {
+            ui.ctx().request_repaint();
+        }
+        if let Some(frame) = self.frame() {
+            ui.label(format!("Frame {}", frame));
+        }
+
+        let mut removed = None;
+        for (i, clip) in self.clips.iter_mut().enumerate() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.text_edit_singleline(&mut clip.name).changed() {
+                    clip.name.retain(|c| !c.is_whitespace());
+                    changed = true;
+                }
+                if ui.button("🗑").clicked() {
+                    removed = Some(i);
+                }
+            });
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(DragValue::new(&mut clip.first_frame).prefix("first "))
+                    .changed();
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut clip.frame_count)
+                            .clamp_range(1..=u32::MAX)
+                            .prefix("count "),
+                    )
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut clip.frames_per_second)
+                            .clamp_range(0.0..=120.0)
+                            .suffix(" fps"),
+                    )
+                    .changed();
+                changed |= ui.checkbox(&mut clip.looping, "Loop").changed();
+            });
+        }
+        if let Some(i) = removed {
+            self.clips.remove(i);
+            self.clip = self.clip.min(self.clips.len().saturating_sub(1));
+            changed = true;
+        }
+
+        ui.separator();
+        if ui.button("Add Clip").clicked() {
+            self.clips.push(SpriteClip {
+                name: format!("clip{}", self.clips.len()),
+                ..SpriteClip::default()
+            });
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+impl Reflect for Tilemap {
+    const NAME: &'static str = "Tilemap";
+
+    /// The tileset's columns and rows and the map's columns and rows, then the tile in each cell,
+    /// or `-` for empty cells. The tileset path goes last, as it's the only value which may
+    /// contain spaces.
+    fn serialise(&self) -> String {
+        let mut value = format!(
+            "{} {} {} {}",
+            self.tileset_columns,
+            self.tileset_rows,
+            self.columns(),
+            self.rows()
+        );
+        for tile in self.tiles() {
+            match tile {
+                Some(tile) => value.push_str(&format!(" {}", tile)),
+                None => value.push_str(" -"),
+            }
+        }
+        value.push_str(&format!(" {}", self.tileset.display()));
+        value
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        let values: Vec<&str> = value.splitn(5, ' ').collect();
+        let sizes: Vec<u32> = values
+            .get(0..4)?
+            .iter()
+            .map(|v| v.parse().ok())
+            .collect::<Option<_>>()?;
+        if sizes[2] > Tilemap::MAX_SIZE || sizes[3] > Tilemap::MAX_SIZE {
+            return None;
+        }
+        let cells = sizes[2].checked_mul(sizes[3])? as usize;
+
+        let rest: Vec<&str> = values.get(4)?.splitn(cells + 1, ' ').collect();
+        if rest.len() != cells + 1 {
+            return None;
+        }
+        let tiles = rest[..cells]
+            .iter()
+            .map(|tile| match *tile {
+                "-" => Some(None),
+                tile => tile.parse().ok().map(Some),
+            })
+            .collect::<Option<_>>()?;
+
+        let mut tilemap = Tilemap::new(rest[cells], sizes[0], sizes[1], sizes[2], sizes[3]);
+        tilemap.set_tiles(tiles).then(|| tilemap)
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Tileset");
+        ui.horizontal(|ui| {
+            ui.label(self.tileset.display().to_string());
+            if ui.button("📂").clicked() {
+                let file = pick_file("Image", &["png", "jpg", "jpeg"]);
+                if let Some(path) = file {
+                    self.tileset = Handle::new(path);
+                    changed = true;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    DragValue::new(&mut self.tileset_columns)
+                        .clamp_range(1..=256)
+                        .suffix(" columns"),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    DragValue::new(&mut self.tileset_rows)
+                        .clamp_range(1..=256)
+                        .suffix(" rows"),
+                )
+                .changed();
+        });
+
+        ui.label("Map");
+        let (mut columns, mut rows) = (self.columns(), self.rows());
+        ui.horizontal(|ui| {
+            ui.add(
+                DragValue::new(&mut columns)
+                    .clamp_range(1..=Tilemap::MAX_SIZE)
+                    .suffix(" columns"),
+            );
+            ui.add(
+                DragValue::new(&mut rows)
+                    .clamp_range(1..=Tilemap::MAX_SIZE)
+                    .suffix(" rows"),
+            );
+        });
+        if (columns, rows) != (self.columns(), self.rows()) {
+            self.resize(columns, rows);
+            changed = true;
+        }
+
+        if ui.button("Clear").clicked() {
+            changed |= self.set_tiles(vec![None; self.tiles().len()]);
+        }
+
+        changed
+    }
+}
+
+impl Reflect for Script {
+    const NAME: &'static str = "Script";
+
+    /// The parameter count goes first, then each parameter's name, type and value, and the path
+    /// last, as it's the only value which may contain spaces.
+    fn serialise(&self) -> String {
+        let mut value = self.parameters.len().to_string();
+        for (name, parameter) in &self.parameters {
+            value.push_str(&format!(
+                " {} {} {}",
+                name,
+                parameter.kind(),
+                parameter.value()
+            ));
+        }
+
+        format!("{} {}", value, self.filepath.display())
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        let mut rest = value;
+        let count: usize = next_word(&mut rest)?.parse().ok()?;
+
+        // The count comes from the file, so isn't trusted to size anything: a count larger than
+        // the parameters there are runs out of words instead.
+        let mut parameters = Vec::new();
+        for _ in 0..count {
+            let name = next_word(&mut rest)?;
+            let kind = next_word(&mut rest)?;
+            let words = if kind == "color" { 4 } else { 1 };
+            let value = (0..words)
+                .map(|_| next_word(&mut rest))
+                .collect::<Option<Vec<_>>>()?
+                .join(" ");
+            parameters.push((name.to_string(), ScriptParameter::parse(kind, &value)?));
+        }
+
+        Some(Self {
+            filepath: Handle::new(rest),
+            parameters,
+        })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Module");
+        ui.horizontal(|ui| {
+            ui.label(self.filepath.display().to_string());
+            if ui.button("📂").clicked() {
+                let file = pick_file("Script", &["wasm", "wat", "lua"]);
+                if let Some(path) = file {
+                    self.filepath = Handle::new(path);
+                    changed = true;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("📄 New Script").clicked() {
+                if let Some(path) = new_script() {
+                    self.filepath = Handle::new(path);
+                    changed = true;
+                }
+            }
+
+            let has_file = !self.filepath.as_os_str().is_empty();
+            let open = ui.add_enabled(has_file, egui::Button::new("✏ Open in Editor"));
+            if open.clicked() {
+                open_in_editor(ui, &self.filepath);
+            }
+        });
+
+        if !self.parameters.is_empty() {
+            ui.label("Parameters");
+            egui::Grid::new("Script Parameters")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (name, parameter) in &mut self.parameters {
+                        ui.label(name.as_str());
+                        changed |= match parameter {
+                            ScriptParameter::Float(value) => {
+                                ui.add(DragValue::new(value).speed(0.1)).changed()
+                            }
+                            ScriptParameter::Int(value) => ui.add(DragValue::new(value)).changed(),
+                            ScriptParameter::Bool(value) => ui.checkbox(value, "").changed(),
+                            ScriptParameter::Color(color) => color_edit(ui, color),
+                        };
+                        ui.end_row();
+                    }
+                });
+        }
+
+        changed
+    }
+}
+
+impl Reflect for PathFollower {
+    const NAME: &'static str = "PathFollower";
+
+    fn serialise(&self) -> String {
+        match self.target() {
+            Some(target) => format!("{} {} {}", self.speed, target.x, target.y),
+            None => self.speed.to_string(),
+        }
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        match floats(value)?.as_slice() {
+            [speed] => Some(Self::new(*speed)),
+            [speed, x, y] => {
+                let mut follower = Self::new(*speed);
+                follower.go_to(Vec2::new(*x, *y));
+                Some(follower)
+            }
+            _ => None,
+        }
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Speed");
+        changed |= ui
+            .add(
+                DragValue::new(&mut self.speed)
+                    .clamp_range(0.0..=f32::MAX)
+                    .suffix(" units/s"),
+            )
+            .changed();
+
+        let mut has_target = self.target().is_some();
+        if ui.checkbox(&mut has_target, "Target").changed() {
+            changed = true;
+            if has_target {
+                self.go_to(Vec2::ZERO);
+            } else {
+                self.stop();
+            }
+        }
+        if let Some(mut target) = self.target() {
+            if vec2_edit(ui, &mut target, ["X", "Y"], 1.0) {
+                self.go_to(target);
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+impl Reflect for SubScene {
+    const NAME: &'static str = "SubScene";
+
+    fn serialise(&self) -> String {
+        self.scene.display().to_string()
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        Some(Self {
+            scene: Handle::new(value),
+        })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Scene");
+        ui.horizontal(|ui| {
+            ui.label(self.scene.display().to_string());
+            if ui.button("📂").clicked() {
+                if let Some(path) = pick_file("Scene", &["alpha"]) {
+                    self.scene = Handle::new(path);
+                    changed = true;
+                }
+            }
+        });
+
+        changed
+    }
+}
+
+impl Reflect for AudioSource {
+    const NAME: &'static str = "AudioSource";
+
+    /// The volume, then `occlude` with the volume and cutoff while blocked if the source is
+    /// occluded, with the sound file's path last as it may contain spaces.
+    fn serialise(&self) -> String {
+        match self.occlusion {
+            Some(occlusion) => format!(
+                "{} occlude {} {} {}",
+                self.volume,
+                occlusion.volume,
+                occlusion.cutoff,
+                self.sound.display()
+            ),
+            None => format!("{} {}", self.volume, self.sound.display()),
+        }
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        let (volume, rest) = value.split_once(' ').unwrap_or((value, ""));
+        let (occlusion, sound) = match rest.strip_prefix("occlude ") {
+            Some(rest) => {
+                let mut words = rest.splitn(3, ' ');
+                let occlusion = Occlusion {
+                    volume: words.next()?.parse().ok()?,
+                    cutoff: words.next()?.parse().ok()?,
+                };
+                (Some(occlusion), words.next().unwrap_or(""))
+            }
+            None => (None, rest),
+        };
+
+        Some(Self {
+            sound: Handle::new(sound),
+            volume: volume.parse().ok()?,
+            occlusion,
+        })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Sound");
+        ui.horizontal(|ui| {
+            ui.label(self.sound.display().to_string());
+            if ui.button("📂").clicked() {
+                if let Some(path) = pick_file("Sound", &["ogg", "wav", "mp3", "flac"]) {
+                    self.sound = Handle::new(path);
+                    changed = true;
+                }
+            }
+        });
+
+        ui.label("Volume");
+        changed |= ui.add(Slider::new(&mut self.volume, 0.0..=1.0)).changed();
+
+        let mut occluded = self.occlusion.is_some();
+        if ui.checkbox(&mut occluded, "Occlusion").changed() {
+            self.occlusion = occluded.then(Occlusion::default);
+            changed = true;
+        }
+        if let Some(occlusion) = &mut self.occlusion {
+            ui.label("Occluded Volume");
+            changed |= ui
+                .add(Slider::new(&mut occlusion.volume, 0.0..=1.0))
+                .changed();
+
+            ui.label("Occluded Cutoff");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut occlusion.cutoff)
+                        .speed(10.0)
+                        .clamp_range(1..=20_000)
+                        .suffix(" Hz"),
+                )
+                .changed();
+        }
+
+        changed
+    }
+}
+
+#[cfg(feature = "network")]
+impl Reflect for Replicated {
+    const NAME: &'static str = "Replicated";
+
+    fn serialise(&self) -> String {
+        self.components.join(" ")
+    }
+
+    fn deserialise(value: &str) -> Option<Self> {
+        Some(Self {
+            components: value.split_whitespace().map(String::from).collect(),
+        })
+    }
+
+    fn inspect(&mut self, ui: &mut Ui) -> bool {
+        // Only the components which change as the game runs are worth sending.
+        const NAMES: [&str; 7] = [
+            Transform::NAME,
+            Shape::NAME,
+            Sprite::NAME,
+            Visible::NAME,
+            Velocity::NAME,
+            AnimatedSprite::NAME,
+            Tilemap::NAME,
+        ];
+
+        let mut changed = false;
+        ui.label("Components");
+        for name in NAMES {
+            let mut replicated = self.components.iter().any(|c| c == name);
+            if ui.checkbox(&mut replicated, name).changed() {
+                if replicated {
+                    self.components.push(name.to_string());
+                } else {
+                    self.components.retain(|c| c != name);
+                }
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+/// Sets the command the Script inspector opens scripts with, e.g. `code --wait`. Without one,
+/// scripts are opened with whatever the platform opens files of their type with.
+pub(crate) fn set_external_editor(ctx: &egui::Context, command: &str) {
+    ctx.data()
+        .insert_temp(external_editor_id(), command.to_string());
+}
+
+fn external_editor_id() -> egui::Id {
+    egui::Id::new("External Editor")
+}
+
+/// Asks for a file of one of the given types.
+#[cfg(not(target_arch = "wasm32"))]
+fn pick_file(name: &str, extensions: &[&str]) -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter(name, extensions)
+        .pick_file()
+}
+
+/// The browser has no file dialogs, or files to pick from.
+#[cfg(target_arch = "wasm32")]
+fn pick_file(_name: &str, _extensions: &[&str]) -> Option<PathBuf> {
+    None
+}
+
+/// Asks where to save a new script and writes the template for its language there.
+#[cfg(not(target_arch = "wasm32"))]
+fn new_script() -> Option<PathBuf> {
+    let dialog = rfd::FileDialog::new().add_filter("WebAssembly Text", &["wat"]);
+    #[cfg(feature = "lua")]
+    let dialog = dialog.add_filter("Lua", &["lua"]);
+
+    let mut path = dialog.set_file_name("script.wat").save_file()?;
+    if path.extension().is_none() {
+        path.set_extension("wat");
+    }
+
+    let template = match scripting::template(&path) {
+        Some(template) => template,
+        None => {
+            error!("no script template for {}", path.display());
+            return None;
+        }
+    };
+    match fs::write(&path, template) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            error!("unable to create script {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn new_script() -> Option<PathBuf> {
+    None
+}
+
+fn open_in_editor(ui: &Ui, path: &Path) {
+    let command = ui
+        .data()
+        .get_temp::<String>(external_editor_id())
+        .unwrap_or_default();
+
+    let mut words = command.split_whitespace();
+    let mut process = match words.next() {
+        Some(program) => {
+            let mut process = Command::new(program);
+            process.args(words);
+            process
+        }
+        None => default_opener(),
+    };
+    if let Err(e) = process.arg(path).spawn() {
+        error!("unable to open {}: {}", path.display(), e);
+    }
+}
+
+fn default_opener() -> Command {
+    if cfg!(target_os = "windows") {
+        let mut process = Command::new("cmd");
+        process.args(["/C", "start", ""]);
+        process
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    }
+}
+
+fn next_word<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let (word, tail) = rest.split_once(' ')?;
+    *rest = tail;
+    Some(word)
+}
+
+fn floats(value: &str) -> Option<Vec<f32>> {
+    value
+        .split_whitespace()
+        .map(|v| f32::from_str(v).ok())
+        .collect()
+}
+
+fn color(color: Vec4) -> String {
+    format!("{} {} {} {}", color.x, color.y, color.z, color.w)
+}
+
+fn color_edit(ui: &mut Ui, color: &mut Vec4) -> bool {
+    let mut rgba = color.to_array();
+    let changed = ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed();
+    if changed {
+        *color = Vec4::from_slice(&rgba);
+    }
+
+    changed
+}
+
+/// Edits both parts of a vector side by side, with a prefix for each, e.g. `x` and `y`.
+fn vec2_edit(ui: &mut Ui, value: &mut Vec2, prefixes: [&str; 2], speed: f32) -> bool {
+    ui.horizontal(|ui| {
+        let x = DragValue::new(&mut value.x)
+            .prefix(format!("{}: ", prefixes[0]))
+            .speed(speed);
+        let y = DragValue::new(&mut value.y)
+            .prefix(format!("{}: ", prefixes[1]))
+            .speed(speed);
+        ui.add(x).changed() | ui.add(y).changed()
+    })
+    .inner
+}
+
+/// A button which puts the value back to its default, only enabled while it's something else.
+fn reset_button<T: PartialEq>(ui: &mut Ui, value: &mut T, default: T) -> bool {
+    let reset = ui
+        .add_enabled(*value != default, egui::Button::new("⟲").small())
+        .on_hover_text("Reset");
+    if reset.clicked() {
+        *value = default;
+    }
+
+    reset.clicked()
+}
+
+fn linear_angular_edit(ui: &mut Ui, linear: &mut Vec2, angular: &mut f32) -> bool {
+    let mut changed = false;
+
+    ui.label("Linear");
+    let slider = Slider::new(&mut linear.x, -1000.0..=1000.0)
+        .text("x")
+        .clamp_to_range(false);
+    changed |= ui.add(slider).changed();
+    let slider = Slider::new(&mut linear.y, -1000.0..=1000.0)
+        .text("y")
+        .clamp_to_range(false);
+    changed |= ui.add(slider).changed();
+
+    ui.label("Angular");
+    let slider = Slider::new(angular, -360.0..=360.0).clamp_to_range(false);
+    changed |= ui.add(slider).changed();
+
+    changed
+}
+
+fn bitmask_edit(ui: &mut Ui, id: &str, bits: &mut u32) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new(id).spacing([2.0, 2.0]).show(ui, |ui| {
+        for bit in 0..32 {
+            let mut set = *bits & (1 << bit) != 0;
+            if ui.toggle_value(&mut set, bit.to_string()).changed() {
+                *bits ^= 1 << bit;
+                changed = true;
+            }
+
+            if bit % 8 == 7 {
+                ui.end_row();
+            }
+        }
+    });
+
+    changed
+}
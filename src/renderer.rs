@@ -1,11 +1,20 @@
+use crate::components::Sprite;
 use crate::error::Error;
+use crate::platform::ScaleFactor;
 use crate::renderer::camera::Camera;
 use crate::renderer::rect::{Rect, RectPipeline, Vertex, ViewProjectionUniform};
+use crate::renderer::sprite::{SpriteBatch, SpritePipeline, SpriteVertex};
+use crate::renderer::texture::Texture;
 use bytemuck::cast_slice;
 use egui::FullOutput;
 use egui_wgpu::renderer::ScreenDescriptor;
-use glam::{Mat4, Vec4, Vec4Swizzles};
-use log::info;
+use glam::{Mat4, Vec2, Vec4, Vec4Swizzles};
+use image::RgbaImage;
+use log::{error, info, warn};
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::{
@@ -16,18 +25,38 @@ use winit::window::Window;
 
 pub mod camera;
 pub mod rect;
+pub mod sprite;
+pub mod texture;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn init(window: &Window) -> Result<Renderer, Error> {
-    let renderer = pollster::block_on(Renderer::new(window));
+    pollster::block_on(init_async(window))
+}
+
+/// As [`init`], for the browser, where the adapter and device can only be waited for
+/// asynchronously.
+pub async fn init_async(window: &Window) -> Result<Renderer, Error> {
+    let renderer = Renderer::new(window).await?;
     info!("renderer initialised");
 
     Ok(renderer)
 }
 
+/// Creates a renderer which draws into a texture rather than a window.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_headless(width: u32, height: u32) -> Result<Renderer, Error> {
+    let renderer = pollster::block_on(Renderer::headless(width, height))?;
+    info!("headless renderer initialised");
+
+    Ok(renderer)
+}
+
 pub struct Renderer {
     _instance: Instance,
-    _adapter: Adapter,
-    pub surface: Arc<Surface>,
+    adapter: Adapter,
+    /// `None` for a headless renderer, which draws into `offscreen` instead.
+    pub surface: Option<Arc<Surface>>,
+    offscreen: Option<wgpu::Texture>,
     pub device: Arc<Device>,
     pub queue: Arc<Queue>,
     pub surface_config: SurfaceConfiguration,
@@ -35,21 +64,26 @@ pub struct Renderer {
     pub height: u32,
     pub scale_factor: f64,
     pub output_texture: Option<TextureView>,
+    pub stats: RenderStats,
+    /// Set by the device's error handler when the GPU has gone away, e.g. after a driver reset.
+    device_lost: Arc<AtomicBool>,
+    /// Set when the device has been replaced, after which anything created with the old one, e.g.
+    /// textures rendered into or registered with egui, has to be created again before it's used.
+    /// Whoever does so clears it.
+    pub recovered: bool,
 
     rect_pipeline: RectPipeline,
+    sprite_pipeline: SpritePipeline,
+    textures: HashMap<PathBuf, Option<Texture>>,
     egui_render_pass: egui_wgpu::renderer::RenderPass,
 }
 
 impl Renderer {
-    async fn new(window: &Window) -> Renderer {
+    async fn new(window: &Window) -> Result<Renderer, Error> {
         let size = window.inner_size();
-        let width = size.width;
-        let height = size.height;
-        let scale_factor = window.scale_factor();
 
         // The instance is a handle to our GPU
-        // Backends::all => Vulkan + Metal + DX12 + Browser WebGPU
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let instance = wgpu::Instance::new(backends());
         let surface = unsafe { instance.create_surface(window) };
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -58,39 +92,81 @@ impl Renderer {
                 force_fallback_adapter: false,
             })
             .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
-                    label: None,
-                },
-                None, // Trace path
-            )
+            .ok_or(Error::GraphicsAdapter)?;
+
+        let surface_format = surface.get_preferred_format(&adapter).ok_or_else(|| {
+            Error::Renderer("the window isn't supported by the graphics adapter".to_string())
+        })?;
+        Self::with_adapter(
+            instance,
+            adapter,
+            Some(surface),
+            surface_format,
+            (size.width, size.height),
+            window.scale_factor(),
+        )
+        .await
+    }
+
+    /// Creates a renderer without a window, which draws into a texture of its own instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn headless(width: u32, height: u32) -> Result<Renderer, Error> {
+        let instance = wgpu::Instance::new(backends());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
             .await
-            .unwrap();
+            .ok_or(Error::GraphicsAdapter)?;
+
+        // BGRA, like most surfaces, so frames can be read back like any other render target.
+        let format = wgpu::TextureFormat::Bgra8UnormSrgb;
+        Self::with_adapter(instance, adapter, None, format, (width, height), 1.0).await
+    }
+
+    async fn with_adapter(
+        instance: Instance,
+        adapter: Adapter,
+        surface: Option<Surface>,
+        format: wgpu::TextureFormat,
+        (width, height): (u32, u32),
+        scale_factor: f64,
+    ) -> Result<Renderer, Error> {
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let (device, queue) = request_device(&adapter, &device_lost).await?;
 
-        let surface_format = surface.get_preferred_format(&adapter).unwrap();
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
+            format,
+            width,
+            height,
             present_mode: wgpu::PresentMode::Fifo,
         };
-        surface.configure(&device, &surface_config);
+        let offscreen = match &surface {
+            Some(surface) => {
+                surface.configure(&device, &surface_config);
+                None
+            }
+            None => Some(offscreen_texture(&device, &surface_config)),
+        };
 
         let rect_pipeline = RectPipeline::init(&device, &surface_config);
+        let sprite_pipeline = SpritePipeline::init(
+            &device,
+            &surface_config,
+            &rect_pipeline.view_projection_uniform_buffer,
+        );
 
         let egui_render_pass =
             egui_wgpu::renderer::RenderPass::new(&device, surface_config.format, 1);
 
-        Self {
+        Ok(Self {
             _instance: instance,
-            _adapter: adapter,
-            surface: Arc::new(surface),
+            adapter,
+            surface: surface.map(Arc::new),
+            offscreen,
             device: Arc::new(device),
             queue: Arc::new(queue),
             surface_config,
@@ -98,30 +174,112 @@ impl Renderer {
             height,
             scale_factor,
             output_texture: None,
+            stats: RenderStats::default(),
+            device_lost,
+            recovered: false,
 
             rect_pipeline,
+            sprite_pipeline,
+            textures: HashMap::new(),
             egui_render_pass,
-        }
+        })
     }
 
-    pub fn prepare(&mut self) -> RenderContext {
+    /// Gets the frame to draw into, or `None` if this frame has to be skipped, e.g. because the
+    /// window changed size while the last one was being drawn.
+    pub fn prepare(&mut self) -> Result<Option<RenderContext>, Error> {
+        if self.device_lost.load(Ordering::Relaxed) {
+            self.recover()?;
+            return Ok(None);
+        }
+
         let (output, view) = if let Some(view) = self.output_texture.take() {
             (None, view)
-        } else {
-            let output = self
-                .surface
-                .get_current_texture()
-                .expect("should have a surface");
+        } else if let Some(surface) = &self.surface {
+            let output = match surface.get_current_texture() {
+                Ok(output) => output,
+                // The surface can go out of date or be lost when the window changes, e.g. when
+                // it's restored after being minimised, resized while a frame was being drawn or
+                // moved to another monitor, so set it up again and draw the next frame instead.
+                Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                    surface.configure(&self.device, &self.surface_config);
+                    return Ok(None);
+                }
+                Err(wgpu::SurfaceError::Timeout) => {
+                    warn!("timed out waiting for the next frame");
+                    return Ok(None);
+                }
+                Err(e) => {
+                    return Err(Error::Renderer(format!(
+                        "unable to get the next frame: {}",
+                        e
+                    )))
+                }
+            };
             let view = output
                 .texture
                 .create_view(&wgpu::TextureViewDescriptor::default());
             (Some(output), view)
+        } else {
+            let offscreen = self
+                .offscreen
+                .as_ref()
+                .ok_or_else(|| Error::Renderer("nothing to draw into".to_string()))?;
+            let view = offscreen.create_view(&wgpu::TextureViewDescriptor::default());
+            (None, view)
         };
 
-        RenderContext { output, view }
+        Ok(Some(RenderContext { output, view }))
+    }
+
+    /// Replaces a lost device with a new one, along with everything which was created with it.
+    /// Textures are loaded again from their files as they're next drawn.
+    fn recover(&mut self) -> Result<(), Error> {
+        // A device can't be waited for in the browser, where reloading the page starts again.
+        if cfg!(target_arch = "wasm32") {
+            return Err(Error::Renderer("graphics device lost".to_string()));
+        }
+        warn!("graphics device lost, creating it again");
+
+        self.device_lost.store(false, Ordering::Relaxed);
+        let (device, queue) = pollster::block_on(request_device(&self.adapter, &self.device_lost))?;
+        match &self.surface {
+            Some(surface) => surface.configure(&device, &self.surface_config),
+            None => self.offscreen = Some(offscreen_texture(&device, &self.surface_config)),
+        }
+
+        self.rect_pipeline = RectPipeline::init(&device, &self.surface_config);
+        self.sprite_pipeline = SpritePipeline::init(
+            &device,
+            &self.surface_config,
+            &self.rect_pipeline.view_projection_uniform_buffer,
+        );
+        self.egui_render_pass =
+            egui_wgpu::renderer::RenderPass::new(&device, self.surface_config.format, 1);
+        self.textures.clear();
+        self.output_texture = None;
+        self.device = Arc::new(device);
+        self.queue = Arc::new(queue);
+        self.recovered = true;
+
+        info!("graphics device recreated");
+
+        Ok(())
     }
 
     pub fn begin_scene(&mut self, camera: &Camera) -> Scene {
+        self.begin(camera, true)
+    }
+
+    /// Begins a scene drawn over the one before it, in screen space: one unit is a pixel, from the
+    /// bottom left of the frame.
+    pub fn begin_overlay(&mut self) -> Scene {
+        let mut camera = Camera::new(self.width, self.height);
+        camera.position = Vec2::ZERO;
+        self.begin(&camera, false)
+    }
+
+    fn begin(&mut self, camera: &Camera, clear: bool) -> Scene {
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -156,12 +314,22 @@ impl Renderer {
 
         let index_offset = 0;
 
+        let sprites = Vec::new();
+
+        let stats = RenderStats::default();
+
         Scene {
             encoder,
             vertices,
             indices,
             transform,
             index_offset,
+            sprites,
+            stats,
+            clear,
+            // The UI is drawn in order, one widget over another, so overlays aren't reordered.
+            batch_by_texture: clear,
+            layer_start: 0,
         }
     }
 
@@ -204,6 +372,33 @@ impl Renderer {
             (std::mem::size_of::<u16>() * scene.indices.len()) as BufferAddress,
         );
 
+        let sprite_buffers: Vec<_> = scene
+            .sprites
+            .iter()
+            .filter_map(|batch| {
+                let texture = self.textures.get(&batch.texture)?.as_ref()?;
+
+                let vertex_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Sprite Vertex Buffer"),
+                    contents: cast_slice(&batch.vertices),
+                    usage: BufferUsages::VERTEX,
+                });
+
+                let index_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some("Sprite Index Buffer"),
+                    contents: cast_slice(&batch.indices),
+                    usage: BufferUsages::INDEX,
+                });
+
+                Some((
+                    texture,
+                    vertex_buffer,
+                    index_buffer,
+                    batch.indices.len() as u32,
+                ))
+            })
+            .collect();
+
         {
             let mut render_pass = scene
                 .encoder
@@ -215,12 +410,16 @@ impl Renderer {
                             view: &ctx.view,
                             resolve_target: None,
                             ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color {
-                                    r: 0.1,
-                                    g: 0.2,
-                                    b: 0.3,
-                                    a: 1.0,
-                                }),
+                                load: if scene.clear {
+                                    wgpu::LoadOp::Clear(wgpu::Color {
+                                        r: 0.1,
+                                        g: 0.2,
+                                        b: 0.3,
+                                        a: 1.0,
+                                    })
+                                } else {
+                                    wgpu::LoadOp::Load
+                                },
                                 store: true,
                             },
                         },
@@ -236,7 +435,35 @@ impl Renderer {
                 self.rect_pipeline.index_buffer_format,
             );
             render_pass.draw_indexed(0..scene.indices.len() as u32, 0, 0..1);
+
+            render_pass.set_pipeline(&self.sprite_pipeline.render_pipeline);
+            render_pass.set_bind_group(0, &self.sprite_pipeline.uniforms_bind_group, &[]);
+            for (texture, vertex_buffer, index_buffer, index_count) in sprite_buffers.iter() {
+                render_pass.set_bind_group(1, &texture.bind_group, &[]);
+                render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.draw_indexed(0..*index_count, 0, 0..1);
+            }
         }
+
+        scene.stats.draw_calls = 1 + sprite_buffers.len();
+        scene.stats.sprite_batches = sprite_buffers.len();
+        scene.stats.vertices = scene.vertices.len()
+            + scene
+                .sprites
+                .iter()
+                .map(|b| b.vertices.len())
+                .sum::<usize>();
+        // Overlays add to the stats of the scene they're drawn over.
+        if !scene.clear {
+            scene.stats.draw_calls += self.stats.draw_calls;
+            scene.stats.vertices += self.stats.vertices;
+            scene.stats.sprite_batches += self.stats.sprite_batches;
+            scene.stats.texture_switch_breaks += self.stats.texture_switch_breaks;
+            scene.stats.batch_full_breaks += self.stats.batch_full_breaks;
+        }
+        self.stats = scene.stats;
+
         let command_buffers = vec![scene.encoder.finish()];
         let command_buffers = command_buffers;
 
@@ -251,6 +478,10 @@ impl Renderer {
         }
     }
 
+    pub fn scale_factor(&self) -> ScaleFactor {
+        ScaleFactor(self.scale_factor)
+    }
+
     pub fn resize(&mut self, width: u32, height: u32, scale_factor: f64) {
         if width > 0 && height > 0 {
             self.width = width;
@@ -258,12 +489,19 @@ impl Renderer {
             self.scale_factor = scale_factor;
             self.surface_config.width = width;
             self.surface_config.height = height;
-            self.surface.configure(&self.device, &self.surface_config);
+            match &self.surface {
+                Some(surface) => surface.configure(&self.device, &self.surface_config),
+                None => {
+                    // Drop the view of the old texture, so the next frame draws into the new one.
+                    self.output_texture = None;
+                    self.offscreen = Some(offscreen_texture(&self.device, &self.surface_config));
+                }
+            }
         }
     }
 
     pub fn draw_rect(&mut self, scene: &mut Scene, rect: &Rect) {
-        let transform = rect.scale_rotation_translation();
+        let transform = rect.transform;
         let vertices: Vec<Vertex> = Rect::VERTEX_COORDS
             .iter()
             .map(|vc| {
@@ -284,6 +522,245 @@ impl Renderer {
         scene.index_offset += 4;
     }
 
+    /// Adds `sprite` to the scene, if its texture has been given to the renderer. Callers skip
+    /// sprites whose textures haven't finished loading.
+    pub fn draw_sprite(&mut self, scene: &mut Scene, transform: &Mat4, sprite: &Sprite) {
+        if self.texture(sprite.texture.path()).is_none() {
+            return;
+        }
+
+        // Sprites in the same layer can be drawn in any order, so they're added to whichever of
+        // the layer's batches already uses their texture, rather than only the last one.
+        let candidates = if scene.batch_by_texture {
+            scene.layer_start.min(scene.sprites.len())
+        } else {
+            scene.sprites.len().saturating_sub(1)
+        };
+        let same_texture = scene.sprites[candidates..]
+            .iter()
+            .rposition(|batch| batch.texture.as_path() == sprite.texture.path())
+            .map(|i| candidates + i);
+        let index = match same_texture {
+            Some(i) if scene.sprites[i].vertices.len() <= u16::MAX as usize - 4 => Some(i),
+            Some(_) => {
+                scene.stats.batch_full_breaks += 1;
+                None
+            }
+            None if scene.sprites.len() > candidates => {
+                scene.stats.texture_switch_breaks += 1;
+                None
+            }
+            None => None,
+        };
+        let index = index.unwrap_or_else(|| {
+            scene.sprites.push(SpriteBatch {
+                texture: sprite.texture.to_path_buf(),
+                vertices: Vec::new(),
+                indices: Vec::new(),
+            });
+            scene.sprites.len() - 1
+        });
+        let batch = &mut scene.sprites[index];
+
+        let index_offset = batch.vertices.len() as u16;
+        for vc in Rect::VERTEX_COORDS.iter() {
+            let position = transform.mul_vec4(Vec4::from((vc[0], vc[1], 0.0, 1.0)));
+
+            // Texture coordinates run top to bottom, world coordinates bottom to top.
+            let mut u = vc[0];
+            let mut v = 1.0 - vc[1];
+            if sprite.flip_x {
+                u = 1.0 - u;
+            }
+            if sprite.flip_y {
+                v = 1.0 - v;
+            }
+            let uv = sprite.uv_min + (sprite.uv_max - sprite.uv_min) * Vec2::new(u, v);
+
+            batch.vertices.push(SpriteVertex::new(
+                position.xy().to_array(),
+                uv.to_array(),
+                sprite.tint.to_array(),
+            ));
+        }
+
+        batch
+            .indices
+            .extend(Rect::INDICES.iter().map(|i| i + index_offset));
+    }
+
+    /// Returns the texture given to the renderer for `path`, by [`Renderer::insert_texture`] once
+    /// it's been loaded as an asset, or one of the others which add them. Nothing is read from
+    /// files here, so drawing never waits on the disk.
+    pub fn texture(&self, path: &Path) -> Option<&Texture> {
+        self.textures.get(path)?.as_ref()
+    }
+
+    /// Reads the texture at `path` from its file, if it hasn't been already, for the editor's
+    /// previews of files which aren't game assets, e.g. scene thumbnails. Failures are logged once
+    /// and then remembered.
+    pub fn load_texture(&mut self, path: &Path) {
+        if !self.textures.contains_key(path) {
+            let texture = Texture::from_file(
+                &self.device,
+                &self.queue,
+                &self.sprite_pipeline.texture_bind_group_layout,
+                &self.sprite_pipeline.sampler,
+                path,
+            );
+            let texture = match texture {
+                Ok(texture) => Some(texture),
+                Err(e) => {
+                    warn!("{}", e);
+                    None
+                }
+            };
+            self.textures.insert(path.to_path_buf(), texture);
+        }
+    }
+
+    /// Replaces the texture cached for `path` with `image`, e.g. once it's been loaded in the
+    /// background or its file has changed.
+    pub fn insert_texture(&mut self, path: &Path, image: &RgbaImage) {
+        let texture = Texture::from_image(
+            &self.device,
+            &self.queue,
+            &self.sprite_pipeline.texture_bind_group_layout,
+            &self.sprite_pipeline.sampler,
+            image,
+        );
+        self.textures.insert(path.to_path_buf(), Some(texture));
+    }
+
+    /// Replaces the texture cached for `path` with an empty one the size of the frame, returning
+    /// a view of it to draw into with [`Renderer::render_to_texture`]. Sprites with the same path
+    /// then draw whatever was drawn into it, e.g. a snapshot of the last frame of a scene.
+    pub fn insert_render_target(&mut self, path: &Path) -> TextureView {
+        let texture = Texture::render_target(
+            &self.device,
+            &self.sprite_pipeline.texture_bind_group_layout,
+            &self.sprite_pipeline.sampler,
+            self.surface_config.format,
+            self.width,
+            self.height,
+        );
+        let view = texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.textures.insert(path.to_path_buf(), Some(texture));
+        view
+    }
+
+    /// Registers the texture given to the renderer for `path` with egui, so it can be previewed in
+    /// the editor.
+    pub fn egui_texture(&mut self, path: &Path) -> Option<(egui::TextureId, u32, u32)> {
+        let texture = self.textures.get_mut(path)?.as_mut()?;
+        let texture_id = match texture.egui_texture_id {
+            Some(texture_id) => texture_id,
+            None => {
+                let texture_id = self.egui_render_pass.register_native_texture(
+                    &self.device,
+                    &texture.view,
+                    FilterMode::Linear,
+                );
+                texture.egui_texture_id = Some(texture_id);
+                texture_id
+            }
+        };
+
+        Some((texture_id, texture.width, texture.height))
+    }
+
+    /// Drops the cached copy of the texture loaded from `path`, so it's read again next time it's used.
+    pub fn forget_texture(&mut self, path: &Path) {
+        self.textures.remove(path);
+    }
+
+    /// Blocks until the GPU has finished all the work it has been given, e.g. before shutting down.
+    pub fn wait_idle(&self) {
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Copies the last frame a headless renderer drew back from the GPU as RGBA pixels.
+    pub fn read_frame_rgba(&self) -> Option<Vec<u8>> {
+        let offscreen = self.offscreen.as_ref()?;
+        self.read_texture_rgba(offscreen, self.width, self.height)
+    }
+
+    /// Copies a BGRA texture back from the GPU as tightly packed RGBA pixels.
+    pub fn read_texture_rgba(
+        &self,
+        texture: &wgpu::Texture,
+        width: u32,
+        height: u32,
+    ) -> Option<Vec<u8>> {
+        // Nor can the copy be waited for in the browser.
+        if cfg!(target_arch = "wasm32") {
+            return None;
+        }
+
+        // Each row of the copy has to start on an aligned offset, so there may be padding to strip.
+        let unpadded_bytes_per_row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (unpadded_bytes_per_row + align - 1) / align * align;
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture Readback Buffer"),
+            size: (padded_bytes_per_row * height) as BufferAddress,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Texture Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: NonZeroU32::new(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        if let Err(e) = pollster::block_on(mapping) {
+            warn!("unable to read texture back from the GPU: {}", e);
+            return None;
+        }
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in slice
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+        {
+            for bgra in row[..unpadded_bytes_per_row as usize].chunks(4) {
+                pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+            }
+        }
+        buffer.unmap();
+
+        Some(pixels)
+    }
+
     pub fn render_to_texture(&mut self, texture: Option<TextureView>) {
         self.output_texture = texture;
     }
@@ -297,11 +774,14 @@ impl Renderer {
         )
     }
 
+    /// Draws egui's output over whatever has already been drawn this frame, e.g. a game's scene,
+    /// or clears the frame to `clear` first if there's nothing under the UI.
     pub fn begin_egui(
         &mut self,
         ctx: &RenderContext,
         egui_ctx: &egui::Context,
         egui_output: &FullOutput,
+        clear: Option<wgpu::Color>,
     ) {
         let paint_jobs = egui_ctx.tessellate(egui_output.shapes.clone());
 
@@ -338,7 +818,7 @@ impl Renderer {
                 &ctx.view,
                 &paint_jobs,
                 &screen_descriptor,
-                Some(wgpu::Color::BLACK),
+                clear,
             );
         }
 
@@ -357,4 +837,109 @@ pub struct Scene {
     pub indices: Vec<u16>,
     pub transform: Mat4,
     pub index_offset: u16,
+    pub sprites: Vec<SpriteBatch>,
+    pub stats: RenderStats,
+    /// Whether the frame is cleared before the scene is drawn, rather than drawn over.
+    pub clear: bool,
+    /// Whether sprites which share a texture are drawn together, whatever order they were added
+    /// in, so that a scene using a few atlases only takes a few draw calls. Only sprites in the
+    /// same layer are reordered.
+    pub batch_by_texture: bool,
+    /// The first batch of the current layer.
+    layer_start: usize,
+}
+
+impl Scene {
+    /// Starts a new layer, so that the sprites drawn from now on are drawn over the ones before,
+    /// even when they share a texture.
+    pub fn next_layer(&mut self) {
+        self.layer_start = self.sprites.len();
+    }
+}
+
+/// Counters describing the work done to render the most recent scene.
+///
+/// Batches are only broken by textures and the index limit: every sprite and shape is alpha
+/// blended and drawn without a scissor rect, as both are fixed for each pass, so neither can break
+/// a batch.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    pub vertices: usize,
+    pub sprite_batches: usize,
+    /// Sprite batches that had to be started because a sprite used a texture no other batch in
+    /// its layer did, or, in overlays, a different texture to the sprite before it.
+    pub texture_switch_breaks: usize,
+    /// Sprite batches that had to be split because they reached the 16-bit index limit.
+    pub batch_full_breaks: usize,
+}
+
+/// Vulkan, Metal and DX12 natively, and WebGPU in the browser, falling back to WebGL where it
+/// isn't available yet.
+fn backends() -> wgpu::Backends {
+    if cfg!(target_arch = "wasm32") {
+        wgpu::Backends::BROWSER_WEBGPU | wgpu::Backends::GL
+    } else {
+        wgpu::Backends::all()
+    }
+}
+
+fn limits() -> wgpu::Limits {
+    // WebGL can't do everything a native GPU can, so the browser asks for less.
+    if cfg!(target_arch = "wasm32") {
+        wgpu::Limits::downlevel_webgl2_defaults()
+    } else {
+        wgpu::Limits::default()
+    }
+}
+
+async fn request_device(
+    adapter: &Adapter,
+    device_lost: &Arc<AtomicBool>,
+) -> Result<(Device, Queue), Error> {
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                features: wgpu::Features::empty(),
+                limits: limits(),
+                label: None,
+            },
+            None, // Trace path
+        )
+        .await
+        .map_err(|e| Error::Renderer(e.to_string()))?;
+
+    // wgpu panics on errors nothing else catches unless it's given a handler. A device which has
+    // run out of memory is gone for good, and is only reported this way, so it's flagged to be
+    // recovered from before the next frame. Validation errors are bugs, so are left to panic while
+    // developing, but released games carry on.
+    let device_lost = device_lost.clone();
+    device.on_uncaptured_error(move |e| {
+        match e {
+            wgpu::Error::OutOfMemory { .. } => device_lost.store(true, Ordering::Relaxed),
+            wgpu::Error::Validation { .. } if cfg!(debug_assertions) => {
+                panic!("graphics device error: {}", e)
+            }
+            wgpu::Error::Validation { .. } => {}
+        }
+        error!("graphics device error: {}", e);
+    });
+
+    Ok((device, queue))
+}
+
+fn offscreen_texture(device: &Device, config: &SurfaceConfiguration) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        label: Some("Offscreen Texture"),
+    })
 }
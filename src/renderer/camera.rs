@@ -1,55 +1,76 @@
-use glam::Mat4;
+use glam::{Mat4, Vec2, Vec4, Vec4Swizzles};
 
 // TODO: Set where the world origin is - might want center of screen, not bottom left.
 // TODO: Set Pixels-Per-Unit and scale things accordingly.
-#[allow(dead_code)]
 pub struct Camera {
     width: u32,
     height: u32,
-    view: Mat4,
-    projection: Mat4,
+    /// The point in the world at the bottom left of the view.
+    pub position: Vec2,
+    /// How many pixels each unit of the world covers.
+    pub zoom: f32,
+    /// Bitmask of the [`CollisionLayers`](crate::CollisionLayers) this camera draws.
+    pub layers: u32,
 }
 
 impl Camera {
     pub fn new(width: u32, height: u32) -> Self {
-        let projection =
-            glam::Mat4::orthographic_lh(0.0, width as f32, 0.0, height as f32, -1.0, 1.0);
-
         Self {
             width,
             height,
-            view: Mat4::IDENTITY,
-            projection,
+            // Just use some jankey values for now.
+            position: Vec2::new(-200.0, -200.0),
+            zoom: 1.0,
+            layers: u32::MAX,
         }
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
-        let projection =
-            glam::Mat4::orthographic_lh(0.0, width as f32, 0.0, height as f32, -1.0, 1.0);
-
         self.width = width;
         self.height = height;
-        self.projection = projection;
     }
 
-    pub fn get_view(&self) -> Mat4 {
-        // Just use some jankey values for look at for now.
-        let view = glam::Mat4::look_at_lh(
-            glam::Vec3::new(-200.0, -200.0, -1.0),
-            glam::Vec3::new(-200.0, -200.0, 0.0),
-            glam::Vec3::Y,
-        );
+    /// The size of the view, in pixels.
+    pub fn screen_size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32)
+    }
+
+    /// The size of the part of the world in view.
+    pub fn view_size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) / self.zoom
+    }
 
-        // let view = glam::Mat4::look_at_lh(
-        //     glam::Vec3::new(0.0, 0.0, -1.0),
-        //     glam::Vec3::new(0.0, 0.0, 0.0),
-        //     glam::Vec3::Y,
-        // );
+    /// The point in the world in the middle of the view.
+    pub fn centre(&self) -> Vec2 {
+        self.position + self.view_size() / 2.0
+    }
 
-        view
+    /// Moves the camera so that `centre` is in the middle of the view.
+    pub fn centre_on(&mut self, centre: Vec2) {
+        self.position = centre - self.view_size() / 2.0;
+    }
+
+    pub fn get_view(&self) -> Mat4 {
+        glam::Mat4::look_at_lh(
+            glam::Vec3::new(self.position.x, self.position.y, -1.0),
+            glam::Vec3::new(self.position.x, self.position.y, 0.0),
+            glam::Vec3::Y,
+        )
     }
 
     pub fn get_projection(&self) -> Mat4 {
-        self.projection
+        let size = self.view_size();
+        glam::Mat4::orthographic_lh(0.0, size.x, 0.0, size.y, -1.0, 1.0)
+    }
+
+    /// Converts a position in physical pixels from the top left of the window into world space.
+    pub fn screen_to_world(&self, position: Vec2) -> Vec2 {
+        let size = Vec2::new(self.width as f32, self.height as f32);
+        let mut ndc = (position / size) * 2.0 - 1.0;
+        ndc.y *= -1.0;
+
+        let world =
+            (self.get_projection() * self.get_view()).inverse() * Vec4::from((ndc, 1.0, 1.0));
+        world.xy()
     }
 }
@@ -1,6 +1,5 @@
-use crate::components::{compute_transformation_matrix, Transform};
 use bytemuck::{Pod, Zeroable};
-use glam::{Mat4, Vec2, Vec4};
+use glam::{Mat4, Vec4};
 use wgpu::{
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
     BindGroupLayoutEntry, BindingType, BlendState, Buffer, BufferAddress, BufferBindingType,
@@ -12,13 +11,9 @@ use wgpu::{
 };
 
 // TODO: This needs to have coords and size specified in pixels/world coords.
-// TODO: Split into relevant components.
-// TODO: Set origin and ensure TRS happens in relation to it.
 pub struct Rect {
     pub color: Vec4,
-    pub position: Vec2,
-    pub rotation_degrees: f32,
-    pub size: Vec2,
+    pub transform: Mat4,
 }
 
 impl Rect {
@@ -30,23 +25,10 @@ impl Rect {
         0, 2, 3
     ];
 
-    pub fn new(position: Vec2, rotation_degrees: f32, size: Vec2, color: Vec4) -> Self {
-        Self {
-            color,
-            position,
-            rotation_degrees,
-            size,
-        }
-    }
-
-    pub fn scale_rotation_translation(&self) -> Mat4 {
-        // TODO: All transformations in relation to origin.
-        let t = Transform {
-            position: self.position,
-            size: self.size,
-            rotation: self.rotation_degrees,
-        };
-        compute_transformation_matrix(&t)
+    /// Creates a unit rect transformed into world space by `transform`, which is typically an
+    /// entity's [`GlobalTransform`](crate::components::GlobalTransform).
+    pub fn new(transform: Mat4, color: Vec4) -> Self {
+        Self { color, transform }
     }
 }
 
@@ -160,7 +142,7 @@ impl RectPipeline {
                 entry_point: "fs_main",
                 targets: &[ColorTargetState {
                     format: surface_config.format,
-                    blend: Some(BlendState::REPLACE),
+                    blend: Some(BlendState::ALPHA_BLENDING),
                     write_mask: ColorWrites::ALL,
                 }],
             }),
@@ -0,0 +1,188 @@
+use bytemuck::{Pod, Zeroable};
+use std::path::PathBuf;
+use wgpu::{
+    AddressMode, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendState, Buffer,
+    BufferAddress, BufferBindingType, ColorTargetState, ColorWrites, Device, Face, FilterMode,
+    FragmentState, FrontFace, MultisampleState, PipelineLayoutDescriptor, PolygonMode,
+    PrimitiveState, PrimitiveTopology, RenderPipeline, RenderPipelineDescriptor, Sampler,
+    SamplerBindingType, SamplerDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+    SurfaceConfiguration, TextureSampleType, TextureViewDimension, VertexAttribute,
+    VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+};
+
+/// Consecutive sprites which share a texture, drawn with a single draw call.
+pub struct SpriteBatch {
+    pub texture: PathBuf,
+    pub vertices: Vec<SpriteVertex>,
+    pub indices: Vec<u16>,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SpriteVertex {
+    pub position: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl SpriteVertex {
+    pub fn new(position: [f32; 2], uv: [f32; 2], color: [f32; 4]) -> Self {
+        Self {
+            position,
+            uv,
+            color,
+        }
+    }
+
+    pub fn desc<'a>() -> VertexBufferLayout<'a> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+pub struct SpritePipeline {
+    pub uniforms_bind_group: BindGroup,
+    pub texture_bind_group_layout: BindGroupLayout,
+    pub sampler: Sampler,
+    pub render_pipeline: RenderPipeline,
+}
+
+impl SpritePipeline {
+    pub fn init(
+        device: &Device,
+        surface_config: &SurfaceConfiguration,
+        view_projection_uniform_buffer: &Buffer,
+    ) -> Self {
+        let shader = device.create_shader_module(&ShaderModuleDescriptor {
+            label: Some("Sprite Shader"),
+            source: ShaderSource::Wgsl(include_str!("../../resources/shaders/sprite.wgsl").into()),
+        });
+
+        let uniforms_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Sprite Uniforms Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        // The view projection is shared with the rect pipeline, so it's only uploaded once per scene.
+        let uniforms_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Sprite Uniforms Bind Group"),
+            layout: &uniforms_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: view_projection_uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Sprite Texture Bind Group Layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: TextureViewDimension::D2,
+                            sample_type: TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Sprite Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Sprite Render Pipeline Layout"),
+            bind_group_layouts: &[&uniforms_bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Sprite Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[SpriteVertex::desc()],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[ColorTargetState {
+                    format: surface_config.format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                }],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        SpritePipeline {
+            uniforms_bind_group,
+            texture_bind_group_layout,
+            sampler,
+            render_pipeline,
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::error::Error;
+use image::RgbaImage;
+use std::num::NonZeroU32;
+use std::path::Path;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindingResource, Device,
+    Extent3d, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, Sampler, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+};
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: TextureView,
+    pub bind_group: BindGroup,
+    pub width: u32,
+    pub height: u32,
+    pub egui_texture_id: Option<egui::TextureId>,
+}
+
+impl Texture {
+    pub fn from_file(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        path: &Path,
+    ) -> Result<Self, Error> {
+        let image = image::open(path)
+            .map_err(|e| Error::Texture(path.to_path_buf(), e))?
+            .to_rgba8();
+        Ok(Self::from_image(device, queue, layout, sampler, &image))
+    }
+
+    /// Creates an empty texture which can be drawn into, like a frame, as well as drawn with.
+    pub fn render_target(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Render Target Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+        });
+        Self::with_texture(device, layout, sampler, texture, width, height)
+    }
+
+    pub fn from_image(
+        device: &Device,
+        queue: &Queue,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        image: &RgbaImage,
+    ) -> Self {
+        let (width, height) = image.dimensions();
+
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Sprite Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        });
+
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            image,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(4 * width),
+                rows_per_image: NonZeroU32::new(height),
+            },
+            size,
+        );
+
+        Self::with_texture(device, layout, sampler, texture, width, height)
+    }
+
+    fn with_texture(
+        device: &Device,
+        layout: &BindGroupLayout,
+        sampler: &Sampler,
+        texture: wgpu::Texture,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Sprite Texture Bind Group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        Self {
+            texture,
+            view,
+            bind_group,
+            width,
+            height,
+            egui_texture_id: None,
+        }
+    }
+}
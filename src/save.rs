@@ -0,0 +1,207 @@
+use crate::error::Error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Stores a game's progress in named slots, each a JSON file in the platform's data directory,
+/// e.g. `~/.local/share/<game>/saves/<slot>.json` on Linux. In the browser, which has no files,
+/// each slot is kept in the page's local storage under the same path instead.
+///
+/// Anything which implements serde's `Serialize` and `Deserialize` can be saved, so games don't
+/// need to read and write the files themselves:
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Progress {
+///     level: u32,
+///     coins: u32,
+/// }
+///
+/// game.saves().write("slot1", &Progress { level: 3, coins: 120 })?;
+/// let progress: Option<Progress> = game.saves().read("slot1")?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaveData {
+    /// Where the slots are kept, or `None` if the platform has nowhere to keep them.
+    directory: Option<PathBuf>,
+}
+
+impl SaveData {
+    /// Keeps the slots in the platform's data directory, under the game's name.
+    pub fn new(game: &str) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let directory = dirs::data_dir().map(|dir| dir.join(game).join("saves"));
+        #[cfg(target_arch = "wasm32")]
+        let directory = Some(Path::new(game).join("saves"));
+
+        Self { directory }
+    }
+
+    /// Keeps the slots in the given directory instead, e.g. next to a portable build.
+    pub fn in_directory<P: Into<PathBuf>>(directory: P) -> Self {
+        Self {
+            directory: Some(directory.into()),
+        }
+    }
+
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// Saves `data` in the slot, replacing anything already in it.
+    ///
+    /// The file is written alongside the old one and then moved over it, so a game which is closed
+    /// part way through saving keeps its previous save.
+    pub fn write<T: Serialize>(&self, slot: &str, data: &T) -> Result<(), Error> {
+        let path = self.path(slot)?;
+        let json =
+            serde_json::to_string_pretty(data).map_err(|e| Error::SaveFormat(path.clone(), e))?;
+
+        storage::write(&path, &json).map_err(|e| Error::SaveWrite(path, e))
+    }
+
+    /// Reads what was saved in the slot, or `None` if nothing has been.
+    pub fn read<T: DeserializeOwned>(&self, slot: &str) -> Result<Option<T>, Error> {
+        let path = self.path(slot)?;
+        let json = match storage::read(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::SaveRead(path, e)),
+        };
+
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| Error::SaveFormat(path, e))
+    }
+
+    pub fn exists(&self, slot: &str) -> bool {
+        self.path(slot).map_or(false, |path| storage::exists(&path))
+    }
+
+    pub fn delete(&self, slot: &str) -> Result<(), Error> {
+        let path = self.path(slot)?;
+        match storage::remove(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(Error::SaveWrite(path, e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The slots which have been saved to, in alphabetical order.
+    pub fn slots(&self) -> Vec<String> {
+        let paths = match &self.directory {
+            Some(directory) => storage::list(directory),
+            None => return Vec::new(),
+        };
+
+        let mut slots: Vec<String> = paths
+            .into_iter()
+            .filter_map(|path| match path.extension() {
+                Some(extension) if extension == "json" => {
+                    Some(path.file_stem()?.to_string_lossy().into_owned())
+                }
+                _ => None,
+            })
+            .collect();
+        slots.sort();
+        slots
+    }
+
+    fn path(&self, slot: &str) -> Result<PathBuf, Error> {
+        let directory = self.directory.as_ref().ok_or(Error::SaveDirectory)?;
+        Ok(directory.join(format!("{}.json", slot)))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    pub fn read(path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    pub fn write(path: &Path, contents: &str) -> io::Result<()> {
+        let temporary = path.with_extension("json.tmp");
+        fs::create_dir_all(path.parent().unwrap_or(path))
+            .and_then(|_| fs::write(&temporary, contents))
+            .and_then(|_| fs::rename(&temporary, path))
+    }
+
+    pub fn exists(path: &Path) -> bool {
+        path.is_file()
+    }
+
+    pub fn remove(path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    pub fn list(directory: &Path) -> Vec<PathBuf> {
+        match fs::read_dir(directory) {
+            Ok(entries) => entries.filter_map(|e| Some(e.ok()?.path())).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Slots are kept in the page's local storage, keyed by the path they'd have on the desktop.
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use web_sys::Storage;
+
+    pub fn read(path: &Path) -> io::Result<String> {
+        local_storage()?
+            .get_item(&key(path))
+            .map_err(|_| unavailable())?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    /// Local storage replaces an item all at once, so there's no need for a temporary copy.
+    pub fn write(path: &Path, contents: &str) -> io::Result<()> {
+        local_storage()?
+            .set_item(&key(path), contents)
+            .map_err(|_| unavailable())
+    }
+
+    pub fn exists(path: &Path) -> bool {
+        read(path).is_ok()
+    }
+
+    pub fn remove(path: &Path) -> io::Result<()> {
+        local_storage()?
+            .remove_item(&key(path))
+            .map_err(|_| unavailable())
+    }
+
+    pub fn list(directory: &Path) -> Vec<PathBuf> {
+        let storage = match local_storage() {
+            Ok(storage) => storage,
+            Err(_) => return Vec::new(),
+        };
+        let length = storage.length().unwrap_or(0);
+
+        (0..length)
+            .filter_map(|index| storage.key(index).ok().flatten())
+            .map(PathBuf::from)
+            .filter(|path| path.parent() == Some(directory))
+            .collect()
+    }
+
+    fn local_storage() -> io::Result<Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(unavailable)
+    }
+
+    fn key(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    fn unavailable() -> io::Error {
+        io::Error::new(io::ErrorKind::Other, "local storage is unavailable")
+    }
+}
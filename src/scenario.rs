@@ -0,0 +1,257 @@
+use crate::components::{Acceleration, Guid, Shape, Tag, Transform, Velocity};
+use crate::error::Error;
+use crate::headless::Headless;
+use crate::query;
+use glam::{Vec2, Vec4};
+use hecs::{Entity, World};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const TOLERANCE: f32 = 1e-4;
+
+/// A scripted regression test, run against a [`Headless`] game.
+///
+/// Scenario files contain one command per line; blank lines and lines starting with `#` are ignored:
+///
+/// ```text
+/// scene level1.alpha
+/// spawn Player 0 0 32 32 0
+/// velocity Player 10 0
+/// acceleration Player 0 -10
+/// step 60
+/// assert Player position 10 0
+/// assert Player velocity 10 0
+/// assert Player color 1 0 0 1
+/// assert count 3
+/// ```
+///
+/// `step` runs the game for a number of frames, each [`Time::FIXED_DELTA`](crate::Time::FIXED_DELTA)
+/// after the last with one fixed update, so a scenario plays out the same every time.
+pub struct Scenario {
+    commands: Vec<(usize, Command)>,
+}
+
+enum Command {
+    Scene(String),
+    Spawn(Tag, Transform),
+    Velocity(String, Vec2),
+    Acceleration(String, Vec2),
+    Step(u32),
+    AssertCount(usize),
+    AssertPosition(String, Vec2),
+    AssertVelocity(String, Vec2),
+    AssertSize(String, Vec2),
+    AssertRotation(String, f32),
+    AssertColor(String, Vec4),
+}
+
+impl Scenario {
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> Result<Self, Error> {
+        let path = filename.as_ref();
+        let source =
+            fs::read_to_string(path).map_err(|e| Error::SceneLoad(path.to_path_buf(), e))?;
+
+        Self::parse(&source)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let mut commands = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let command = match words.as_slice() {
+                ["scene", filename] => Command::Scene(filename.to_string()),
+                ["spawn", tag, x, y, width, height, rotation] => {
+                    let transform = Transform {
+                        position: Vec2::new(number(line_number, x)?, number(line_number, y)?),
+                        size: Vec2::new(number(line_number, width)?, number(line_number, height)?),
+                        rotation: number(line_number, rotation)?,
+                        scale: Vec2::ONE,
+                        origin: Transform::ORIGIN_BOTTOM_LEFT,
+                    };
+                    Command::Spawn(Tag(tag.to_string()), transform)
+                }
+                ["velocity", tag, x, y] => Command::Velocity(
+                    tag.to_string(),
+                    Vec2::new(number(line_number, x)?, number(line_number, y)?),
+                ),
+                ["acceleration", tag, x, y] => Command::Acceleration(
+                    tag.to_string(),
+                    Vec2::new(number(line_number, x)?, number(line_number, y)?),
+                ),
+                ["step", steps] => Command::Step(
+                    u32::from_str(steps).map_err(|_| parse_error(line_number, steps))?,
+                ),
+                ["assert", "count", count] => Command::AssertCount(
+                    usize::from_str(count).map_err(|_| parse_error(line_number, count))?,
+                ),
+                ["assert", tag, "position", x, y] => Command::AssertPosition(
+                    tag.to_string(),
+                    Vec2::new(number(line_number, x)?, number(line_number, y)?),
+                ),
+                ["assert", tag, "velocity", x, y] => Command::AssertVelocity(
+                    tag.to_string(),
+                    Vec2::new(number(line_number, x)?, number(line_number, y)?),
+                ),
+                ["assert", tag, "size", width, height] => Command::AssertSize(
+                    tag.to_string(),
+                    Vec2::new(number(line_number, width)?, number(line_number, height)?),
+                ),
+                ["assert", tag, "rotation", rotation] => {
+                    Command::AssertRotation(tag.to_string(), number(line_number, rotation)?)
+                }
+                ["assert", tag, "color", r, g, b, a] => Command::AssertColor(
+                    tag.to_string(),
+                    Vec4::new(
+                        number(line_number, r)?,
+                        number(line_number, g)?,
+                        number(line_number, b)?,
+                        number(line_number, a)?,
+                    ),
+                ),
+                _ => return Err(parse_error(line_number, line)),
+            };
+
+            commands.push((line_number, command));
+        }
+
+        Ok(Self { commands })
+    }
+
+    /// Runs the scenario in a fresh headless game, which is returned so that it can be looked at
+    /// further once every assertion has passed.
+    pub fn run(&self) -> Result<Headless, Error> {
+        let mut headless = Headless::new();
+
+        for (line_number, command) in &self.commands {
+            let line_number = *line_number;
+            let world = &mut headless.game.world;
+
+            match command {
+                Command::Scene(filename) => headless.game.load_scene(filename)?,
+                Command::Spawn(tag, transform) => {
+                    let shape = Shape { color: Vec4::ONE };
+                    world.spawn((Guid::generate(), tag.clone(), transform.clone(), shape));
+                }
+                Command::Velocity(tag, linear) => {
+                    let entity = find(world, line_number, tag)?;
+                    let velocity = Velocity {
+                        linear: *linear,
+                        angular: 0.0,
+                    };
+                    world.insert_one(entity, velocity).ok();
+                }
+                Command::Acceleration(tag, linear) => {
+                    let entity = find(world, line_number, tag)?;
+                    let acceleration = Acceleration {
+                        linear: *linear,
+                        angular: 0.0,
+                    };
+                    world.insert_one(entity, acceleration).ok();
+                }
+                Command::Step(steps) => headless.step(*steps)?,
+                Command::AssertVelocity(tag, expected) => {
+                    let entity = find(world, line_number, tag)?;
+                    let actual = component::<Velocity>(world, line_number, entity)?.linear;
+                    if !actual.abs_diff_eq(*expected, TOLERANCE) {
+                        return Err(assertion_failed(
+                            line_number,
+                            format!("expected {} velocity {}, found {}", tag, expected, actual),
+                        ));
+                    }
+                }
+                Command::AssertCount(expected) => {
+                    let actual = world.len() as usize;
+                    if actual != *expected {
+                        return Err(assertion_failed(
+                            line_number,
+                            format!("expected {} entities, found {}", expected, actual),
+                        ));
+                    }
+                }
+                Command::AssertPosition(tag, expected) => {
+                    let entity = find(world, line_number, tag)?;
+                    let actual = component::<Transform>(world, line_number, entity)?.position;
+                    if !actual.abs_diff_eq(*expected, TOLERANCE) {
+                        return Err(assertion_failed(
+                            line_number,
+                            format!("expected {} position {}, found {}", tag, expected, actual),
+                        ));
+                    }
+                }
+                Command::AssertSize(tag, expected) => {
+                    let entity = find(world, line_number, tag)?;
+                    let actual = component::<Transform>(world, line_number, entity)?.size;
+                    if !actual.abs_diff_eq(*expected, TOLERANCE) {
+                        return Err(assertion_failed(
+                            line_number,
+                            format!("expected {} size {}, found {}", tag, expected, actual),
+                        ));
+                    }
+                }
+                Command::AssertRotation(tag, expected) => {
+                    let entity = find(world, line_number, tag)?;
+                    let actual = component::<Transform>(world, line_number, entity)?.rotation;
+                    if (actual - expected).abs() > TOLERANCE {
+                        return Err(assertion_failed(
+                            line_number,
+                            format!("expected {} rotation {}, found {}", tag, expected, actual),
+                        ));
+                    }
+                }
+                Command::AssertColor(tag, expected) => {
+                    let entity = find(world, line_number, tag)?;
+                    let actual = component::<Shape>(world, line_number, entity)?.color;
+                    if !actual.abs_diff_eq(*expected, TOLERANCE) {
+                        return Err(assertion_failed(
+                            line_number,
+                            format!("expected {} color {}, found {}", tag, expected, actual),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(headless)
+    }
+}
+
+fn number(line_number: usize, word: &str) -> Result<f32, Error> {
+    f32::from_str(word).map_err(|_| parse_error(line_number, word))
+}
+
+fn parse_error(line_number: usize, unexpected: &str) -> Error {
+    Error::ScenarioParse(line_number, unexpected.to_string())
+}
+
+fn assertion_failed(line_number: usize, message: String) -> Error {
+    Error::ScenarioAssertion(line_number, message)
+}
+
+fn find(world: &World, line_number: usize, tag: &str) -> Result<Entity, Error> {
+    query::find_by_tag(world, tag)
+        .ok_or_else(|| assertion_failed(line_number, format!("no entity tagged {}", tag)))
+}
+
+fn component<T: hecs::Component + Clone>(
+    world: &World,
+    line_number: usize,
+    entity: Entity,
+) -> Result<T, Error> {
+    world
+        .get::<T>(entity)
+        .map(|component| (*component).clone())
+        .map_err(|_| {
+            assertion_failed(
+                line_number,
+                format!("entity is missing {}", std::any::type_name::<T>()),
+            )
+        })
+}
@@ -0,0 +1,435 @@
+use crate::components::{Children, Guid, Parent, Tag};
+use crate::error::Error;
+use crate::files;
+use crate::hierarchy;
+use crate::migration::{Migrations, SCENE_VERSION};
+use crate::registry::{ComponentRegistry, Reflect};
+use crate::sub_scene::SubSceneEntity;
+use hecs::{Entity, EntityBuilder, World};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+struct EntityDescription {
+    guid: Guid,
+    parent: Option<Guid>,
+    /// Component names and their serialised values, in the order they appear in the file.
+    components: Vec<(String, String)>,
+}
+
+/// A component in a [`SceneDescription`] whose value matched a search.
+#[derive(Clone, Debug)]
+pub struct SceneMatch {
+    pub entity: usize,
+    pub component: usize,
+    /// The tag of the matching entity, if it has one, to tell the user which entity matched.
+    pub tag: Option<String>,
+    pub name: String,
+    pub value: String,
+}
+
+/// The parsed contents of a scene file, ready to be spawned into a world any number of times.
+///
+/// Each entity is a block of `Name: value` lines, one per component, separated by `---` lines.
+/// The components are read and written through a [`ComponentRegistry`].
+#[derive(Clone, Debug, Default)]
+pub struct SceneDescription {
+    entities: Vec<EntityDescription>,
+}
+
+impl SceneDescription {
+    /// Parses a scene, first upgrading it in memory if it was saved by an older version of alpha.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let (source, _) = Migrations::default().upgrade(source)?;
+
+        let mut entities = Vec::new();
+        let mut lines = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            if line == "---" {
+                if !lines.is_empty() {
+                    entities.push(parse_entity(&lines)?);
+                    lines.clear();
+                }
+            } else if !line.trim().is_empty() {
+                lines.push((i + 1, line));
+            }
+        }
+        if !lines.is_empty() {
+            entities.push(parse_entity(&lines)?);
+        }
+
+        Ok(Self { entities })
+    }
+
+    /// Captures `entity` and all of its descendants, so that they can be spawned again later.
+    pub fn capture(world: &World, registry: &ComponentRegistry, entity: Entity) -> Self {
+        let mut entities = Vec::new();
+        let mut pending = vec![entity];
+        while let Some(entity) = pending.pop() {
+            if let Ok(children) = world.get::<Children>(entity) {
+                pending.extend(children.0.iter().copied());
+            }
+            if world.get::<SubSceneEntity>(entity).is_ok() {
+                continue;
+            }
+
+            let guid = match world.get::<Guid>(entity) {
+                Ok(guid) => *guid,
+                Err(_) => continue,
+            };
+            let parent = world
+                .get::<Parent>(entity)
+                .ok()
+                .and_then(|parent| world.get::<Guid>(parent.0).ok().map(|guid| *guid));
+            let components = registry
+                .iter()
+                .filter_map(|registration| {
+                    (registration.serialise)(world, entity)
+                        .map(|value| (registration.name.to_string(), value))
+                })
+                .collect();
+
+            entities.push(EntityDescription {
+                guid,
+                parent,
+                components,
+            });
+        }
+
+        Self { entities }
+    }
+
+    /// The first entity whose parent isn't part of the scene, e.g. the entity a scene was captured
+    /// from with [`SceneDescription::capture`].
+    pub fn root(&self) -> Option<Guid> {
+        self.entities
+            .iter()
+            .find(|entity| {
+                entity.parent.map_or(true, |parent| {
+                    !self.entities.iter().any(|other| other.guid == parent)
+                })
+            })
+            .map(|entity| entity.guid)
+    }
+
+    /// Captures every entity in the world, so that it can be put back the way it was later.
+    pub fn capture_world(world: &World, registry: &ComponentRegistry) -> Self {
+        let roots: Vec<Entity> = world
+            .query::<()>()
+            .without::<Parent>()
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        let entities = roots
+            .into_iter()
+            .flat_map(|root| Self::capture(world, registry, root).entities)
+            .collect();
+
+        Self { entities }
+    }
+
+    /// Gives every entity a new GUID, keeping their parents pointing at the right entities, so the
+    /// scene can be spawned alongside the entities it was captured from. Returns the new GUIDs
+    /// keyed by the old ones.
+    pub fn regenerate_guids(&mut self) -> HashMap<Guid, Guid> {
+        let guids: HashMap<Guid, Guid> = self
+            .entities
+            .iter()
+            .map(|entity| (entity.guid, Guid::generate()))
+            .collect();
+
+        for entity in &mut self.entities {
+            entity.guid = guids[&entity.guid];
+            if let Some(parent) = entity.parent.and_then(|parent| guids.get(&parent)) {
+                entity.parent = Some(*parent);
+            }
+        }
+
+        guids
+    }
+
+    pub fn spawn(&self, world: &mut World, registry: &ComponentRegistry) {
+        self.spawn_entities(world, registry);
+    }
+
+    /// Spawns the scene with its root entities as children of `parent`, returning the roots. The
+    /// entities are marked as belonging to a [`SubScene`](crate::SubScene), so they aren't saved.
+    pub(crate) fn spawn_under(
+        &self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+        parent: Entity,
+    ) -> Vec<Entity> {
+        let spawned = self.spawn_entities(world, registry);
+        for entity in spawned.values() {
+            world.insert_one(*entity, SubSceneEntity).ok();
+        }
+
+        let roots: Vec<Entity> = self
+            .entities
+            .iter()
+            .filter(|entity| {
+                entity
+                    .parent
+                    .map_or(true, |guid| !spawned.contains_key(&guid))
+            })
+            .map(|entity| spawned[&entity.guid])
+            .collect();
+        for root in &roots {
+            hierarchy::set_parent(world, *root, Some(parent));
+        }
+
+        roots
+    }
+
+    fn spawn_entities(
+        &self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> HashMap<Guid, Entity> {
+        let mut spawned = HashMap::with_capacity(self.entities.len());
+        for entity in &self.entities {
+            let mut builder = EntityBuilder::new();
+            builder.add(entity.guid);
+
+            for (name, value) in &entity.components {
+                match registry.get(name) {
+                    Some(registration) => {
+                        if !(registration.deserialise)(value, &mut builder) {
+                            warn!("invalid {} component: {}", name, value);
+                        }
+                    }
+                    None => warn!("unknown component {}", name),
+                }
+            }
+
+            let id = world.spawn(builder.build());
+            spawned.insert(entity.guid, id);
+        }
+
+        // Parents can appear after their children in the file, so link them up once everything exists.
+        for entity in &self.entities {
+            if let Some(parent) = entity.parent.and_then(|guid| spawned.get(&guid)) {
+                hierarchy::set_parent(world, spawned[&entity.guid], Some(*parent));
+            }
+        }
+
+        spawned
+    }
+
+    /// Finds every component whose value contains `pattern`, optionally only looking at components
+    /// with the given name.
+    pub fn find(&self, name: Option<&str>, pattern: &str) -> Vec<SceneMatch> {
+        let mut matches = Vec::new();
+        if pattern.is_empty() {
+            return matches;
+        }
+
+        for (entity_index, entity) in self.entities.iter().enumerate() {
+            let tag = entity
+                .components
+                .iter()
+                .find(|(n, _)| n == Tag::NAME)
+                .map(|(_, value)| value.clone());
+
+            for (component_index, (n, value)) in entity.components.iter().enumerate() {
+                if name.map_or(true, |name| name == n) && value.contains(pattern) {
+                    matches.push(SceneMatch {
+                        entity: entity_index,
+                        component: component_index,
+                        tag: tag.clone(),
+                        name: n.clone(),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Replaces every occurrence of `pattern` in the component found by [`SceneDescription::find`].
+    pub fn replace(&mut self, found: &SceneMatch, pattern: &str, replacement: &str) {
+        if let Some((_, value)) = self
+            .entities
+            .get_mut(found.entity)
+            .and_then(|entity| entity.components.get_mut(found.component))
+        {
+            *value = value.replace(pattern, replacement);
+        }
+    }
+
+    /// Writes the scene back out in the format understood by [`SceneDescription::parse`].
+    pub fn serialise(&self) -> String {
+        let mut scene = format!("Version: {}\n", SCENE_VERSION);
+
+        for entity in &self.entities {
+            let components = entity
+                .components
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone()));
+            write_entity(&mut scene, entity.guid, entity.parent, components);
+        }
+
+        scene
+    }
+}
+
+/// Parses an entity's `Name: value` lines, each numbered by where it is in the scene file.
+fn parse_entity(lines: &[(usize, &str)]) -> Result<EntityDescription, Error> {
+    let mut guid = None;
+    let mut parent = None;
+    let mut components = Vec::new();
+
+    for (number, line) in lines {
+        let invalid = || Error::SceneParse(*number, line.to_string());
+        let (name, value) = line.split_once(':').ok_or_else(invalid)?;
+        let value = value.strip_prefix(' ').unwrap_or(value);
+
+        match name {
+            "Guid" => guid = Some(Guid(u64::from_str_radix(value, 16).map_err(|_| invalid())?)),
+            "Parent" => parent = Some(Guid(u64::from_str_radix(value, 16).map_err(|_| invalid())?)),
+            "Version" => {}
+            _ => components.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    Ok(EntityDescription {
+        guid: guid.unwrap_or_else(Guid::generate),
+        parent,
+        components,
+    })
+}
+
+/// Serialises every entity in the world into the scene file format understood by
+/// [`SceneDescription::parse`].
+pub fn serialise(world: &World, registry: &ComponentRegistry) -> String {
+    let mut scene = format!("Version: {}\n", SCENE_VERSION);
+
+    for e_ref in world.iter() {
+        let entity = e_ref.entity();
+        if world.get::<SubSceneEntity>(entity).is_ok() {
+            continue;
+        }
+
+        let guid = match world.get::<Guid>(entity) {
+            Ok(guid) => *guid,
+            Err(_) => Guid::generate(),
+        };
+
+        let parent = world
+            .get::<Parent>(entity)
+            .ok()
+            .and_then(|parent| world.get::<Guid>(parent.0).ok().map(|guid| *guid));
+
+        let components = registry.iter().filter_map(|registration| {
+            (registration.serialise)(world, entity).map(|value| (registration.name, value))
+        });
+
+        write_entity(&mut scene, guid, parent, components);
+    }
+
+    scene
+}
+
+fn write_entity<'a>(
+    scene: &mut String,
+    guid: Guid,
+    parent: Option<Guid>,
+    components: impl Iterator<Item = (&'a str, String)>,
+) {
+    scene.push_str(&format!("Guid: {:016x}\n", guid.0));
+    if let Some(parent) = parent {
+        scene.push_str(&format!("Parent: {:016x}\n", parent.0));
+    }
+
+    for (name, value) in components {
+        scene.push_str(&format!("{}: {}\n", name, value));
+    }
+
+    scene.push_str("---\n");
+}
+
+/// Lists every scene file under `directory`, including those in subdirectories.
+pub fn scene_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    fn visit(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                visit(&path, files)?;
+            } else if path.extension().map_or(false, |ext| ext == "alpha") {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    visit(directory, &mut files)?;
+    files.sort();
+
+    Ok(files)
+}
+
+/// Keeps track of the scene files a game has loaded and which one currently populates the world.
+#[derive(Default)]
+pub struct SceneManager {
+    scenes: HashMap<PathBuf, SceneDescription>,
+    active: Option<PathBuf>,
+}
+
+impl SceneManager {
+    pub fn load<P: AsRef<Path>>(&mut self, filename: P) -> Result<(), Error> {
+        let path = filename.as_ref();
+        let source =
+            files::read_to_string(path).map_err(|e| Error::SceneLoad(path.to_path_buf(), e))?;
+        self.scenes
+            .insert(path.to_path_buf(), SceneDescription::parse(&source)?);
+
+        info!("loaded scene {}", path.display());
+
+        Ok(())
+    }
+
+    pub fn unload<P: AsRef<Path>>(&mut self, filename: P) {
+        let path = filename.as_ref();
+        self.scenes.remove(path);
+
+        if self.active.as_deref() == Some(path) {
+            self.active = None;
+        }
+    }
+
+    pub fn is_loaded<P: AsRef<Path>>(&self, filename: P) -> bool {
+        self.scenes.contains_key(filename.as_ref())
+    }
+
+    pub fn active(&self) -> Option<&Path> {
+        self.active.as_deref()
+    }
+
+    /// Clears the world and repopulates it from the given scene, loading the scene first if necessary.
+    pub fn switch<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<(), Error> {
+        let path = filename.as_ref();
+        if !self.is_loaded(path) {
+            self.load(path)?;
+        }
+
+        world.clear();
+        self.scenes[path].spawn(world, registry);
+        self.active = Some(path.to_path_buf());
+
+        info!("switched to scene {}", path.display());
+
+        Ok(())
+    }
+}
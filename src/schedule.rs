@@ -0,0 +1,113 @@
+use crate::events::Events;
+use crate::game::measure;
+use crate::random::Random;
+use crate::renderer::camera::Camera;
+use crate::renderer::{Renderer, Scene};
+use crate::time::Time;
+use crate::timers::Timers;
+use crate::ui::GameUi;
+use hecs::World;
+use std::time::Duration;
+use winit_input_helper::WinitInputHelper;
+
+/// When a system runs during a frame, relative to the engine's own systems.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Before scripts are run.
+    PreUpdate,
+    /// Every [`Time::FIXED_DELTA`], after movement.
+    FixedUpdate,
+    /// After scripts are run.
+    Update,
+    /// After transforms are propagated, so that every entity's [`GlobalTransform`] is up to date.
+    ///
+    /// [`GlobalTransform`]: crate::GlobalTransform
+    PostUpdate,
+}
+
+/// What a system can work with while it runs.
+pub struct SystemContext<'a> {
+    pub world: &'a mut World,
+    pub events: &'a mut Events,
+    pub time: &'a Time,
+    /// The keyboard and mouse, except during [`Stage::FixedUpdate`], which doesn't line up with
+    /// the frames input is gathered for.
+    pub input: Option<&'a WinitInputHelper>,
+    pub ui: &'a mut GameUi,
+    /// Seeded random numbers, so that what the systems do can be played back exactly.
+    pub random: &'a mut Random,
+    pub timers: &'a mut Timers,
+}
+
+/// What a draw system can work with while the world is being drawn.
+pub struct DrawContext<'a> {
+    pub world: &'a World,
+    pub camera: &'a Camera,
+    pub renderer: &'a mut Renderer,
+    pub scene: &'a mut Scene,
+}
+
+/// A bundle of systems which are added together, e.g. everything a game's enemies need.
+pub trait Plugin {
+    fn build(&self, schedule: &mut Schedule);
+}
+
+type System = Box<dyn FnMut(&mut SystemContext)>;
+type DrawSystem = Box<dyn FnMut(&mut DrawContext)>;
+
+/// The systems added to a [`Game`](crate::Game) on top of the engine's own, which run in the
+/// order they were added within each [`Stage`].
+///
+/// Systems only run while the game is playing. Draw systems run whenever the world is drawn,
+/// after the engine has drawn the world's shapes and sprites.
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(Stage, &'static str, System)>,
+    draw_systems: Vec<(&'static str, DrawSystem)>,
+}
+
+impl Schedule {
+    /// Adds a system to run in the given stage. Its name is shown in the editor's profiler.
+    pub fn add_system(
+        &mut self,
+        stage: Stage,
+        name: &'static str,
+        system: impl FnMut(&mut SystemContext) + 'static,
+    ) -> &mut Self {
+        self.systems.push((stage, name, Box::new(system)));
+        self
+    }
+
+    /// Adds a system to run while the world is drawn, which can draw more into the scene.
+    pub fn add_draw_system(
+        &mut self,
+        name: &'static str,
+        system: impl FnMut(&mut DrawContext) + 'static,
+    ) -> &mut Self {
+        self.draw_systems.push((name, Box::new(system)));
+        self
+    }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin) -> &mut Self {
+        plugin.build(self);
+        self
+    }
+
+    /// Runs the systems in the given stage, adding how long each took to `timings`.
+    pub(crate) fn run(
+        &mut self,
+        stage: Stage,
+        ctx: &mut SystemContext,
+        timings: &mut Vec<(&'static str, Duration)>,
+    ) {
+        for (_, name, system) in self.systems.iter_mut().filter(|(s, ..)| *s == stage) {
+            measure(timings, *name, || system(ctx));
+        }
+    }
+
+    pub(crate) fn draw(&mut self, ctx: &mut DrawContext) {
+        for (_, system) in &mut self.draw_systems {
+            system(ctx);
+        }
+    }
+}
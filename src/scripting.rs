@@ -0,0 +1,1311 @@
+use crate::animation::{self, AnimatedProperty, AnimatedSprite, AnimationPreset, Easing};
+use crate::audio::SoundHandle;
+use crate::components::{Guid, Script, ScriptParameter, Shape, Transform};
+use crate::error::Error;
+use crate::events::{CollisionEnded, CollisionStarted, Events, ScriptEvent, TimerFired};
+use crate::handle::EntityHandle;
+use crate::navigation::NavGrid;
+use crate::project::ScriptLimits;
+use crate::random::Random;
+use crate::renderer::camera::Camera;
+use crate::save::SaveData;
+use crate::time::Time;
+use crate::timers::{Delay, TimerId, TimerRequest};
+use cache::ModuleCache;
+use glam::{Vec2, Vec4};
+use hecs::{Entity, World};
+use limits::LimitingTunables;
+use log::{error, info, warn};
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use wasmer::wasmparser::Operator;
+use wasmer::{
+    imports, BaseTunables, CompilerConfig, Cranelift, Exports, Function, ImportObject, Instance,
+    Module, Pages, RuntimeError, Store, Target, Universal, WasmerEnv,
+};
+use wasmer_middlewares::metering::{self, MeteringPoints};
+use wasmer_middlewares::Metering;
+use wasmer_wasi::{Pipe, WasiEnv, WasiState};
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+mod cache;
+mod limits;
+#[cfg(feature = "lua")]
+mod lua;
+
+/// How often to check whether any scripts have changed on disk.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The keys scripts can ask about with `get_input`, which takes an index into this table.
+const KEYS: [VirtualKeyCode; 16] = [
+    VirtualKeyCode::Left,
+    VirtualKeyCode::Right,
+    VirtualKeyCode::Up,
+    VirtualKeyCode::Down,
+    VirtualKeyCode::W,
+    VirtualKeyCode::A,
+    VirtualKeyCode::S,
+    VirtualKeyCode::D,
+    VirtualKeyCode::Space,
+    VirtualKeyCode::Return,
+    VirtualKeyCode::Escape,
+    VirtualKeyCode::LShift,
+    VirtualKeyCode::LControl,
+    VirtualKeyCode::Q,
+    VirtualKeyCode::E,
+    VirtualKeyCode::Tab,
+];
+
+/// Runs the WebAssembly modules attached to entities with a [`Script`] component.
+///
+/// Each module is read and compiled the first time an entity uses it and then cached by path, so
+/// any number of entities can share a script without compiling it again. Modules are compiled again
+/// when their file changes, so scripts can be rebuilt while the game or editor is running.
+///
+/// Scripts can import the following functions from the `alpha` namespace to read and modify the
+/// entity they're attached to:
+///
+/// ```text
+/// get_position_x() -> f32
+/// get_position_y() -> f32
+/// set_position(x: f32, y: f32)
+/// get_rotation() -> f32
+/// set_rotation(degrees: f32)
+/// set_color(r: f32, g: f32, b: f32, a: f32)
+/// get_input(key: u32) -> u32
+/// get_input_pressed(key: u32) -> u32
+/// get_mouse_x() -> f32
+/// get_mouse_y() -> f32
+/// get_mouse_button(button: u32) -> u32
+/// get_parameter_float(index: u32) -> f32
+/// get_parameter_int(index: u32) -> i32
+/// get_parameter_bool(index: u32) -> u32
+/// get_parameter_color(index: u32, channel: u32) -> f32
+/// emit_event(id: u32)
+/// get_time() -> f32
+/// get_frame() -> u64
+/// get_time_scale() -> f32
+/// set_time_scale(scale: f32)
+/// quit()
+/// play_sound(sound: u32)
+/// tween(property: u32, x: f32, y: f32, z: f32, w: f32, seconds: f32, easing: u32)
+/// play_preset(preset: u32) -> u32
+/// play_animation()
+/// stop_animation()
+/// set_animation_clip(index: u32)
+/// find_path(from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> u32
+/// get_path_x(index: u32) -> f32
+/// get_path_y(index: u32) -> f32
+/// save_set(key: u32, value: f32)
+/// save_get(key: u32) -> f32
+/// save_has(key: u32) -> u32
+/// save_write(slot: u32) -> u32
+/// save_read(slot: u32) -> u32
+/// random() -> f32
+/// random_range(min: f32, max: f32) -> f32
+/// start_timer(seconds: f32, repeating: u32) -> u64
+/// start_timer_frames(frames: u32, repeating: u32) -> u64
+/// cancel_timer(timer: u64)
+/// entity_valid(entity: u64) -> u32
+/// get_entity_position_x(entity: u64) -> f32
+/// get_entity_position_y(entity: u64) -> f32
+/// ```
+///
+/// `get_time` is how many seconds the game has been running, and `set_time_scale` speeds time up
+/// or slows it down, as in [`Time::scale`], from the next frame on.
+///
+/// `play_sound` plays one of the sounds registered with [`Audio`](crate::Audio), given by its
+/// [`SoundHandle::index`], at the end of the frame.
+///
+/// `tween` animates one of the entity's properties to a new value, as in [`animation::tween`].
+/// Properties and easings are given by their index in [`AnimatedProperty::ALL`] and
+/// [`Easing::ALL`]. `play_preset` plays one of the [`AnimationPreset::ALL`] on the entity, as in
+/// [`animation::play_preset`], and returns 0 if there's no preset at the index. `play_animation`,
+/// `stop_animation` and `set_animation_clip` control the entity's [`AnimatedSprite`], whose clips
+/// are given by their index.
+///
+/// `find_path` searches the [`NavGrid`] for a way around the world's obstacles and returns how
+/// many points there are along it, or 0 if there's no way through. The points are then read with
+/// `get_path_x` and `get_path_y`, until the next call to `find_path`.
+///
+/// `save_set` stores a value under a key, which every script can read back with `save_get`.
+/// `save_write` saves all of the stored values to a slot of the game's [`SaveData`], and
+/// `save_read` replaces them with those saved in a slot. Both return 1 if they succeed.
+///
+/// `random` returns a number from 0 up to 1, and `random_range` one from `min` up to `max`. Each
+/// entity's script gets its own stream of numbers, which is the same each time the game is played
+/// with the same seed, as for [`Random`].
+///
+/// `start_timer` and `start_timer_frames` start one of the [`Timers`](crate::Timers) for the
+/// entity, which fires once or, if `repeating` is 1, until it's cancelled. They return the
+/// timer's id, which `on_timer` is called with each time it fires.
+///
+/// Entities are given to scripts as [`EntityHandle`]s, passed as the entity's [`Guid`], which
+/// scripts can hold on to across frames. `entity_valid` returns 1 while the entity is alive and 0
+/// once it has been despawned, rather than the handle coming to mean whichever entity hecs reuses
+/// its id for. `get_entity_position_x` and `get_entity_position_y` read where another entity was
+/// at the start of the frame, or return 0 if it's gone. Entities without a [`Guid`] are given the
+/// handle 0, which is never valid.
+///
+/// Parameters are declared in an `alpha_parameters` custom section, in the format read by
+/// [`parse_parameters`], and looked up by the order they're declared in.
+///
+/// Scripts compiled from languages which expect WASI, like Rust or C, can also use it to print to
+/// the log, read the clocks and read files from the script's own directory.
+///
+/// With the `lua` feature, `.lua` scripts are run too. They get the same functions in a global
+/// `alpha` table, except that `play_preset` takes the preset's name, e.g. `"pulse"`, and define
+/// the same callbacks as global functions. Their parameters are declared
+/// with their defaults in a global `parameters` table, which holds the entity's values whenever a
+/// callback is called.
+pub struct SystemWasmer {
+    store: Store,
+    limits: ScriptLimits,
+    cache: ModuleCache,
+    modules: HashMap<PathBuf, CachedModule>,
+    last_reload_check: Instant,
+    host: HostEnv,
+    instances: HashMap<Entity, ScriptInstance>,
+    faults: HashMap<Entity, Fault>,
+}
+
+/// A script which failed to load or trapped. It isn't run again until its module changes.
+struct Fault {
+    path: PathBuf,
+    message: String,
+}
+
+/// A script's instance lives as long as its entity, so anything the script keeps in its linear
+/// memory carries over from one update to the next.
+struct ScriptInstance {
+    path: PathBuf,
+    /// The handle passed to the script's callbacks, kept so `on_destroy` gets the same one after
+    /// its entity has been despawned.
+    handle: Option<EntityHandle>,
+    runtime: Runtime,
+    started: bool,
+}
+
+enum Runtime {
+    Wasm {
+        instance: Instance,
+        wasi: Option<WasiEnv>,
+    },
+    #[cfg(feature = "lua")]
+    Lua(mlua::Lua),
+}
+
+struct CachedModule {
+    module: Compiled,
+    modified: Option<SystemTime>,
+    /// The parameters the script declares, with their default values.
+    parameters: Vec<(String, ScriptParameter)>,
+}
+
+enum Compiled {
+    Wasm(Module),
+    /// Lua has no separate compile step, so the source is kept and run for each instance.
+    #[cfg(feature = "lua")]
+    Lua(String),
+}
+
+enum Callback {
+    Start,
+    Update(f32),
+    Destroy,
+    /// Holds the handle of the other entity.
+    CollisionStart(u64),
+    CollisionEnd(u64),
+    /// Holds the id of the timer.
+    Timer(u64),
+}
+
+/// A copy of the components belonging to the entity whose script is running. The host functions
+/// work on this rather than the world, which is written back to once the script returns.
+#[derive(Default)]
+struct ScriptContext {
+    entity: Option<Entity>,
+    guid: Option<Guid>,
+    transform: Option<Transform>,
+    color: Option<Vec4>,
+    animated_sprite: Option<AnimatedSprite>,
+    parameters: Vec<(String, ScriptParameter)>,
+    input: ScriptInput,
+    /// Tweens started by the script, which are added to the entity once it returns.
+    tweens: Vec<(AnimatedProperty, Vec4, f32, Easing)>,
+    /// Presets started by the script, which are played once it returns.
+    presets: Vec<AnimationPreset>,
+    /// The path last found by `find_path`.
+    path: Vec<Vec2>,
+}
+
+/// The state of the keyboard and mouse this frame, with the mouse position in world coordinates.
+#[derive(Clone, Copy, Default)]
+struct ScriptInput {
+    keys_held: u32,
+    keys_pressed: u32,
+    mouse: Vec2,
+    mouse_buttons: u32,
+}
+
+#[derive(Clone, Default, WasmerEnv)]
+struct HostEnv {
+    context: Arc<Mutex<ScriptContext>>,
+    /// Events emitted by scripts which haven't been taken by [`SystemWasmer::take_events`] yet.
+    events: Arc<Mutex<Vec<ScriptEvent>>>,
+    /// Set when a script calls `quit`, until it is taken by [`SystemWasmer::take_quit_request`].
+    quit_requested: Arc<Mutex<bool>>,
+    /// The current frame's time, whose scale scripts can change.
+    time: Arc<Mutex<Time>>,
+    /// The grid `find_path` searches, as of the current frame.
+    navigation: Arc<Mutex<Arc<NavGrid>>>,
+    saves: Arc<Mutex<ScriptSaves>>,
+    random: Arc<Mutex<Random>>,
+    /// Timers started and cancelled by scripts which haven't been taken by
+    /// [`SystemWasmer::take_timer_requests`] yet.
+    timer_requests: Arc<Mutex<Vec<TimerRequest>>>,
+    /// Sounds scripts have played which haven't been taken by
+    /// [`SystemWasmer::take_sound_requests`] yet.
+    sound_requests: Arc<Mutex<Vec<SoundHandle>>>,
+    /// The position of every entity with a [`Guid`] at the start of the current frame, by which
+    /// scripts' entity handles are resolved.
+    entities: Arc<Mutex<HashMap<Guid, Vec2>>>,
+}
+
+/// The values scripts have stored to be saved, shared between every script, and where they're
+/// saved to.
+#[derive(Default)]
+struct ScriptSaves {
+    data: SaveData,
+    values: BTreeMap<u32, f32>,
+}
+
+impl SystemWasmer {
+    /// Every WebAssembly instruction a script runs uses up a point of fuel, which is topped up to
+    /// `limits.fuel` before each callback. Scripts which run out, or try to use more memory than
+    /// `limits.memory_pages`, are faulted.
+    pub fn new(limits: ScriptLimits) -> Self {
+        let metering = Arc::new(Metering::new(limits.fuel, |_: &Operator| 1));
+        let mut compiler = Cranelift::default();
+        compiler.push_middleware(metering);
+
+        let engine = Universal::new(compiler).engine();
+        let base = BaseTunables::for_target(&Target::default());
+        let tunables = LimitingTunables::new(base, Pages(limits.memory_pages));
+
+        info!("scripting initialised");
+
+        Self {
+            store: Store::new_with_tunables(&engine, tunables),
+            limits,
+            cache: ModuleCache::new(cache::DIRECTORY, limits.fuel),
+            modules: HashMap::new(),
+            last_reload_check: Instant::now(),
+            host: HostEnv::default(),
+            instances: HashMap::new(),
+            faults: HashMap::new(),
+        }
+    }
+
+    /// Creates an instance of the script for each scripted entity which doesn't have one yet, so
+    /// the cost of compiling and instantiating scripts is paid when a scene loads rather than on
+    /// its first frame.
+    pub fn instantiate_all(&mut self, world: &World) {
+        for (entity, path) in scripted_entities(world) {
+            if !self.instances.contains_key(&entity) && !self.faults.contains_key(&entity) {
+                self.instantiate(world, entity, path);
+            }
+        }
+    }
+
+    /// Takes the events scripts have emitted since this was last called.
+    pub fn take_events(&mut self) -> Vec<ScriptEvent> {
+        std::mem::take(&mut *self.host.events.lock().unwrap())
+    }
+
+    /// The time scale scripts want, which is the scale of the time last passed to
+    /// [`SystemWasmer::run`] unless a script has changed it.
+    pub fn time_scale(&self) -> f32 {
+        self.host.time.lock().unwrap().scale()
+    }
+
+    /// Takes the timers scripts have started or cancelled since this was last called.
+    pub(crate) fn take_timer_requests(&mut self) -> Vec<TimerRequest> {
+        std::mem::take(&mut *self.host.timer_requests.lock().unwrap())
+    }
+
+    /// Takes the sounds scripts have played since this was last called.
+    pub fn take_sound_requests(&mut self) -> Vec<SoundHandle> {
+        std::mem::take(&mut *self.host.sound_requests.lock().unwrap())
+    }
+
+    /// Returns `true` if a script has asked for the game to quit since this was last called.
+    pub fn take_quit_request(&mut self) -> bool {
+        std::mem::take(&mut *self.host.quit_requested.lock().unwrap())
+    }
+
+    /// Starts the random numbers scripts get again from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.host.random.lock().unwrap().reseed(seed);
+    }
+
+    /// Sets where scripts save their values to with `save_write`.
+    pub fn set_save_data(&mut self, data: SaveData) {
+        self.host.saves.lock().unwrap().data = data;
+    }
+
+    /// Returns why the entity's script stopped running, if it has.
+    pub fn fault(&self, entity: Entity) -> Option<&str> {
+        self.faults.get(&entity).map(|fault| fault.message.as_str())
+    }
+
+    /// Calls each entity's script, passing the entity's handle to each callback it exports:
+    ///
+    /// ```text
+    /// on_start(entity: u64)
+    /// on_update(entity: u64, dt: f32)
+    /// on_destroy(entity: u64)
+    /// on_collision_start(entity: u64, other: u64)
+    /// on_collision_end(entity: u64, other: u64)
+    /// on_timer(entity: u64, timer: u64)
+    /// ```
+    ///
+    /// `on_start` is called before the script's first update, and `on_destroy` once its entity has
+    /// been despawned or the script removed, after which its instance is dropped. Scripts which
+    /// are reloaded get a new instance and start again.
+    ///
+    /// The collision callbacks are called after `on_update` for each of the `events` published
+    /// last frame about the entity's collider starting or stopping overlapping another, and then
+    /// `on_timer` for each of the entity's timers which fired last frame.
+    ///
+    /// A script which fails to load or traps is logged and marked as faulted, and the other
+    /// scripts carry on running.
+    pub fn run(
+        &mut self,
+        world: &mut World,
+        input: &WinitInputHelper,
+        camera: &Camera,
+        time: &Time,
+        events: &Events,
+        navigation: Arc<NavGrid>,
+    ) {
+        let scripts = scripted_entities(world);
+        let input = ScriptInput::new(input, camera);
+        let dt = time.delta_seconds();
+        *self.host.time.lock().unwrap() = *time;
+        *self.host.navigation.lock().unwrap() = navigation;
+        self.resolve_entities(world);
+
+        let destroyed: Vec<Entity> = self
+            .instances
+            .iter()
+            .filter(|(entity, script)| !scripts.contains(&(**entity, script.path.clone())))
+            .map(|(entity, _)| *entity)
+            .collect();
+        for entity in destroyed {
+            if let Some(script) = self.instances.remove(&entity) {
+                let callbacks = [Callback::Destroy];
+                let limits = self.limits;
+                if let Err(e) = call(
+                    &self.host, limits, world, entity, &script, input, &callbacks,
+                ) {
+                    error!("{}", e);
+                }
+            }
+        }
+
+        for (entity, path) in scripts {
+            if self.faults.contains_key(&entity) {
+                continue;
+            }
+
+            if !self.instances.contains_key(&entity) {
+                self.instantiate(world, entity, path);
+            }
+
+            if let Some(script) = self.instances.get_mut(&entity) {
+                let mut callbacks = Vec::new();
+                if !script.started {
+                    callbacks.push(Callback::Start);
+                }
+                callbacks.push(Callback::Update(dt));
+                callbacks.extend(collisions(events, world, entity));
+                callbacks.extend(timers(events, entity));
+                script.started = true;
+
+                if let Err(e) = call(
+                    &self.host,
+                    self.limits,
+                    world,
+                    entity,
+                    script,
+                    input,
+                    &callbacks,
+                ) {
+                    let path = script.path.clone();
+                    self.instances.remove(&entity);
+                    self.set_fault(entity, path, e);
+                }
+            }
+        }
+    }
+
+    /// Destroys every script's instance, so they start again the next time they're run. Any
+    /// faults and stored values are forgotten too, giving the scripts another chance.
+    pub fn stop(&mut self, world: &mut World) {
+        self.resolve_entities(world);
+        for (entity, script) in self.instances.drain() {
+            if !script.started || !world.contains(entity) {
+                continue;
+            }
+
+            let callbacks = [Callback::Destroy];
+            if let Err(e) = call(
+                &self.host,
+                self.limits,
+                world,
+                entity,
+                &script,
+                ScriptInput::default(),
+                &callbacks,
+            ) {
+                error!("{}", e);
+            }
+        }
+
+        self.faults.clear();
+        self.host.saves.lock().unwrap().values.clear();
+    }
+
+    /// Takes note of where every entity with a [`Guid`] is, for scripts to look up by handle.
+    fn resolve_entities(&mut self, world: &World) {
+        let mut entities = self.host.entities.lock().unwrap();
+        entities.clear();
+        entities.extend(
+            world
+                .query::<(&Guid, Option<&Transform>)>()
+                .iter()
+                .map(|(_, (guid, transform))| {
+                    (*guid, transform.map_or(Vec2::ZERO, |t| t.position))
+                }),
+        );
+    }
+
+    fn set_fault(&mut self, entity: Entity, path: PathBuf, error: Error) {
+        error!("{}", error);
+
+        let fault = Fault {
+            path,
+            message: error.to_string(),
+        };
+        self.faults.insert(entity, fault);
+    }
+
+    fn instantiate(&mut self, world: &World, entity: Entity, path: PathBuf) {
+        match self.create_instance(&path) {
+            Ok(runtime) => {
+                let script = ScriptInstance {
+                    path,
+                    handle: EntityHandle::new(world, entity),
+                    runtime,
+                    started: false,
+                };
+                self.instances.insert(entity, script);
+            }
+            Err(e) => self.set_fault(entity, path, e),
+        }
+    }
+
+    fn create_instance(&mut self, path: &Path) -> Result<Runtime, Error> {
+        #[cfg(feature = "lua")]
+        let (host, limits) = (self.host.clone(), self.limits);
+        let imports = self.imports();
+
+        match self.module(path)? {
+            Compiled::Wasm(module) => wasm_instance(module, imports, path),
+            #[cfg(feature = "lua")]
+            Compiled::Lua(source) => {
+                lua::instantiate(source, path, &host, limits).map(Runtime::Lua)
+            }
+        }
+    }
+
+    /// Returns the compiled module for the script at `path`, compiling it if it isn't cached.
+    fn module(&mut self, path: &Path) -> Result<&Compiled, Error> {
+        if !self.modules.contains_key(path) {
+            let modified = modified(path);
+            let bytes = fs::read(path).map_err(|e| Error::ScriptLoad(path.to_path_buf(), e))?;
+
+            #[cfg(feature = "lua")]
+            if path.extension().map_or(false, |ext| ext == "lua") {
+                let source = String::from_utf8_lossy(&bytes).into_owned();
+                let parameters = lua::declared_parameters(&source, path, &self.host, self.limits)?;
+                let module = Compiled::Lua(source);
+                let cached = CachedModule {
+                    module,
+                    modified,
+                    parameters,
+                };
+                self.modules.insert(path.to_path_buf(), cached);
+
+                return Ok(&self.modules[path].module);
+            }
+
+            let module = self.cache.compile(&self.store, path, &bytes)?;
+            let parameters = module
+                .custom_sections("alpha_parameters")
+                .flat_map(|section| parse_parameters(&String::from_utf8_lossy(&section)))
+                .collect();
+            let cached = CachedModule {
+                module: Compiled::Wasm(module),
+                modified,
+                parameters,
+            };
+            self.modules.insert(path.to_path_buf(), cached);
+        }
+
+        Ok(&self.modules[path].module)
+    }
+
+    /// Keeps the scripts in step with the world and with their files on disk. Unlike
+    /// [`SystemWasmer::run`], this is called while the game is paused too.
+    pub fn maintain(&mut self, world: &mut World) {
+        let reloaded = self.forget_changed_modules();
+        self.instances
+            .retain(|_, script| !reloaded.contains(&script.path));
+
+        let scripts = scripted_entities(world);
+        self.faults.retain(|entity, fault| {
+            !reloaded.contains(&fault.path) && scripts.contains(&(*entity, fault.path.clone()))
+        });
+
+        self.declare_parameters(world);
+    }
+
+    /// Brings each script's parameters in line with those its module declares, adding any new
+    /// ones with their default values and dropping any it no longer declares.
+    fn declare_parameters(&mut self, world: &mut World) {
+        for (entity, script) in world.query_mut::<&mut Script>() {
+            if script.filepath.as_os_str().is_empty() || self.faults.contains_key(&entity) {
+                continue;
+            }
+
+            if let Err(e) = self.module(&script.filepath) {
+                self.set_fault(entity, script.filepath.to_path_buf(), e);
+                continue;
+            }
+
+            let declared = &self.modules[script.filepath.path()].parameters;
+            let parameters: Vec<(String, ScriptParameter)> = declared
+                .iter()
+                .map(|(name, default)| {
+                    let value = script
+                        .parameters
+                        .iter()
+                        .find(|(n, value)| n == name && value.kind() == default.kind())
+                        .map_or(*default, |(_, value)| *value);
+                    (name.clone(), value)
+                })
+                .collect();
+
+            if parameters != script.parameters {
+                script.parameters = parameters;
+            }
+        }
+    }
+
+    /// Drops any cached module whose file has changed on disk, so it's compiled again the next
+    /// time it's used. Returns the paths of the modules which were dropped.
+    fn forget_changed_modules(&mut self) -> Vec<PathBuf> {
+        if self.last_reload_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return Vec::new();
+        }
+        self.last_reload_check = Instant::now();
+
+        let changed: Vec<PathBuf> = self
+            .modules
+            .iter()
+            .filter(|(path, cached)| modified(path) != cached.modified)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &changed {
+            info!("reloading script {}", path.display());
+            self.modules.remove(path);
+        }
+
+        changed
+    }
+
+    fn imports(&self) -> ImportObject {
+        let store = &self.store;
+        let host = &self.host;
+
+        imports! {
+            "alpha" => {
+                "get_position_x" => Function::new_native_with_env(store, host.clone(), get_position_x),
+                "get_position_y" => Function::new_native_with_env(store, host.clone(), get_position_y),
+                "set_position" => Function::new_native_with_env(store, host.clone(), set_position),
+                "get_rotation" => Function::new_native_with_env(store, host.clone(), get_rotation),
+                "set_rotation" => Function::new_native_with_env(store, host.clone(), set_rotation),
+                "set_color" => Function::new_native_with_env(store, host.clone(), set_color),
+                "get_input" => Function::new_native_with_env(store, host.clone(), get_input),
+                "get_input_pressed" => Function::new_native_with_env(store, host.clone(), get_input_pressed),
+                "get_mouse_x" => Function::new_native_with_env(store, host.clone(), get_mouse_x),
+                "get_mouse_y" => Function::new_native_with_env(store, host.clone(), get_mouse_y),
+                "get_mouse_button" => Function::new_native_with_env(store, host.clone(), get_mouse_button),
+                "get_parameter_float" => Function::new_native_with_env(store, host.clone(), get_parameter_float),
+                "get_parameter_int" => Function::new_native_with_env(store, host.clone(), get_parameter_int),
+                "get_parameter_bool" => Function::new_native_with_env(store, host.clone(), get_parameter_bool),
+                "get_parameter_color" => Function::new_native_with_env(store, host.clone(), get_parameter_color),
+                "emit_event" => Function::new_native_with_env(store, host.clone(), emit_event),
+                "get_time" => Function::new_native_with_env(store, host.clone(), get_time),
+                "get_frame" => Function::new_native_with_env(store, host.clone(), get_frame),
+                "get_time_scale" => Function::new_native_with_env(store, host.clone(), get_time_scale),
+                "set_time_scale" => Function::new_native_with_env(store, host.clone(), set_time_scale),
+                "quit" => Function::new_native_with_env(store, host.clone(), quit),
+                "play_sound" => Function::new_native_with_env(store, host.clone(), play_sound),
+                "tween" => Function::new_native_with_env(store, host.clone(), tween),
+                "play_preset" => Function::new_native_with_env(store, host.clone(), play_preset),
+                "play_animation" => Function::new_native_with_env(store, host.clone(), play_animation),
+                "stop_animation" => Function::new_native_with_env(store, host.clone(), stop_animation),
+                "set_animation_clip" => Function::new_native_with_env(store, host.clone(), set_animation_clip),
+                "find_path" => Function::new_native_with_env(store, host.clone(), find_path),
+                "get_path_x" => Function::new_native_with_env(store, host.clone(), get_path_x),
+                "get_path_y" => Function::new_native_with_env(store, host.clone(), get_path_y),
+                "save_set" => Function::new_native_with_env(store, host.clone(), save_set),
+                "save_get" => Function::new_native_with_env(store, host.clone(), save_get),
+                "save_has" => Function::new_native_with_env(store, host.clone(), save_has),
+                "save_write" => Function::new_native_with_env(store, host.clone(), save_write),
+                "save_read" => Function::new_native_with_env(store, host.clone(), save_read),
+                "random" => Function::new_native_with_env(store, host.clone(), random),
+                "random_range" => Function::new_native_with_env(store, host.clone(), random_range),
+                "start_timer" => Function::new_native_with_env(store, host.clone(), start_timer),
+                "start_timer_frames" => Function::new_native_with_env(store, host.clone(), start_timer_frames),
+                "cancel_timer" => Function::new_native_with_env(store, host.clone(), cancel_timer),
+                "entity_valid" => Function::new_native_with_env(store, host.clone(), entity_valid),
+                "get_entity_position_x" => Function::new_native_with_env(store, host.clone(), get_entity_position_x),
+                "get_entity_position_y" => Function::new_native_with_env(store, host.clone(), get_entity_position_y),
+            },
+        }
+    }
+}
+
+impl ScriptInput {
+    fn new(input: &WinitInputHelper, camera: &Camera) -> Self {
+        let keys = |held: &dyn Fn(VirtualKeyCode) -> bool| {
+            KEYS.iter()
+                .enumerate()
+                .filter(|(_, key)| held(**key))
+                .fold(0, |bits, (index, _)| bits | 1 << index)
+        };
+
+        let mouse = input
+            .mouse()
+            .map(|(x, y)| camera.screen_to_world(Vec2::new(x, y)))
+            .unwrap_or_default();
+
+        let mouse_buttons = (0..3)
+            .filter(|button| input.mouse_held(*button))
+            .fold(0, |bits, button| bits | 1 << button);
+
+        Self {
+            keys_held: keys(&|key| input.key_held(key)),
+            keys_pressed: keys(&|key| input.key_pressed(key)),
+            mouse,
+            mouse_buttons,
+        }
+    }
+}
+
+/// Modules which import WASI get a WASI environment, with output going to the log and read-only
+/// access to the directory the script is in.
+fn wasm_instance(module: &Module, imports: ImportObject, path: &Path) -> Result<Runtime, Error> {
+    let (imports, wasi) = match wasmer_wasi::get_wasi_version(module, false) {
+        Some(version) => {
+            let wasi = wasi_env(path)?;
+            let mut wasi_imports =
+                wasmer_wasi::generate_import_object_from_env(module.store(), wasi.clone(), version);
+            if let Some(alpha) = imports.get_namespace_exports("alpha") {
+                wasi_imports.register("alpha", alpha);
+            }
+
+            (wasi_imports, Some(wasi))
+        }
+        None => (imports, None),
+    };
+
+    let instance = Instance::new(module, &imports)
+        .map_err(|e| Error::ScriptInstantiate(path.to_path_buf(), e))?;
+
+    Ok(Runtime::Wasm { instance, wasi })
+}
+
+/// The bundled source a new script starts from, chosen by the script's extension.
+pub(crate) fn template(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "wat" => Some(include_str!("../resources/templates/script.wat")),
+        #[cfg(feature = "lua")]
+        "lua" => Some(include_str!("../resources/templates/script.lua")),
+        _ => None,
+    }
+}
+
+/// Parses parameter declarations, one per line, each with a name, a type and a default value:
+///
+/// ```text
+/// speed float 120
+/// lives int 3
+/// invincible bool false
+/// tint color 1 0.5 0 1
+/// ```
+fn parse_parameters(declarations: &str) -> Vec<(String, ScriptParameter)> {
+    declarations
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.trim().split_once(' ')?;
+            let (kind, value) = rest.split_once(' ')?;
+            let parameter = ScriptParameter::parse(kind, value);
+            if parameter.is_none() {
+                warn!("invalid script parameter declaration '{}'", line);
+            }
+            parameter.map(|parameter| (name.to_string(), parameter))
+        })
+        .collect()
+}
+
+fn scripted_entities(world: &World) -> Vec<(Entity, PathBuf)> {
+    world
+        .query::<&Script>()
+        .iter()
+        .filter(|(_, script)| !script.filepath.as_os_str().is_empty())
+        .map(|(entity, script)| (entity, script.filepath.to_path_buf()))
+        .collect()
+}
+
+/// Runs the script's callbacks against a copy of the entity's components, then writes any changes
+/// back to the world.
+fn call(
+    host: &HostEnv,
+    limits: ScriptLimits,
+    world: &mut World,
+    entity: Entity,
+    script: &ScriptInstance,
+    input: ScriptInput,
+    callbacks: &[Callback],
+) -> Result<(), Error> {
+    *host.context.lock().unwrap() = ScriptContext {
+        entity: Some(entity),
+        guid: world.get::<Guid>(entity).ok().map(|guid| *guid),
+        transform: world.get::<Transform>(entity).ok().map(|t| (*t).clone()),
+        color: world.get::<Shape>(entity).ok().map(|s| s.color),
+        animated_sprite: world
+            .get::<AnimatedSprite>(entity)
+            .ok()
+            .map(|s| (*s).clone()),
+        parameters: world
+            .get::<Script>(entity)
+            .map(|s| s.parameters.clone())
+            .unwrap_or_default(),
+        input,
+        tweens: Vec::new(),
+        presets: Vec::new(),
+        path: Vec::new(),
+    };
+
+    let mut result = Ok(());
+    let id = handle_id(script.handle);
+    for callback in callbacks {
+        let called = match &script.runtime {
+            Runtime::Wasm { instance, wasi } => {
+                metering::set_remaining_points(instance, limits.fuel);
+                let called = call_wasm(&instance.exports, id, callback);
+                if let Some(wasi) = wasi {
+                    forward_output(wasi, &script.path);
+                }
+
+                called.map_err(|e| match metering::get_remaining_points(instance) {
+                    MeteringPoints::Exhausted => Error::ScriptFuel(script.path.clone()),
+                    MeteringPoints::Remaining(_) => Error::ScriptRuntime(script.path.clone(), e),
+                })
+            }
+            #[cfg(feature = "lua")]
+            Runtime::Lua(lua) => {
+                let parameters = host.context.lock().unwrap().parameters.clone();
+                lua::call(lua, limits, id, callback, &parameters)
+                    .map_err(|e| Error::ScriptLua(script.path.clone(), e))
+            }
+        };
+
+        if let Err(e) = called {
+            result = Err(e);
+            break;
+        }
+    }
+
+    let mut context = host.context.lock().unwrap();
+    if let (Some(transform), Ok(mut t)) = (&context.transform, world.get_mut::<Transform>(entity)) {
+        *t = transform.clone();
+    }
+    if let (Some(color), Ok(mut shape)) = (context.color, world.get_mut::<Shape>(entity)) {
+        shape.color = color;
+    }
+    if let (Some(animated), Ok(mut a)) = (
+        context.animated_sprite.take(),
+        world.get_mut::<AnimatedSprite>(entity),
+    ) {
+        *a = animated;
+    }
+    for (property, to, duration, easing) in context.tweens.drain(..) {
+        animation::tween(world, entity, property, to, duration, easing);
+    }
+    for preset in context.presets.drain(..) {
+        animation::play_preset(world, entity, preset);
+    }
+
+    result
+}
+
+fn call_wasm(exports: &Exports, id: u64, callback: &Callback) -> Result<(), RuntimeError> {
+    match callback {
+        Callback::Start => match exports.get_native_function::<u64, ()>("on_start") {
+            Ok(on_start) => on_start.call(id),
+            Err(_) => Ok(()),
+        },
+        Callback::Update(dt) => match exports.get_native_function::<(u64, f32), ()>("on_update") {
+            Ok(on_update) => on_update.call(id, *dt),
+            Err(_) => Ok(()),
+        },
+        Callback::Destroy => match exports.get_native_function::<u64, ()>("on_destroy") {
+            Ok(on_destroy) => on_destroy.call(id),
+            Err(_) => Ok(()),
+        },
+        Callback::CollisionStart(other) => {
+            match exports.get_native_function::<(u64, u64), ()>("on_collision_start") {
+                Ok(on_collision_start) => on_collision_start.call(id, *other),
+                Err(_) => Ok(()),
+            }
+        }
+        Callback::CollisionEnd(other) => {
+            match exports.get_native_function::<(u64, u64), ()>("on_collision_end") {
+                Ok(on_collision_end) => on_collision_end.call(id, *other),
+                Err(_) => Ok(()),
+            }
+        }
+        Callback::Timer(timer) => match exports.get_native_function::<(u64, u64), ()>("on_timer") {
+            Ok(on_timer) => on_timer.call(id, *timer),
+            Err(_) => Ok(()),
+        },
+    }
+}
+
+/// The collision callbacks for the entity, from the collision events published last frame.
+fn collisions<'a>(
+    events: &'a Events,
+    world: &'a World,
+    entity: Entity,
+) -> impl Iterator<Item = Callback> + 'a {
+    let handle = move |other: Entity| handle_id(EntityHandle::new(world, other));
+    let other = move |a: Entity, b: Entity| match (a == entity, b == entity) {
+        (true, _) => Some(handle(b)),
+        (_, true) => Some(handle(a)),
+        _ => None,
+    };
+    let started = events.read::<CollisionStarted>().iter();
+    let ended = events.read::<CollisionEnded>().iter();
+
+    started
+        .filter_map(move |e| other(e.a, e.b).map(Callback::CollisionStart))
+        .chain(ended.filter_map(move |e| other(e.a, e.b).map(Callback::CollisionEnd)))
+}
+
+/// The timer callbacks for the entity, from the timers which fired last frame.
+fn timers(events: &Events, entity: Entity) -> impl Iterator<Item = Callback> + '_ {
+    events
+        .read::<TimerFired>()
+        .iter()
+        .filter(move |e| e.entity == Some(entity))
+        .map(|e| Callback::Timer(e.id.0))
+}
+
+/// The id scripts are given for an entity's handle, which is 0 if it doesn't have one.
+fn handle_id(handle: Option<EntityHandle>) -> u64 {
+    handle.map_or(0, |handle| handle.guid().0)
+}
+
+fn wasi_env(path: &Path) -> Result<WasiEnv, Error> {
+    let directory = match path.parent() {
+        Some(directory) if directory != Path::new("") => directory,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    WasiState::new(name)
+        .stdout(Box::new(Pipe::new()))
+        .stderr(Box::new(Pipe::new()))
+        .preopen(|p| p.directory(directory).alias("/").read(true))
+        .and_then(|builder| builder.finalize())
+        .map_err(|e| Error::ScriptWasi(path.to_path_buf(), e))
+}
+
+/// Logs anything the script has written to stdout or stderr.
+fn forward_output(wasi: &WasiEnv, path: &Path) {
+    let mut state = wasi.state();
+
+    let mut stdout = String::new();
+    if let Ok(Some(file)) = state.fs.stdout_mut() {
+        let _ = file.read_to_string(&mut stdout);
+    }
+    for line in stdout.lines() {
+        info!("{}: {}", path.display(), line);
+    }
+
+    let mut stderr = String::new();
+    if let Ok(Some(file)) = state.fs.stderr_mut() {
+        let _ = file.read_to_string(&mut stderr);
+    }
+    for line in stderr.lines() {
+        warn!("{}: {}", path.display(), line);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn position(env: &HostEnv) -> Vec2 {
+    let context = env.context.lock().unwrap();
+    context
+        .transform
+        .as_ref()
+        .map_or(Vec2::ZERO, |transform| transform.position)
+}
+
+fn get_position_x(env: &HostEnv) -> f32 {
+    position(env).x
+}
+
+fn get_position_y(env: &HostEnv) -> f32 {
+    position(env).y
+}
+
+fn set_position(env: &HostEnv, x: f32, y: f32) {
+    if let Some(transform) = &mut env.context.lock().unwrap().transform {
+        transform.position = Vec2::new(x, y);
+    }
+}
+
+fn get_rotation(env: &HostEnv) -> f32 {
+    let context = env.context.lock().unwrap();
+    context
+        .transform
+        .as_ref()
+        .map_or(0.0, |transform| transform.rotation)
+}
+
+fn set_rotation(env: &HostEnv, degrees: f32) {
+    if let Some(transform) = &mut env.context.lock().unwrap().transform {
+        transform.rotation = degrees;
+    }
+}
+
+fn set_color(env: &HostEnv, r: f32, g: f32, b: f32, a: f32) {
+    if let Some(color) = &mut env.context.lock().unwrap().color {
+        *color = Vec4::new(r, g, b, a);
+    }
+}
+
+/// Returns 1 if the key at the given index of [`KEYS`] is held down, otherwise 0.
+fn get_input(env: &HostEnv, key: u32) -> u32 {
+    bit(env.context.lock().unwrap().input.keys_held, key)
+}
+
+/// Returns 1 if the key at the given index of [`KEYS`] was pressed this frame, otherwise 0.
+fn get_input_pressed(env: &HostEnv, key: u32) -> u32 {
+    bit(env.context.lock().unwrap().input.keys_pressed, key)
+}
+
+fn get_mouse_x(env: &HostEnv) -> f32 {
+    env.context.lock().unwrap().input.mouse.x
+}
+
+fn get_mouse_y(env: &HostEnv) -> f32 {
+    env.context.lock().unwrap().input.mouse.y
+}
+
+/// Returns 1 if the mouse button is held down, otherwise 0. Buttons are numbered 0 for left, 1
+/// for right and 2 for middle.
+fn get_mouse_button(env: &HostEnv, button: u32) -> u32 {
+    bit(env.context.lock().unwrap().input.mouse_buttons, button)
+}
+
+fn parameter(env: &HostEnv, index: u32) -> Option<ScriptParameter> {
+    let context = env.context.lock().unwrap();
+    context
+        .parameters
+        .get(index as usize)
+        .map(|(_, parameter)| *parameter)
+}
+
+/// Returns the value of the float parameter the script declared at the given index, or 0 if it
+/// isn't a float.
+fn get_parameter_float(env: &HostEnv, index: u32) -> f32 {
+    match parameter(env, index) {
+        Some(ScriptParameter::Float(value)) => value,
+        _ => 0.0,
+    }
+}
+
+fn get_parameter_int(env: &HostEnv, index: u32) -> i32 {
+    match parameter(env, index) {
+        Some(ScriptParameter::Int(value)) => value,
+        _ => 0,
+    }
+}
+
+fn get_parameter_bool(env: &HostEnv, index: u32) -> u32 {
+    match parameter(env, index) {
+        Some(ScriptParameter::Bool(value)) => value as u32,
+        _ => 0,
+    }
+}
+
+/// Returns one channel of a color parameter: 0 for red, 1 for green, 2 for blue and 3 for alpha.
+fn get_parameter_color(env: &HostEnv, index: u32, channel: u32) -> f32 {
+    match parameter(env, index) {
+        Some(ScriptParameter::Color(color)) if channel < 4 => color[channel as usize],
+        _ => 0.0,
+    }
+}
+
+fn emit_event(env: &HostEnv, id: u32) {
+    if let Some(entity) = env.context.lock().unwrap().entity {
+        env.events.lock().unwrap().push(ScriptEvent { entity, id });
+    }
+}
+
+fn get_time(env: &HostEnv) -> f32 {
+    env.time.lock().unwrap().elapsed().as_secs_f32()
+}
+
+fn get_frame(env: &HostEnv) -> u64 {
+    env.time.lock().unwrap().frame()
+}
+
+fn get_time_scale(env: &HostEnv) -> f32 {
+    env.time.lock().unwrap().scale()
+}
+
+fn set_time_scale(env: &HostEnv, scale: f32) {
+    env.time.lock().unwrap().set_scale(scale);
+}
+
+fn quit(env: &HostEnv) {
+    *env.quit_requested.lock().unwrap() = true;
+}
+
+fn play_sound(env: &HostEnv, sound: u32) {
+    let sound = SoundHandle::from_index(sound);
+    env.sound_requests.lock().unwrap().push(sound);
+}
+
+/// Does nothing if the property or easing is out of range.
+#[allow(clippy::too_many_arguments)]
+fn tween(env: &HostEnv, property: u32, x: f32, y: f32, z: f32, w: f32, seconds: f32, easing: u32) {
+    let property = AnimatedProperty::ALL.get(property as usize);
+    let easing = Easing::ALL.get(easing as usize);
+    if let (Some(property), Some(easing)) = (property, easing) {
+        let tween = (*property, Vec4::new(x, y, z, w), seconds, *easing);
+        env.context.lock().unwrap().tweens.push(tween);
+    }
+}
+
+fn play_preset(env: &HostEnv, preset: u32) -> u32 {
+    match AnimationPreset::ALL.get(preset as usize) {
+        Some(preset) => start_preset(env, *preset),
+        None => 0,
+    }
+}
+
+fn start_preset(env: &HostEnv, preset: AnimationPreset) -> u32 {
+    env.context.lock().unwrap().presets.push(preset);
+    1
+}
+
+fn play_animation(env: &HostEnv) {
+    if let Some(animated) = &mut env.context.lock().unwrap().animated_sprite {
+        animated.play();
+    }
+}
+
+fn stop_animation(env: &HostEnv) {
+    if let Some(animated) = &mut env.context.lock().unwrap().animated_sprite {
+        animated.stop();
+    }
+}
+
+/// Does nothing if there's no clip at the index.
+fn set_animation_clip(env: &HostEnv, index: u32) {
+    if let Some(animated) = &mut env.context.lock().unwrap().animated_sprite {
+        animated.set_clip_index(index as usize);
+    }
+}
+
+fn find_path(env: &HostEnv, from_x: f32, from_y: f32, to_x: f32, to_y: f32) -> u32 {
+    let from = Vec2::new(from_x, from_y);
+    let to = Vec2::new(to_x, to_y);
+    let path = env
+        .navigation
+        .lock()
+        .unwrap()
+        .find_path(from, to)
+        .unwrap_or_default();
+
+    let mut context = env.context.lock().unwrap();
+    context.path = path;
+    context.path.len() as u32
+}
+
+/// Returns 0 if there's no point at the index.
+fn get_path_x(env: &HostEnv, index: u32) -> f32 {
+    let context = env.context.lock().unwrap();
+    context
+        .path
+        .get(index as usize)
+        .map_or(0.0, |point| point.x)
+}
+
+fn get_path_y(env: &HostEnv, index: u32) -> f32 {
+    let context = env.context.lock().unwrap();
+    context
+        .path
+        .get(index as usize)
+        .map_or(0.0, |point| point.y)
+}
+
+fn save_set(env: &HostEnv, key: u32, value: f32) {
+    env.saves.lock().unwrap().values.insert(key, value);
+}
+
+/// Returns 0 if nothing is stored under the key.
+fn save_get(env: &HostEnv, key: u32) -> f32 {
+    let saves = env.saves.lock().unwrap();
+    saves.values.get(&key).copied().unwrap_or_default()
+}
+
+fn save_has(env: &HostEnv, key: u32) -> u32 {
+    env.saves.lock().unwrap().values.contains_key(&key) as u32
+}
+
+fn save_write(env: &HostEnv, slot: u32) -> u32 {
+    let saves = env.saves.lock().unwrap();
+    match saves.data.write(&script_slot(slot), &saves.values) {
+        Ok(()) => 1,
+        Err(e) => {
+            error!("{}", e);
+            0
+        }
+    }
+}
+
+/// Leaves the stored values as they are if the slot hasn't been saved to.
+fn save_read(env: &HostEnv, slot: u32) -> u32 {
+    let mut saves = env.saves.lock().unwrap();
+    match saves.data.read(&script_slot(slot)) {
+        Ok(Some(values)) => {
+            saves.values = values;
+            1
+        }
+        Ok(None) => 0,
+        Err(e) => {
+            error!("{}", e);
+            0
+        }
+    }
+}
+
+/// The name of a slot scripts save to, kept apart from the slots the game saves to itself.
+fn script_slot(slot: u32) -> String {
+    format!("script{}", slot)
+}
+
+fn random(env: &HostEnv) -> f32 {
+    random_range(env, 0.0, 1.0)
+}
+
+/// Returns `min` if there's nothing between it and `max`, or if either end, or the distance
+/// between them, isn't finite, as rand panics on ranges it can't scale to.
+fn random_range(env: &HostEnv, min: f32, max: f32) -> f32 {
+    let finite = min.is_finite() && max.is_finite() && (max - min).is_finite();
+    if !finite || min.partial_cmp(&max) != Some(Ordering::Less) {
+        return min;
+    }
+
+    let guid = env.context.lock().unwrap().guid;
+    let mut random = env.random.lock().unwrap();
+    let stream = match guid {
+        Some(guid) => random.stream(&format!("script {:x}", guid.0)),
+        None => random.stream("script"),
+    };
+    stream.gen_range(min..max)
+}
+
+fn start_timer(env: &HostEnv, seconds: f32, repeating: u32) -> u64 {
+    request_timer(env, Delay::Seconds(seconds), repeating != 0)
+}
+
+fn start_timer_frames(env: &HostEnv, frames: u32, repeating: u32) -> u64 {
+    request_timer(env, Delay::Frames(frames as u64), repeating != 0)
+}
+
+fn request_timer(env: &HostEnv, delay: Delay, repeating: bool) -> u64 {
+    let id = TimerId::next();
+    let entity = env.context.lock().unwrap().entity;
+    env.timer_requests
+        .lock()
+        .unwrap()
+        .push(TimerRequest::Start {
+            id,
+            entity,
+            delay,
+            repeating,
+        });
+    id.0
+}
+
+fn cancel_timer(env: &HostEnv, timer: u64) {
+    let request = TimerRequest::Cancel(TimerId(timer));
+    env.timer_requests.lock().unwrap().push(request);
+}
+
+/// Returns 1 if the entity the handle refers to is still alive, otherwise 0.
+fn entity_valid(env: &HostEnv, entity: u64) -> u32 {
+    env.entities.lock().unwrap().contains_key(&Guid(entity)) as u32
+}
+
+/// Returns 0 if the entity the handle refers to has been despawned.
+fn get_entity_position_x(env: &HostEnv, entity: u64) -> f32 {
+    entity_position(env, entity).x
+}
+
+fn get_entity_position_y(env: &HostEnv, entity: u64) -> f32 {
+    entity_position(env, entity).y
+}
+
+fn entity_position(env: &HostEnv, entity: u64) -> Vec2 {
+    let entities = env.entities.lock().unwrap();
+    entities.get(&Guid(entity)).copied().unwrap_or_default()
+}
+
+fn bit(bits: u32, index: u32) -> u32 {
+    match index {
+        0..=31 => (bits >> index) & 1,
+        _ => 0,
+    }
+}
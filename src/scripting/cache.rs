@@ -0,0 +1,88 @@
+use crate::error::Error;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasmer::{Module, Store};
+
+/// Where compiled scripts are kept between runs of the engine.
+pub(super) const DIRECTORY: &str = "alpha_script_cache";
+
+/// Compiles each distinct module once, however many scripts share it, keyed by a hash of its
+/// contents. Compiled modules are also written to disk, so they don't need compiling again the
+/// next time the engine starts.
+pub(super) struct ModuleCache {
+    directory: PathBuf,
+    /// Anything besides the module's contents which changes the compiled code.
+    salt: String,
+    modules: HashMap<blake3::Hash, Module>,
+}
+
+impl ModuleCache {
+    /// The fuel limit is compiled into each module, so modules compiled with a different limit
+    /// aren't reused.
+    pub fn new<P: AsRef<Path>>(directory: P, fuel: u64) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            salt: format!("wasmer {} fuel {}", wasmer::VERSION, fuel),
+            modules: HashMap::new(),
+        }
+    }
+
+    pub fn compile(&mut self, store: &Store, path: &Path, bytes: &[u8]) -> Result<Module, Error> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(bytes);
+        let hash = hasher.finalize();
+
+        if let Some(module) = self.modules.get(&hash) {
+            return Ok(module.clone());
+        }
+
+        let artifact = self.directory.join(format!("{}.bin", hash.to_hex()));
+        let module = match self.deserialize(store, &artifact) {
+            Some(module) => {
+                info!("loaded compiled script {}", path.display());
+                module
+            }
+            None => {
+                let module = Module::new(store, bytes)
+                    .map_err(|e| Error::ScriptCompile(path.to_path_buf(), e))?;
+                info!("compiled script {}", path.display());
+
+                let saved = fs::create_dir_all(&self.directory)
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| {
+                        module
+                            .serialize_to_file(&artifact)
+                            .map_err(|e| e.to_string())
+                    });
+                if let Err(e) = saved {
+                    warn!("unable to cache compiled script {}: {}", path.display(), e);
+                }
+
+                module
+            }
+        };
+
+        self.modules.insert(hash, module.clone());
+
+        Ok(module)
+    }
+
+    fn deserialize(&self, store: &Store, artifact: &Path) -> Option<Module> {
+        if !artifact.exists() {
+            return None;
+        }
+
+        // Safety: the cache only holds artifacts written by `compile`, with this version of
+        // wasmer. Anything which fails to load is compiled again and overwritten.
+        match unsafe { Module::deserialize_from_file(store, artifact) } {
+            Ok(module) => Some(module),
+            Err(e) => {
+                warn!("discarding cached script {}: {}", artifact.display(), e);
+                None
+            }
+        }
+    }
+}
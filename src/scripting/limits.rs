@@ -0,0 +1,93 @@
+use std::ptr::NonNull;
+use std::sync::Arc;
+use wasmer::vm::{
+    self, MemoryError, MemoryStyle, TableStyle, VMMemoryDefinition, VMTableDefinition,
+};
+use wasmer::{BaseTunables, MemoryType, Pages, TableType, Tunables};
+
+/// Caps how large a script's linear memory can grow, on top of the default tunables.
+///
+/// Memories which declare a smaller maximum keep it. Memories which declare a larger one, or none
+/// at all, have it lowered to the limit, so `memory.grow` fails rather than exhausting the host.
+pub(super) struct LimitingTunables {
+    base: BaseTunables,
+    limit: Pages,
+}
+
+impl LimitingTunables {
+    pub fn new(base: BaseTunables, limit: Pages) -> Self {
+        Self { base, limit }
+    }
+
+    fn adjust_memory(&self, requested: &MemoryType) -> MemoryType {
+        let mut adjusted = *requested;
+        if requested
+            .maximum
+            .map_or(true, |maximum| maximum > self.limit)
+        {
+            adjusted.maximum = Some(self.limit);
+        }
+
+        adjusted
+    }
+
+    fn validate_memory(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.limit {
+            return Err(MemoryError::Generic(format!(
+                "script needs {} pages of memory, more than the limit of {}",
+                ty.minimum.0, self.limit.0
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Tunables for LimitingTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(&self.adjust_memory(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base.create_host_memory(&adjusted, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn vm::Memory>, MemoryError> {
+        let adjusted = self.adjust_memory(ty);
+        self.validate_memory(&adjusted)?;
+        self.base
+            .create_vm_memory(&adjusted, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
@@ -0,0 +1,311 @@
+use super::{
+    cancel_timer, emit_event, entity_valid, find_path, get_entity_position_x,
+    get_entity_position_y, get_frame, get_input, get_input_pressed, get_mouse_button, get_mouse_x,
+    get_mouse_y, get_parameter_bool, get_parameter_color, get_parameter_float, get_parameter_int,
+    get_path_x, get_path_y, get_position_x, get_position_y, get_rotation, get_time, get_time_scale,
+    play_animation, play_sound, quit, random, random_range, save_get, save_has, save_read,
+    save_set, save_write, set_animation_clip, set_color, set_position, set_rotation,
+    set_time_scale, start_preset, start_timer, start_timer_frames, stop_animation, tween, Callback,
+    HostEnv,
+};
+use crate::animation::AnimationPreset;
+use crate::components::ScriptParameter;
+use crate::error::Error;
+use crate::project::ScriptLimits;
+use glam::Vec4;
+use log::warn;
+use mlua::{FromLuaMulti, Function, HookTriggers, Lua, Table, ToLuaMulti, Value};
+use std::cell::Cell;
+use std::path::Path;
+use wasmer::WASM_PAGE_SIZE;
+
+/// Runs the script's top level, after giving it the host functions in a global `alpha` table.
+pub(super) fn instantiate(
+    source: &str,
+    path: &Path,
+    host: &HostEnv,
+    limits: ScriptLimits,
+) -> Result<Lua, Error> {
+    let lua = Lua::new();
+
+    lua.set_memory_limit(limits.memory_pages as usize * WASM_PAGE_SIZE)
+        .and_then(|_| register(&lua, host))
+        .and_then(|_| limit_fuel(&lua, limits))
+        .and_then(|_| lua.load(source).exec())
+        .map_err(|e| Error::ScriptLua(path.to_path_buf(), e))?;
+
+    Ok(lua)
+}
+
+/// Lua counts fuel in VM instructions rather than WebAssembly ones, checking every thousand.
+fn limit_fuel(lua: &Lua, limits: ScriptLimits) -> mlua::Result<()> {
+    const INSTRUCTIONS: u32 = 1000;
+
+    let used = Cell::new(0);
+    let triggers = HookTriggers {
+        every_nth_instruction: Some(INSTRUCTIONS),
+        ..Default::default()
+    };
+
+    lua.set_hook(triggers, move |_, _| {
+        used.set(used.get() + INSTRUCTIONS as u64);
+        if used.get() > limits.fuel {
+            return Err(mlua::Error::RuntimeError(String::from("ran out of fuel")));
+        }
+
+        Ok(())
+    })
+}
+
+/// Runs the script's top level in a throwaway state to find the parameters declared in its global
+/// `parameters` table. Integers become int parameters, other numbers float parameters, and tables
+/// of four numbers color parameters.
+pub(super) fn declared_parameters(
+    source: &str,
+    path: &Path,
+    host: &HostEnv,
+    limits: ScriptLimits,
+) -> Result<Vec<(String, ScriptParameter)>, Error> {
+    let lua = instantiate(source, path, host, limits)?;
+    let parameters: Option<Table> = lua
+        .globals()
+        .get("parameters")
+        .map_err(|e| Error::ScriptLua(path.to_path_buf(), e))?;
+
+    let mut declared = Vec::new();
+    for pair in parameters
+        .into_iter()
+        .flat_map(|t| t.pairs::<String, Value>())
+    {
+        let (name, value) = pair.map_err(|e| Error::ScriptLua(path.to_path_buf(), e))?;
+        match parameter(value) {
+            Some(parameter) => declared.push((name, parameter)),
+            None => warn!("invalid script parameter '{}' in {}", name, path.display()),
+        }
+    }
+
+    // Lua doesn't keep the order the table was written in.
+    declared.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(declared)
+}
+
+/// Calls the global function for the callback, if the script defines it, with the entity's
+/// parameters in the global `parameters` table.
+pub(super) fn call(
+    lua: &Lua,
+    limits: ScriptLimits,
+    id: u64,
+    callback: &Callback,
+    parameters: &[(String, ScriptParameter)],
+) -> mlua::Result<()> {
+    // Setting the hook again gives the callback a full tank of fuel.
+    limit_fuel(lua, limits)?;
+    set_parameters(lua, parameters)?;
+
+    // Entity handles are passed as Lua integers, which are signed, so they don't lose precision.
+    let id = id as i64;
+    let globals = lua.globals();
+    match callback {
+        Callback::Start => call_global(&globals, "on_start", id),
+        Callback::Update(dt) => call_global(&globals, "on_update", (id, *dt)),
+        Callback::Destroy => call_global(&globals, "on_destroy", id),
+        Callback::CollisionStart(other) => {
+            call_global(&globals, "on_collision_start", (id, *other as i64))
+        }
+        Callback::CollisionEnd(other) => {
+            call_global(&globals, "on_collision_end", (id, *other as i64))
+        }
+        Callback::Timer(timer) => call_global(&globals, "on_timer", (id, *timer)),
+    }
+}
+
+fn call_global<'lua, A: ToLuaMulti<'lua>>(
+    globals: &Table<'lua>,
+    name: &str,
+    args: A,
+) -> mlua::Result<()> {
+    match globals.get::<_, Option<Function>>(name)? {
+        Some(function) => function.call(args),
+        None => Ok(()),
+    }
+}
+
+fn parameter(value: Value) -> Option<ScriptParameter> {
+    match value {
+        Value::Integer(value) => Some(ScriptParameter::Int(value as i32)),
+        Value::Number(value) => Some(ScriptParameter::Float(value as f32)),
+        Value::Boolean(value) => Some(ScriptParameter::Bool(value)),
+        Value::Table(table) => {
+            let rgba = table
+                .sequence_values::<f32>()
+                .collect::<mlua::Result<Vec<_>>>()
+                .ok()?;
+            match rgba.as_slice() {
+                [r, g, b, a] => Some(ScriptParameter::Color(Vec4::new(*r, *g, *b, *a))),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn set_parameters(lua: &Lua, parameters: &[(String, ScriptParameter)]) -> mlua::Result<()> {
+    let table = lua.create_table()?;
+    for (name, parameter) in parameters {
+        let name = name.as_str();
+        match parameter {
+            ScriptParameter::Float(value) => table.set(name, *value)?,
+            ScriptParameter::Int(value) => table.set(name, *value)?,
+            ScriptParameter::Bool(value) => table.set(name, *value)?,
+            ScriptParameter::Color(color) => table.set(name, color.to_array().to_vec())?,
+        }
+    }
+
+    lua.globals().set("parameters", table)
+}
+
+fn register(lua: &Lua, host: &HostEnv) -> mlua::Result<()> {
+    let alpha = lua.create_table()?;
+
+    set(lua, &alpha, host, "get_position_x", |env, ()| {
+        get_position_x(env)
+    })?;
+    set(lua, &alpha, host, "get_position_y", |env, ()| {
+        get_position_y(env)
+    })?;
+    set(lua, &alpha, host, "set_position", |env, (x, y)| {
+        set_position(env, x, y)
+    })?;
+    set(lua, &alpha, host, "get_rotation", |env, ()| {
+        get_rotation(env)
+    })?;
+    set(lua, &alpha, host, "set_rotation", set_rotation)?;
+    set(lua, &alpha, host, "set_color", |env, (r, g, b, a)| {
+        set_color(env, r, g, b, a)
+    })?;
+    set(lua, &alpha, host, "get_input", get_input)?;
+    set(lua, &alpha, host, "get_input_pressed", get_input_pressed)?;
+    set(lua, &alpha, host, "get_mouse_x", |env, ()| get_mouse_x(env))?;
+    set(lua, &alpha, host, "get_mouse_y", |env, ()| get_mouse_y(env))?;
+    set(lua, &alpha, host, "get_mouse_button", get_mouse_button)?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "get_parameter_float",
+        get_parameter_float,
+    )?;
+    set(lua, &alpha, host, "get_parameter_int", get_parameter_int)?;
+    set(lua, &alpha, host, "get_parameter_bool", get_parameter_bool)?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "get_parameter_color",
+        |env, (index, channel)| get_parameter_color(env, index, channel),
+    )?;
+    set(lua, &alpha, host, "emit_event", emit_event)?;
+    set(lua, &alpha, host, "get_time", |env, ()| get_time(env))?;
+    set(lua, &alpha, host, "get_frame", |env, ()| get_frame(env))?;
+    set(lua, &alpha, host, "get_time_scale", |env, ()| {
+        get_time_scale(env)
+    })?;
+    set(lua, &alpha, host, "set_time_scale", set_time_scale)?;
+    set(lua, &alpha, host, "quit", |env, ()| quit(env))?;
+    set(lua, &alpha, host, "play_sound", play_sound)?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "tween",
+        |env, (property, x, y, z, w, seconds, easing)| {
+            tween(env, property, x, y, z, w, seconds, easing)
+        },
+    )?;
+
+    // Lua scripts give presets by name rather than by index.
+    set(lua, &alpha, host, "play_preset", |env, name: String| {
+        AnimationPreset::parse(&name).map_or(0, |preset| start_preset(env, preset))
+    })?;
+    set(lua, &alpha, host, "play_animation", |env, ()| {
+        play_animation(env)
+    })?;
+    set(lua, &alpha, host, "stop_animation", |env, ()| {
+        stop_animation(env)
+    })?;
+    set(lua, &alpha, host, "set_animation_clip", set_animation_clip)?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "find_path",
+        |env, (from_x, from_y, to_x, to_y)| find_path(env, from_x, from_y, to_x, to_y),
+    )?;
+    set(lua, &alpha, host, "get_path_x", get_path_x)?;
+    set(lua, &alpha, host, "get_path_y", get_path_y)?;
+    set(lua, &alpha, host, "save_set", |env, (key, value)| {
+        save_set(env, key, value)
+    })?;
+    set(lua, &alpha, host, "save_get", save_get)?;
+    set(lua, &alpha, host, "save_has", save_has)?;
+    set(lua, &alpha, host, "save_write", save_write)?;
+    set(lua, &alpha, host, "save_read", save_read)?;
+    set(lua, &alpha, host, "random", |env, ()| random(env))?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "start_timer",
+        |env, (seconds, repeating)| start_timer(env, seconds, repeating),
+    )?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "start_timer_frames",
+        |env, (frames, repeating)| start_timer_frames(env, frames, repeating),
+    )?;
+    set(lua, &alpha, host, "cancel_timer", cancel_timer)?;
+    set(lua, &alpha, host, "random_range", |env, (min, max)| {
+        random_range(env, min, max)
+    })?;
+    set(lua, &alpha, host, "entity_valid", |env, entity: i64| {
+        entity_valid(env, entity as u64)
+    })?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "get_entity_position_x",
+        |env, entity: i64| get_entity_position_x(env, entity as u64),
+    )?;
+    set(
+        lua,
+        &alpha,
+        host,
+        "get_entity_position_y",
+        |env, entity: i64| get_entity_position_y(env, entity as u64),
+    )?;
+
+    lua.globals().set("alpha", alpha)
+}
+
+fn set<A, R, F>(
+    lua: &Lua,
+    table: &Table,
+    host: &HostEnv,
+    name: &str,
+    function: F,
+) -> mlua::Result<()>
+where
+    A: for<'lua> FromLuaMulti<'lua>,
+    R: for<'lua> ToLuaMulti<'lua>,
+    F: Fn(&HostEnv, A) -> R + 'static,
+{
+    let env = host.clone();
+    table.set(
+        name,
+        lua.create_function(move |_, args| Ok(function(&env, args)))?,
+    )
+}
@@ -0,0 +1,140 @@
+use crate::assets::{Assets, Handle, LoadState, SceneFile};
+use crate::hierarchy;
+use crate::registry::ComponentRegistry;
+use crate::scene::SceneDescription;
+use hecs::{Entity, World};
+use log::{info, warn};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Spawns the entities of another scene file as children of the entity, e.g. a room shared by
+/// several levels. They're spawned again whenever the file changes, and aren't saved with the
+/// scene the entity is in.
+#[derive(Clone, Debug, Default)]
+pub struct SubScene {
+    pub scene: Handle<SceneFile>,
+}
+
+/// Marks an entity spawned from a [`SubScene`], so it's left out when the world is saved.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SubSceneEntity;
+
+/// The scene a [`SubScene`]'s entities were spawned from, and the roots of what was spawned.
+struct Spawned {
+    scene: PathBuf,
+    roots: Vec<Entity>,
+}
+
+/// Spawns the entities of [`SubScene`]s once their scene files have loaded, and again in place of
+/// the old ones whenever a file is reloaded.
+pub(crate) struct SubScenes {
+    /// The scene files reloaded since the last update.
+    reloaded: Rc<RefCell<HashSet<PathBuf>>>,
+}
+
+impl SubScenes {
+    pub(crate) fn new(assets: &mut Assets) -> Self {
+        let reloaded = Rc::new(RefCell::new(HashSet::new()));
+        let hook = reloaded.clone();
+        assets.on_reload::<SceneFile>(move |path, _| {
+            hook.borrow_mut().insert(path.to_path_buf());
+        });
+
+        Self { reloaded }
+    }
+
+    pub(crate) fn update(
+        &mut self,
+        world: &mut World,
+        assets: &Assets,
+        registry: &ComponentRegistry,
+    ) {
+        let reloaded = std::mem::take(&mut *self.reloaded.borrow_mut());
+
+        // Entities whose scene has changed, or which no longer have one, lose what was spawned.
+        let stale: Vec<(Entity, Vec<Entity>)> = world
+            .query::<(&Spawned, Option<&SubScene>)>()
+            .iter()
+            .filter(|(_, (spawned, sub_scene))| match sub_scene {
+                Some(sub_scene) => {
+                    reloaded.contains(&spawned.scene) || sub_scene.scene.path() != spawned.scene
+                }
+                None => true,
+            })
+            .map(|(entity, (spawned, _))| (entity, spawned.roots.clone()))
+            .collect();
+        for (entity, roots) in stale {
+            for root in roots {
+                hierarchy::despawn(world, root);
+            }
+            world.remove_one::<Spawned>(entity).ok();
+        }
+
+        let pending: Vec<(Entity, PathBuf)> = world
+            .query::<&SubScene>()
+            .without::<Spawned>()
+            .iter()
+            .filter(|(_, sub_scene)| assets.state(&sub_scene.scene) == LoadState::Loaded)
+            .map(|(entity, sub_scene)| (entity, sub_scene.scene.path().to_path_buf()))
+            .collect();
+        for (entity, path) in pending {
+            let roots = if is_spawned_within(world, entity, &path) {
+                warn!(
+                    "{} is a sub-scene of itself, so isn't spawned",
+                    path.display()
+                );
+                Vec::new()
+            } else {
+                spawn(world, assets, registry, entity, &path)
+            };
+            world
+                .insert_one(entity, Spawned { scene: path, roots })
+                .ok();
+        }
+    }
+}
+
+fn spawn(
+    world: &mut World,
+    assets: &Assets,
+    registry: &ComponentRegistry,
+    parent: Entity,
+    path: &Path,
+) -> Vec<Entity> {
+    let file = match assets.get(&Handle::<SceneFile>::new(path)) {
+        Some(file) => file,
+        None => return Vec::new(),
+    };
+    let mut scene = match SceneDescription::parse(&file.source) {
+        Ok(scene) => scene,
+        Err(e) => {
+            warn!("unable to spawn sub-scene {}: {}", path.display(), e);
+            return Vec::new();
+        }
+    };
+
+    // The same scene can be spawned any number of times, so each copy gets GUIDs of its own.
+    scene.regenerate_guids();
+    let roots = scene.spawn_under(world, registry, parent);
+    info!("spawned sub-scene {}", path.display());
+
+    roots
+}
+
+/// Whether `entity` was itself spawned, at any depth, from the scene at `path`, which would
+/// otherwise spawn copies of itself forever.
+fn is_spawned_within(world: &World, entity: Entity, path: &Path) -> bool {
+    let mut ancestor = hierarchy::parent(world, entity);
+    while let Some(entity) = ancestor {
+        if let Ok(spawned) = world.get::<Spawned>(entity) {
+            if spawned.scene == path {
+                return true;
+            }
+        }
+        ancestor = hierarchy::parent(world, entity);
+    }
+
+    false
+}
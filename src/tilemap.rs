@@ -0,0 +1,173 @@
+use crate::assets::{Handle, Image};
+use glam::{Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+use std::path::PathBuf;
+
+/// A grid of tiles drawn from a tileset, covering the entity's rect.
+///
+/// The tileset is a texture split into a grid of equally sized tiles, which are numbered left to
+/// right, top to bottom. Cells are numbered the same way, from the top left of the map.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tilemap {
+    pub tileset: Handle<Image>,
+    pub tileset_columns: u32,
+    pub tileset_rows: u32,
+    columns: u32,
+    rows: u32,
+    /// The tile in each cell, row by row, or `None` where there isn't one.
+    tiles: Vec<Option<u32>>,
+}
+
+impl Default for Tilemap {
+    fn default() -> Self {
+        Self::new(PathBuf::new(), 1, 1, 8, 8)
+    }
+}
+
+impl Tilemap {
+    /// The most columns or rows a map can have, which keeps maps read from scene files to a size
+    /// that can be held in memory.
+    pub const MAX_SIZE: u32 = 1024;
+
+    pub fn new<P: Into<PathBuf>>(
+        tileset: P,
+        tileset_columns: u32,
+        tileset_rows: u32,
+        columns: u32,
+        rows: u32,
+    ) -> Self {
+        Self {
+            tileset: Handle::new(tileset),
+            tileset_columns: tileset_columns.max(1),
+            tileset_rows: tileset_rows.max(1),
+            columns: columns.max(1),
+            rows: rows.max(1),
+            tiles: vec![None; (columns.max(1) * rows.max(1)) as usize],
+        }
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn tiles(&self) -> &[Option<u32>] {
+        &self.tiles
+    }
+
+    /// The number of tiles in the tileset.
+    pub fn tile_count(&self) -> u32 {
+        self.tileset_columns.max(1) * self.tileset_rows.max(1)
+    }
+
+    /// Changes the size of the map, keeping the tiles which are still inside it.
+    pub fn resize(&mut self, columns: u32, rows: u32) {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let mut tiles = vec![None; (columns * rows) as usize];
+        for row in 0..rows.min(self.rows) {
+            for column in 0..columns.min(self.columns) {
+                tiles[(row * columns + column) as usize] = self.get(column, row);
+            }
+        }
+
+        self.columns = columns;
+        self.rows = rows;
+        self.tiles = tiles;
+    }
+
+    /// Replaces every tile, returning `false` if there isn't one for each cell.
+    pub fn set_tiles(&mut self, tiles: Vec<Option<u32>>) -> bool {
+        if tiles.len() != (self.columns * self.rows) as usize {
+            return false;
+        }
+
+        self.tiles = tiles;
+        true
+    }
+
+    pub fn get(&self, column: u32, row: u32) -> Option<u32> {
+        self.index(column, row).and_then(|i| self.tiles[i])
+    }
+
+    /// Sets the tile in a cell, returning `true` if it changed.
+    pub fn set(&mut self, column: u32, row: u32, tile: Option<u32>) -> bool {
+        match self.index(column, row) {
+            Some(i) if self.tiles[i] != tile => {
+                self.tiles[i] = tile;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Sets the tile in a cell and in every cell connected to it which had the same tile, returning
+    /// `true` if any changed.
+    pub fn fill(&mut self, column: u32, row: u32, tile: Option<u32>) -> bool {
+        let target = match self.index(column, row) {
+            Some(i) if self.tiles[i] != tile => self.tiles[i],
+            _ => return false,
+        };
+
+        let mut stack = vec![(column, row)];
+        while let Some((column, row)) = stack.pop() {
+            match self.index(column, row) {
+                Some(i) if self.tiles[i] == target => self.tiles[i] = tile,
+                _ => continue,
+            }
+
+            stack.push((column + 1, row));
+            stack.push((column, row + 1));
+            if column > 0 {
+                stack.push((column - 1, row));
+            }
+            if row > 0 {
+                stack.push((column, row - 1));
+            }
+        }
+        true
+    }
+
+    /// The cell at a position in the world, given the inverse of the entity's global transform.
+    pub fn cell_at(&self, inverse: &Mat4, position: Vec2) -> Option<(u32, u32)> {
+        let local = (*inverse * Vec4::new(position.x, position.y, 0.0, 1.0)).xy();
+        if !(0.0..1.0).contains(&local.x) || !(0.0..1.0).contains(&local.y) {
+            return None;
+        }
+
+        let column = (local.x * self.columns as f32) as u32;
+        let row = ((1.0 - local.y) * self.rows as f32) as u32;
+        Some((column.min(self.columns - 1), row.min(self.rows - 1)))
+    }
+
+    /// Where a cell is drawn, given the entity's global transform.
+    pub fn cell_matrix(&self, matrix: &Mat4, column: u32, row: u32) -> Mat4 {
+        let size = Vec2::new(1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let min = Vec2::new(column as f32, (self.rows - 1 - row) as f32) * size;
+        *matrix
+            * Mat4::from_translation(Vec3::new(min.x, min.y, 0.0))
+            * Mat4::from_scale(Vec3::new(size.x, size.y, 1.0))
+    }
+
+    /// The corners of a tile in the tileset, in texture coordinates.
+    pub fn tile_uvs(&self, tile: u32) -> (Vec2, Vec2) {
+        let grid = Vec2::new(
+            self.tileset_columns.max(1) as f32,
+            self.tileset_rows.max(1) as f32,
+        );
+        let cell = Vec2::new(
+            (tile % self.tileset_columns.max(1)) as f32,
+            (tile / self.tileset_columns.max(1)) as f32,
+        );
+        (cell / grid, (cell + Vec2::ONE) / grid)
+    }
+
+    fn index(&self, column: u32, row: u32) -> Option<usize> {
+        if column < self.columns && row < self.rows {
+            Some((row * self.columns + column) as usize)
+        } else {
+            None
+        }
+    }
+}
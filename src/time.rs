@@ -0,0 +1,131 @@
+use instant::Instant;
+use std::time::Duration;
+
+/// A frame slower than this many fixed steps skips the rest, rather than falling ever further
+/// behind trying to catch up.
+const MAX_FIXED_STEPS: u32 = 5;
+
+/// How much time has passed, measured once per frame by the [`Engine`](crate::Engine) and handed
+/// to [`Application::on_update`](crate::engine::Application::on_update).
+#[derive(Clone, Copy, Debug)]
+pub struct Time {
+    started: Instant,
+    last_frame: Instant,
+    delta: Duration,
+    unscaled_delta: Duration,
+    elapsed: Duration,
+    scale: f32,
+    frame: u64,
+    /// Time which has passed but hasn't been used up by fixed updates yet.
+    accumulator: Duration,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last_frame: now,
+            delta: Duration::ZERO,
+            unscaled_delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            scale: 1.0,
+            frame: 0,
+            accumulator: Duration::ZERO,
+        }
+    }
+}
+
+impl Time {
+    /// How far apart fixed updates are, which is 60 times a second.
+    pub const FIXED_DELTA: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    /// Starts a new frame.
+    pub(crate) fn tick(&mut self) {
+        let now = Instant::now();
+        let delta = if self.frame > 0 {
+            now - self.last_frame
+        } else {
+            Duration::ZERO
+        };
+        self.last_frame = now;
+        self.advance(delta);
+        self.elapsed = now - self.started;
+    }
+
+    /// Leaves out the time since the last frame, so that time spent paused isn't counted as one
+    /// long frame.
+    pub(crate) fn resume(&mut self) {
+        let now = Instant::now();
+        self.started += now - self.last_frame;
+        self.last_frame = now;
+    }
+
+    /// Starts a new frame `delta` after the last, however much time has really passed, so that
+    /// headless runs are the same every time.
+    pub(crate) fn advance(&mut self, delta: Duration) {
+        self.unscaled_delta = delta;
+        self.delta = delta.mul_f32(self.scale);
+        self.elapsed += delta;
+        self.frame += 1;
+
+        self.accumulator = (self.accumulator + self.delta).min(Self::FIXED_DELTA * MAX_FIXED_STEPS);
+    }
+
+    /// Uses up a fixed step's worth of time, if enough has passed, returning `false` once the
+    /// fixed updates have caught up with the frame.
+    pub(crate) fn next_fixed_step(&mut self) -> bool {
+        if self.accumulator >= Self::FIXED_DELTA {
+            self.accumulator -= Self::FIXED_DELTA;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// How long the previous frame took, multiplied by the [`Time::scale`]. Zero on the first
+    /// frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// How long the previous frame really took, whatever the [`Time::scale`].
+    pub fn unscaled_delta(&self) -> Duration {
+        self.unscaled_delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn fixed_delta_seconds(&self) -> f32 {
+        Self::FIXED_DELTA.as_secs_f32()
+    }
+
+    /// How fast game time passes compared to real time, where 0.5 is half speed for slow motion
+    /// and 0 pauses.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Sets the [`Time::scale`] from the next frame on. Negative scales are treated as 0.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.0);
+    }
+
+    /// How far the frame is between the last fixed update and the next, from 0 to 1, for
+    /// smoothing out things which only move in fixed updates.
+    pub fn fixed_alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / Self::FIXED_DELTA.as_secs_f32()
+    }
+
+    /// How long the engine has been running, not counting any time it spent paused.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The number of the current frame, counting from 1.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
@@ -0,0 +1,176 @@
+use crate::events::{Events, TimerFired};
+use hecs::{Entity, World};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Timers are numbered across every [`Timers`], so that scripts can be given the id of a timer
+/// before it's added.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerId(pub u64);
+
+impl TimerId {
+    pub(crate) fn next() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// How long a timer waits before it fires.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delay {
+    /// Game time, which is slowed down and sped up by [`Time::scale`](crate::Time::scale).
+    Seconds(f32),
+    /// Updates of the game, whatever the time between them.
+    Frames(u64),
+}
+
+struct Timer {
+    id: TimerId,
+    /// The entity the timer belongs to, which it's stopped with when the entity is despawned.
+    entity: Option<Entity>,
+    delay: Delay,
+    repeating: bool,
+    /// The time or number of frames left before the timer fires.
+    seconds_left: f32,
+    frames_left: u64,
+}
+
+/// Timers requested by scripts, which are applied to [`Timers`] once the scripts have run.
+pub(crate) enum TimerRequest {
+    Start {
+        id: TimerId,
+        entity: Option<Entity>,
+        delay: Delay,
+        repeating: bool,
+    },
+    Cancel(TimerId),
+}
+
+/// Publishes a [`TimerFired`] event when each timer runs out, so systems don't each need to add
+/// up the time themselves. Entities' scripts are also called back with `on_timer` for the timers
+/// which belong to them.
+#[derive(Default)]
+pub struct Timers {
+    timers: Vec<Timer>,
+}
+
+impl Timers {
+    /// Fires once, after the delay.
+    pub fn after(&mut self, delay: Delay) -> TimerId {
+        self.start(TimerId::next(), None, delay, false)
+    }
+
+    /// Fires every time the delay passes, until it's cancelled.
+    pub fn every(&mut self, delay: Delay) -> TimerId {
+        self.start(TimerId::next(), None, delay, true)
+    }
+
+    /// As [`Timers::after`], for a timer which belongs to `entity`.
+    pub fn after_for(&mut self, entity: Entity, delay: Delay) -> TimerId {
+        self.start(TimerId::next(), Some(entity), delay, false)
+    }
+
+    /// As [`Timers::every`], for a timer which belongs to `entity`.
+    pub fn every_for(&mut self, entity: Entity, delay: Delay) -> TimerId {
+        self.start(TimerId::next(), Some(entity), delay, true)
+    }
+
+    /// Stops the timer, returning `true` if it hadn't already stopped.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let count = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        self.timers.len() != count
+    }
+
+    pub fn is_running(&self, id: TimerId) -> bool {
+        self.timers.iter().any(|timer| timer.id == id)
+    }
+
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    pub(crate) fn apply(&mut self, request: TimerRequest) {
+        match request {
+            TimerRequest::Start {
+                id,
+                entity,
+                delay,
+                repeating,
+            } => {
+                self.start(id, entity, delay, repeating);
+            }
+            TimerRequest::Cancel(id) => {
+                self.cancel(id);
+            }
+        }
+    }
+
+    fn start(
+        &mut self,
+        id: TimerId,
+        entity: Option<Entity>,
+        delay: Delay,
+        repeating: bool,
+    ) -> TimerId {
+        let (seconds_left, frames_left) = match delay {
+            Delay::Seconds(seconds) => (seconds, 0),
+            Delay::Frames(frames) => (0.0, frames),
+        };
+        self.timers.push(Timer {
+            id,
+            entity,
+            delay,
+            repeating,
+            seconds_left,
+            frames_left,
+        });
+        id
+    }
+
+    /// Counts down each timer by a frame of `dt` seconds, publishing an event for each which
+    /// fires.
+    pub(crate) fn update(&mut self, world: &World, events: &mut Events, dt: f32) {
+        let timers = std::mem::take(&mut self.timers);
+        for mut timer in timers {
+            if timer.entity.map_or(false, |entity| !world.contains(entity)) {
+                continue;
+            }
+
+            let fired = match timer.delay {
+                Delay::Seconds(seconds) => {
+                    timer.seconds_left -= dt;
+                    let mut fired = 0;
+                    while timer.seconds_left <= 0.0 && (fired == 0 || timer.repeating) {
+                        fired += 1;
+                        // A timer with no delay would otherwise fire forever.
+                        if seconds <= 0.0 {
+                            break;
+                        }
+                        timer.seconds_left += seconds;
+                    }
+                    fired
+                }
+                Delay::Frames(frames) => {
+                    timer.frames_left = timer.frames_left.saturating_sub(1);
+                    if timer.frames_left == 0 {
+                        timer.frames_left = frames;
+                        1
+                    } else {
+                        0
+                    }
+                }
+            };
+
+            for _ in 0..fired {
+                events.publish(TimerFired {
+                    id: timer.id,
+                    entity: timer.entity,
+                });
+            }
+            if fired == 0 || timer.repeating {
+                self.timers.push(timer);
+            }
+        }
+    }
+}
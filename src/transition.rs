@@ -0,0 +1,155 @@
+use crate::components::Sprite;
+use crate::error::Error;
+use crate::renderer::rect::Rect;
+use crate::renderer::{RenderContext, Renderer};
+use glam::{Mat4, Vec3, Vec4};
+use std::path::{Path, PathBuf};
+
+/// The texture the last frame of the old scene is kept in during a crossfade.
+const SNAPSHOT: &str = "<scene transition>";
+
+/// How a game changes from one scene to the next with [`Game::transition_to`](crate::Game::transition_to).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    /// Fades the old scene out to the colour, switches scene and fades the new one in from it.
+    Fade(Vec4),
+    /// Switches scene straight away and fades the last frame of the old scene out over the new one.
+    Crossfade,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    /// Fading the old scene out.
+    Out,
+    /// Waiting for the last frame of the old scene to be drawn into the snapshot.
+    Capture,
+    /// Waiting for the game to switch scene.
+    Switch,
+    /// Fading the new scene in.
+    In,
+}
+
+/// A change of scene in progress, which the game switches scene part way through, rather than
+/// swapping one world for the other from one frame to the next.
+pub struct SceneTransition {
+    transition: Transition,
+    scene: PathBuf,
+    /// How long the fade out and fade in take altogether.
+    duration: f32,
+    elapsed: f32,
+    phase: Phase,
+}
+
+impl SceneTransition {
+    pub(crate) fn new<P: Into<PathBuf>>(scene: P, transition: Transition, duration: f32) -> Self {
+        let phase = match transition {
+            Transition::Fade(_) => Phase::Out,
+            Transition::Crossfade => Phase::Capture,
+        };
+
+        Self {
+            transition,
+            scene: scene.into(),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            phase,
+        }
+    }
+
+    pub fn transition(&self) -> Transition {
+        self.transition
+    }
+
+    /// The scene being switched to.
+    pub fn scene(&self) -> &Path {
+        &self.scene
+    }
+
+    /// Whether the game has switched to the new scene yet.
+    pub fn has_switched(&self) -> bool {
+        self.phase == Phase::In
+    }
+
+    /// Moves the transition on by `dt` seconds, returning `true` once it's time to switch scene.
+    pub(crate) fn update(&mut self, dt: f32) -> bool {
+        match self.phase {
+            Phase::Out => {
+                self.elapsed += dt;
+                if self.elapsed >= self.duration / 2.0 {
+                    self.phase = Phase::Switch;
+                }
+            }
+            Phase::In => self.elapsed += dt,
+            Phase::Capture | Phase::Switch => {}
+        }
+
+        self.phase == Phase::Switch
+    }
+
+    /// Starts fading in, once the game has switched scene.
+    pub(crate) fn switched(&mut self) {
+        self.phase = Phase::In;
+        self.elapsed = match self.transition {
+            Transition::Fade(_) => self.duration / 2.0,
+            Transition::Crossfade => 0.0,
+        };
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.phase == Phase::In && self.elapsed >= self.duration
+    }
+
+    /// Draws the last frame of the old scene into the snapshot, if it's needed and hasn't been
+    /// drawn yet. `draw` draws the frame into whatever the renderer is rendering to.
+    pub(crate) fn capture(
+        &mut self,
+        renderer: &mut Renderer,
+        draw: impl FnOnce(&mut Renderer) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if self.phase != Phase::Capture {
+            return Ok(());
+        }
+
+        let snapshot = renderer.insert_render_target(Path::new(SNAPSHOT));
+        let target = renderer.output_texture.take();
+        renderer.render_to_texture(Some(snapshot));
+        let drawn = draw(renderer);
+        renderer.render_to_texture(target);
+        self.phase = Phase::Switch;
+
+        drawn
+    }
+
+    /// Covers the frame with the fade colour or the snapshot of the old scene, over the game's UI.
+    pub(crate) fn draw(&self, renderer: &mut Renderer, ctx: &mut RenderContext) {
+        let alpha = match (self.transition, self.phase) {
+            (_, Phase::Capture) => return,
+            (Transition::Crossfade, _) if self.duration <= 0.0 => return,
+            (Transition::Crossfade, _) => 1.0 - self.elapsed / self.duration,
+            (Transition::Fade(_), _) if self.duration <= 0.0 => 1.0,
+            (Transition::Fade(_), _) => 1.0 - (self.elapsed / self.duration * 2.0 - 1.0).abs(),
+        };
+        let alpha = alpha.clamp(0.0, 1.0);
+
+        let size = Vec3::new(renderer.width as f32, renderer.height as f32, 1.0);
+        let matrix = Mat4::from_scale(size);
+        let mut scene = renderer.begin_overlay();
+        match self.transition {
+            Transition::Fade(color) => {
+                let color = Vec4::new(color.x, color.y, color.z, color.w * alpha);
+                renderer.draw_rect(&mut scene, &Rect::new(matrix, color));
+            }
+            Transition::Crossfade => {
+                let mut snapshot = Sprite::new(SNAPSHOT);
+                snapshot.tint = Vec4::new(1.0, 1.0, 1.0, alpha);
+                renderer.draw_sprite(&mut scene, &matrix, &snapshot);
+            }
+        }
+        renderer.end_scene(scene, ctx);
+    }
+
+    /// Lets go of the snapshot, once the transition has finished with it.
+    pub(crate) fn release(renderer: &mut Renderer) {
+        renderer.forget_texture(Path::new(SNAPSHOT));
+    }
+}
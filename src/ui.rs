@@ -0,0 +1,445 @@
+use crate::assets::{Assets, Handle, Image, LoadState};
+use crate::components::Sprite;
+use crate::renderer::rect::Rect;
+use crate::renderer::{RenderContext, Renderer};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use winit::event::VirtualKeyCode;
+use winit_input_helper::WinitInputHelper;
+
+const BUTTON_COLOR: Vec4 = Vec4::new(0.2, 0.2, 0.25, 0.9);
+const BUTTON_HOVERED_COLOR: Vec4 = Vec4::new(0.3, 0.3, 0.38, 0.9);
+const BUTTON_PRESSED_COLOR: Vec4 = Vec4::new(0.15, 0.15, 0.18, 0.9);
+const TEXT_COLOR: Vec4 = Vec4::ONE;
+
+/// The point of the screen, and of a widget, which the widget is positioned from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Centre,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far across and down the screen, or the widget, the anchor is.
+    fn fraction(self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => Vec2::new(0.0, 0.0),
+            Anchor::Top => Vec2::new(0.5, 0.0),
+            Anchor::TopRight => Vec2::new(1.0, 0.0),
+            Anchor::Left => Vec2::new(0.0, 0.5),
+            Anchor::Centre => Vec2::new(0.5, 0.5),
+            Anchor::Right => Vec2::new(1.0, 0.5),
+            Anchor::BottomLeft => Vec2::new(0.0, 1.0),
+            Anchor::Bottom => Vec2::new(0.5, 1.0),
+            Anchor::BottomRight => Vec2::new(1.0, 1.0),
+        }
+    }
+}
+
+/// Where a widget goes on the screen, in pixels. The widget's anchor is put `offset` pixels right
+/// of and below the screen's anchor, so e.g. a [`Anchor::BottomRight`] widget with a negative
+/// offset stays in the bottom right corner however the window is resized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UiRect {
+    pub anchor: Anchor,
+    pub offset: Vec2,
+    pub size: Vec2,
+}
+
+impl UiRect {
+    pub fn new(anchor: Anchor, offset: Vec2, size: Vec2) -> Self {
+        Self {
+            anchor,
+            offset,
+            size,
+        }
+    }
+
+    /// The top left corner of the widget, from the top left of a screen of the given size.
+    fn min(&self, screen: Vec2) -> Vec2 {
+        let fraction = self.anchor.fraction();
+        fraction * screen + self.offset - fraction * self.size
+    }
+
+    fn contains(&self, screen: Vec2, point: Vec2) -> bool {
+        let min = self.min(screen);
+        let max = min + self.size;
+        point.x >= min.x && point.x < max.x && point.y >= min.y && point.y < max.y
+    }
+}
+
+/// A texture of equally sized glyphs in a grid, for the printable ASCII characters in order from
+/// the space, left to right, top to bottom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitmapFont {
+    pub texture: Handle<Image>,
+    pub columns: u32,
+    pub rows: u32,
+    /// The size each glyph is drawn at, in pixels.
+    pub glyph_size: Vec2,
+}
+
+impl BitmapFont {
+    fn glyph_uvs(&self, c: char) -> Option<(Vec2, Vec2)> {
+        let glyph = (c as u32).checked_sub(' ' as u32)?;
+        let (columns, rows) = (self.columns.max(1), self.rows.max(1));
+        if glyph >= columns * rows {
+            return None;
+        }
+
+        let grid = Vec2::new(columns as f32, rows as f32);
+        let cell = Vec2::new((glyph % columns) as f32, (glyph / columns) as f32);
+        Some((cell / grid, (cell + Vec2::ONE) / grid))
+    }
+}
+
+enum Widget {
+    Panel(UiRect, Vec4),
+    Image(UiRect, Sprite),
+    Text(UiRect, String, Vec4),
+}
+
+/// What a widget is, for assistive technology such as screen readers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessRole {
+    Label,
+    Image,
+    Button,
+}
+
+/// A widget as assistive technology sees it. Panels, and images without alt text, are decoration
+/// and left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessNode {
+    pub role: AccessRole,
+    /// What the widget says, or for an image, what it shows.
+    pub label: String,
+    /// The widget's top left corner, in pixels from the top left of the screen.
+    pub position: Vec2,
+    pub size: Vec2,
+    /// The button's place in the order Tab moves through them, if it is one.
+    focus_order: Option<usize>,
+}
+
+/// The game UI's widgets as assistive technology sees them, in the order they were added. Nodes
+/// are identified by their index, which stays the same from frame to frame as long as the game
+/// adds the same widgets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessTree {
+    pub nodes: Vec<AccessNode>,
+    /// The node which has the keyboard focus.
+    pub focus: Option<usize>,
+}
+
+/// Something assistive technology has asked the game UI to do, to the node with the given index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessAction {
+    Focus(usize),
+    /// Clicks the button.
+    Activate(usize),
+}
+
+/// Connects the game UI to the platform's assistive technology, as an AccessKit platform adapter
+/// does, e.g. by passing the tree on to `accesskit_winit`.
+pub trait AccessibilityAdapter {
+    /// Called at the end of each frame in which the tree has changed.
+    fn update(&mut self, tree: &AccessTree);
+
+    /// Takes the actions assistive technology has asked for since this was last called.
+    fn take_actions(&mut self) -> Vec<AccessAction> {
+        Vec::new()
+    }
+}
+
+/// The game's own user interface, e.g. its HUD and menus, drawn in screen space over the world by
+/// the engine's renderer rather than by the editor's egui.
+///
+/// It's immediate mode: widgets are added each frame by the systems which want them, from the
+/// [`Stage::Update`](crate::Stage::Update) or [`Stage::PostUpdate`](crate::Stage::PostUpdate)
+/// stages, and drawn until the next frame's widgets replace them. Text is drawn over panels and
+/// images, whatever order they're added in.
+///
+/// Buttons can be used from the keyboard too: Tab and Shift+Tab move the focus between them, in
+/// the order they're added, and Enter or Space clicks the focused one. Labels, buttons and images
+/// with alt text are described to assistive technology by an [`AccessibilityAdapter`].
+#[derive(Default)]
+pub struct GameUi {
+    /// The font labels and buttons are written in. Without one, text isn't drawn.
+    pub font: Option<BitmapFont>,
+    widgets: Vec<Widget>,
+    screen: Vec2,
+    mouse: Option<Vec2>,
+    mouse_pressed: bool,
+    mouse_held: bool,
+    /// Whether the mouse is over any widget added this frame.
+    hovered: bool,
+    access: AccessTree,
+    /// The tree last given to the adapter.
+    published: AccessTree,
+    adapter: Option<Box<dyn AccessibilityAdapter>>,
+    /// The place in the focus order of the button with the keyboard focus.
+    focus: Option<usize>,
+    /// Whether the focused button is clicked this frame.
+    activate: bool,
+}
+
+impl GameUi {
+    /// Forgets last frame's widgets and catches up with the mouse.
+    pub(crate) fn begin_frame(&mut self, input: &WinitInputHelper, screen: Vec2) {
+        let buttons = self.button_count();
+        let actions = match &mut self.adapter {
+            Some(adapter) => adapter.take_actions(),
+            None => Vec::new(),
+        };
+
+        self.widgets.clear();
+        self.screen = screen;
+        self.mouse = input.mouse().map(|(x, y)| Vec2::new(x, y));
+        self.mouse_pressed = input.mouse_pressed(0);
+        self.mouse_held = input.mouse_held(0);
+        self.hovered = false;
+        self.activate =
+            input.key_pressed(VirtualKeyCode::Return) || input.key_pressed(VirtualKeyCode::Space);
+
+        if input.key_pressed(VirtualKeyCode::Tab) && buttons > 0 {
+            let backwards = input.held_shift();
+            self.focus = Some(match (self.focus, backwards) {
+                (None, false) => 0,
+                (None, true) => buttons - 1,
+                (Some(focus), false) => (focus + 1) % buttons,
+                (Some(focus), true) => (focus + buttons - 1) % buttons,
+            });
+        }
+
+        // Actions refer to last frame's nodes, which the game is about to add again.
+        for action in actions {
+            let (node, activate) = match action {
+                AccessAction::Focus(node) => (node, false),
+                AccessAction::Activate(node) => (node, true),
+            };
+            if let Some(order) = self.access.nodes.get(node).and_then(|n| n.focus_order) {
+                self.focus = Some(order);
+                self.activate |= activate;
+            }
+        }
+        self.access.nodes.clear();
+        self.access.focus = None;
+    }
+
+    /// Gives the adapter the frame's widgets, if they've changed.
+    pub(crate) fn end_frame(&mut self) {
+        if self
+            .focus
+            .map_or(false, |focus| focus >= self.button_count())
+        {
+            self.focus = None;
+        }
+
+        if let Some(adapter) = &mut self.adapter {
+            if self.access != self.published {
+                adapter.update(&self.access);
+                self.published = self.access.clone();
+            }
+        }
+    }
+
+    /// Starts loading the textures the frame's images and text are drawn with.
+    pub(crate) fn track_assets(&mut self, assets: &mut Assets) {
+        if let Some(font) = &mut self.font {
+            assets.track(&mut font.texture);
+        }
+        for widget in &mut self.widgets {
+            if let Widget::Image(_, sprite) = widget {
+                assets.track(&mut sprite.texture);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.widgets.clear();
+        self.hovered = false;
+        self.access = AccessTree::default();
+        self.focus = None;
+        self.activate = false;
+    }
+
+    /// Describes the game's widgets to assistive technology through `adapter` from now on.
+    pub fn set_accessibility_adapter<A: AccessibilityAdapter + 'static>(&mut self, adapter: A) {
+        self.adapter = Some(Box::new(adapter));
+        self.published = AccessTree::default();
+    }
+
+    /// The widgets added so far this frame, as assistive technology sees them.
+    pub fn access_tree(&self) -> &AccessTree {
+        &self.access
+    }
+
+    /// The size of the screen, in pixels.
+    pub fn screen_size(&self) -> Vec2 {
+        self.screen
+    }
+
+    /// Whether the mouse is over a widget, so that clicks shouldn't also go to the world.
+    pub fn wants_mouse(&self) -> bool {
+        self.hovered
+    }
+
+    pub fn panel(&mut self, rect: UiRect, color: Vec4) {
+        self.track_hover(&rect);
+        self.widgets.push(Widget::Panel(rect, color));
+    }
+
+    /// Draws a sprite stretched over the rect.
+    pub fn image(&mut self, rect: UiRect, sprite: Sprite) {
+        self.track_hover(&rect);
+        self.widgets.push(Widget::Image(rect, sprite));
+    }
+
+    /// As [`GameUi::image`], describing what the image shows to assistive technology.
+    pub fn image_with_alt(&mut self, rect: UiRect, sprite: Sprite, alt: &str) {
+        self.image(rect, sprite);
+        self.add_node(AccessRole::Image, alt, &rect, None);
+    }
+
+    /// Writes a line of text, starting from the top left of the rect.
+    pub fn label(&mut self, rect: UiRect, text: &str, color: Vec4) {
+        self.add_node(AccessRole::Label, text, &rect, None);
+        self.widgets
+            .push(Widget::Text(rect, text.to_string(), color));
+    }
+
+    /// Returns `true` on the frame the button is clicked, or pressed from the keyboard while it
+    /// has the focus.
+    pub fn button(&mut self, rect: UiRect, text: &str) -> bool {
+        let order = self.button_count();
+        let focused = self.focus == Some(order);
+        self.add_node(AccessRole::Button, text, &rect, Some(order));
+
+        let hovered = self.track_hover(&rect);
+        let color = match (hovered, self.mouse_held, focused) {
+            (true, true, _) => BUTTON_PRESSED_COLOR,
+            (true, false, _) | (false, _, true) => BUTTON_HOVERED_COLOR,
+            (false, _, false) => BUTTON_COLOR,
+        };
+        self.widgets.push(Widget::Panel(rect, color));
+
+        // The text is centred on the button.
+        let text_size = self.text_size(text);
+        let text_rect = UiRect {
+            offset: rect.min(self.screen) + (rect.size - text_size) / 2.0,
+            anchor: Anchor::TopLeft,
+            size: text_size,
+        };
+        self.widgets
+            .push(Widget::Text(text_rect, text.to_string(), TEXT_COLOR));
+
+        (hovered && self.mouse_pressed) || (focused && self.activate)
+    }
+
+    /// The size of a line of text in the font, or zero without one.
+    pub fn text_size(&self, text: &str) -> Vec2 {
+        match &self.font {
+            Some(font) => font.glyph_size * Vec2::new(text.chars().count() as f32, 1.0),
+            None => Vec2::ZERO,
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        role: AccessRole,
+        label: &str,
+        rect: &UiRect,
+        focus_order: Option<usize>,
+    ) {
+        if focus_order.is_some() && focus_order == self.focus {
+            self.access.focus = Some(self.access.nodes.len());
+        }
+        self.access.nodes.push(AccessNode {
+            role,
+            label: label.to_string(),
+            position: rect.min(self.screen),
+            size: rect.size,
+            focus_order,
+        });
+    }
+
+    /// How many buttons have been added so far this frame.
+    fn button_count(&self) -> usize {
+        self.access
+            .nodes
+            .iter()
+            .filter(|node| node.focus_order.is_some())
+            .count()
+    }
+
+    fn track_hover(&mut self, rect: &UiRect) -> bool {
+        let hovered = self
+            .mouse
+            .map_or(false, |mouse| rect.contains(self.screen, mouse));
+        self.hovered |= hovered;
+        hovered
+    }
+
+    /// Draws the widgets over the frame being rendered.
+    pub(crate) fn draw(&self, renderer: &mut Renderer, assets: &Assets, ctx: &mut RenderContext) {
+        if self.widgets.is_empty() {
+            return;
+        }
+
+        let screen = Vec2::new(renderer.width as f32, renderer.height as f32);
+        let mut scene = renderer.begin_overlay();
+        for widget in &self.widgets {
+            match widget {
+                Widget::Panel(rect, color) => {
+                    let matrix = matrix(screen, rect.min(screen), rect.size);
+                    renderer.draw_rect(&mut scene, &Rect::new(matrix, *color));
+                }
+                Widget::Image(rect, sprite) => {
+                    if assets.state(&sprite.texture) != LoadState::Loaded {
+                        continue;
+                    }
+
+                    let matrix = matrix(screen, rect.min(screen), rect.size);
+                    renderer.draw_sprite(&mut scene, &matrix, sprite);
+                }
+                Widget::Text(rect, text, color) => {
+                    let font = match &self.font {
+                        Some(font) if assets.state(&font.texture) == LoadState::Loaded => font,
+                        _ => continue,
+                    };
+
+                    let mut glyph = Sprite {
+                        texture: font.texture.clone(),
+                        ..Sprite::default()
+                    };
+                    glyph.tint = *color;
+                    let mut position = rect.min(screen);
+                    for c in text.chars() {
+                        if let Some((uv_min, uv_max)) = font.glyph_uvs(c) {
+                            glyph.uv_min = uv_min;
+                            glyph.uv_max = uv_max;
+                            let matrix = matrix(screen, position, font.glyph_size);
+                            renderer.draw_sprite(&mut scene, &matrix, &glyph);
+                        }
+                        position.x += font.glyph_size.x;
+                    }
+                }
+            }
+        }
+        renderer.end_scene(scene, ctx);
+    }
+}
+
+/// Maps the unit square onto a rect given from the top left of the screen, in the overlay's
+/// coordinates, which run from the bottom left.
+fn matrix(screen: Vec2, min: Vec2, size: Vec2) -> Mat4 {
+    let bottom_left = Vec2::new(min.x, screen.y - min.y - size.y);
+    Mat4::from_translation(Vec3::new(bottom_left.x, bottom_left.y, 0.0))
+        * Mat4::from_scale(Vec3::new(size.x, size.y, 1.0))
+}
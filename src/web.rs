@@ -0,0 +1,106 @@
+//! Running games in the browser, when built for `wasm32-unknown-unknown` with `wasm-bindgen`.
+
+use crate::engine::{CreateApplication, Engine};
+use crate::error::Error;
+use crate::files;
+use crate::project::LogSettings;
+use js_sys::Uint8Array;
+use log::{error, info, Level};
+use std::path::Path;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
+
+pub(crate) use scripting::SystemWasmer;
+
+mod scripting;
+
+/// Sends log messages, and panics, to the browser's console, as there's no terminal or log file.
+pub(crate) fn init(settings: &LogSettings) {
+    console_error_panic_hook::set_once();
+
+    // The console only has the one level, so the levels for particular modules are ignored.
+    let level = settings
+        .level
+        .split(',')
+        .find_map(|part| part.trim().parse().ok())
+        .unwrap_or(Level::Info);
+    // Fails if something else has already set up a logger, which is then used instead.
+    console_log::init_with_level(level).ok();
+}
+
+/// Starts the application in the page, drawing into a canvas added to the page's body. Returns
+/// straight away, as the browser runs the engine from then on.
+///
+/// The page is expected to be served from the directory of a game built by the editor: every file
+/// in the build's manifest is fetched before the engine starts, so the game can read its scenes,
+/// settings and assets as it would from disk.
+///
+/// Called from `main`, which `wasm-bindgen` runs once the page has loaded the module:
+///
+/// ```ignore
+/// fn main() {
+///     alpha::web::start::<alpha::Game>();
+/// }
+/// ```
+pub fn start<App>()
+where
+    App: CreateApplication + 'static,
+    App::App: 'static,
+{
+    wasm_bindgen_futures::spawn_local(async {
+        let fetched = fetch_game().await;
+        let engine = Engine::<App>::init_async().await;
+
+        // Logging is set up by the engine, from settings which were fetched with the game.
+        match fetched {
+            Ok(count) => info!("fetched {} files", count),
+            Err(e) => error!("unable to fetch the game: {}", e),
+        }
+        match engine {
+            Ok(engine) => engine.run(),
+            Err(e) => error!("unable to start the engine: {}", e),
+        }
+    });
+}
+
+/// Fetches the build's manifest, then each file it lists, returning how many there were.
+async fn fetch_game() -> Result<usize, Error> {
+    let manifest = fetch(Path::new(files::MANIFEST)).await?;
+    let manifest = String::from_utf8_lossy(&manifest);
+
+    let mut count = 0;
+    for line in manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let path = Path::new(line);
+        files::insert(path, fetch(path).await?);
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Fetches a file from the server, relative to the page.
+async fn fetch(path: &Path) -> Result<Vec<u8>, Error> {
+    let failed = |e: JsValue| Error::Fetch(path.to_path_buf(), format!("{:?}", e));
+
+    let page = web_sys::window()
+        .ok_or_else(|| Error::Fetch(path.to_path_buf(), String::from("no window")))?;
+    let response: Response = JsFuture::from(page.fetch_with_str(&path.to_string_lossy()))
+        .await
+        .map_err(failed)?
+        .dyn_into()
+        .map_err(failed)?;
+    if !response.ok() {
+        let status = format!("{} {}", response.status(), response.status_text());
+        return Err(Error::Fetch(path.to_path_buf(), status));
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(failed)?)
+        .await
+        .map_err(failed)?;
+
+    Ok(Uint8Array::new(&buffer).to_vec())
+}
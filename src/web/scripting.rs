@@ -0,0 +1,96 @@
+use crate::audio::SoundHandle;
+use crate::components::Script;
+use crate::events::{Events, ScriptEvent};
+use crate::navigation::NavGrid;
+use crate::project::ScriptLimits;
+use crate::renderer::camera::Camera;
+use crate::save::SaveData;
+use crate::time::Time;
+use crate::timers::TimerRequest;
+use hecs::{Entity, World};
+use log::warn;
+use std::sync::Arc;
+use winit_input_helper::WinitInputHelper;
+
+/// Stands in for the desktop's `SystemWasmer` in the browser, where wasmer can't run, so games
+/// without scripts run the same. Scripted entities are left alone, with a warning the first time
+/// one is found.
+pub struct SystemWasmer {
+    time_scale: f32,
+    warned: bool,
+}
+
+impl SystemWasmer {
+    pub fn new(_limits: ScriptLimits) -> Self {
+        Self {
+            time_scale: 1.0,
+            warned: false,
+        }
+    }
+
+    pub fn instantiate_all(&mut self, world: &World) {
+        self.warn_if_scripted(world);
+    }
+
+    pub fn take_events(&mut self) -> Vec<ScriptEvent> {
+        Vec::new()
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    pub(crate) fn take_timer_requests(&mut self) -> Vec<TimerRequest> {
+        Vec::new()
+    }
+
+    pub fn take_sound_requests(&mut self) -> Vec<SoundHandle> {
+        Vec::new()
+    }
+
+    pub fn take_quit_request(&mut self) -> bool {
+        false
+    }
+
+    pub fn reseed(&mut self, _seed: u64) {}
+
+    pub fn set_save_data(&mut self, _data: SaveData) {}
+
+    pub fn fault(&self, _entity: Entity) -> Option<&str> {
+        None
+    }
+
+    pub fn run(
+        &mut self,
+        world: &mut World,
+        _input: &WinitInputHelper,
+        _camera: &Camera,
+        time: &Time,
+        _events: &Events,
+        _navigation: Arc<NavGrid>,
+    ) {
+        self.time_scale = time.scale();
+        self.warn_if_scripted(world);
+    }
+
+    pub fn stop(&mut self, _world: &mut World) {
+        self.time_scale = 1.0;
+    }
+
+    pub fn maintain(&mut self, _world: &mut World) {}
+
+    fn warn_if_scripted(&mut self, world: &World) {
+        if self.warned {
+            return;
+        }
+
+        let scripted = world
+            .query::<&Script>()
+            .iter()
+            .any(|(_, script)| !script.filepath.as_os_str().is_empty());
+        if scripted {
+            warn!("scripts aren't run in the browser");
+            self.warned = true;
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use alpha::Scenario;
+
+#[test]
+fn falling_body() {
+    let scenario = Scenario::from_file("tests/scenarios/falling_body.scenario").unwrap();
+
+    scenario.run().unwrap();
+}